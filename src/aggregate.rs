@@ -0,0 +1,104 @@
+// --- Weighted Aggregation ---
+// Combining several prices into one: the Binance P2P rate from its top ads,
+// and derived symbols defined as a weighted blend of other sources.
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Weighting {
+    // The best-priced ad only; the historical behaviour.
+    #[default]
+    First,
+    // Plain average of the ads.
+    Mean,
+    // Weighted by how much each ad still has available, so a tiny ad priced
+    // far off the market moves the rate less than a deep one.
+    Volume,
+}
+
+// Mean of `values` weighted by their paired weight. Entries with a weight of
+// zero or less (or NaN) are left out; None if nothing is left.
+pub fn weighted_mean(values: &[(f64, f64)]) -> Option<f64> {
+    let (sum, total_weight) = values
+        .iter()
+        .filter(|(value, weight)| value.is_finite() && *weight > 0.0)
+        .fold((0.0, 0.0), |(sum, total), (value, weight)| {
+            (sum + value * weight, total + weight)
+        });
+    (total_weight > 0.0).then(|| sum / total_weight)
+}
+
+// One price from a list of (price, available volume) ads, best first.
+pub fn ads_price(ads: &[(f64, f64)], weighting: Weighting) -> Option<f64> {
+    match weighting {
+        Weighting::First => ads.first().map(|(price, _)| *price),
+        Weighting::Mean => weighted_mean(
+            &ads.iter()
+                .map(|(price, _)| (*price, 1.0))
+                .collect::<Vec<_>>(),
+        ),
+        // Ads without a reported volume fall back to counting once each.
+        Weighting::Volume => weighted_mean(ads).or_else(|| ads_price(ads, Weighting::Mean)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn weighted_mean_uses_weights() {
+        let mean = weighted_mean(&[(100.0, 3.0), (200.0, 1.0)]).unwrap();
+        assert_eq!(mean, 125.0);
+    }
+
+    #[test]
+    fn weighted_mean_skips_non_positive_weights() {
+        let mean = weighted_mean(&[(100.0, 1.0), (500.0, 0.0), (900.0, -2.0)]).unwrap();
+        assert_eq!(mean, 100.0);
+    }
+
+    #[test]
+    fn weighted_mean_of_nothing_is_none() {
+        assert_eq!(weighted_mean(&[]), None);
+        assert_eq!(weighted_mean(&[(100.0, 0.0)]), None);
+    }
+
+    #[test]
+    fn weighted_mean_ignores_nan_prices() {
+        let mean = weighted_mean(&[(f64::NAN, 1.0), (50.0, 1.0)]).unwrap();
+        assert_eq!(mean, 50.0);
+    }
+
+    #[test]
+    fn first_takes_best_ad() {
+        let ads = [(203.5, 10.0), (205.0, 5000.0)];
+        assert_eq!(ads_price(&ads, Weighting::First), Some(203.5));
+    }
+
+    #[test]
+    fn mean_ignores_volume() {
+        let ads = [(200.0, 10.0), (210.0, 5000.0)];
+        assert_eq!(ads_price(&ads, Weighting::Mean), Some(205.0));
+    }
+
+    #[test]
+    fn volume_favours_deep_ads() {
+        // A thin outlier barely moves the result.
+        let ads = [(150.0, 1.0), (200.0, 99.0)];
+        let price = ads_price(&ads, Weighting::Volume).unwrap();
+        assert!((price - 199.5).abs() < 1e-9, "got {}", price);
+    }
+
+    #[test]
+    fn volume_without_volumes_falls_back_to_mean() {
+        let ads = [(200.0, 0.0), (210.0, 0.0)];
+        assert_eq!(ads_price(&ads, Weighting::Volume), Some(205.0));
+    }
+
+    #[test]
+    fn no_ads_is_none() {
+        assert_eq!(ads_price(&[], Weighting::First), None);
+        assert_eq!(ads_price(&[], Weighting::Volume), None);
+    }
+}
@@ -0,0 +1,190 @@
+// --- Alerts ---
+// `[[alerts]]` rules checked after every update cycle. A rule notifies once when
+// its condition starts to hold and re-arms after the condition clears by at
+// least its `hysteresis`, so a rate hovering at the threshold notifies once.
+// `change_pct` rules compare the latest rate with the one stored
+// `window_minutes` earlier, to catch sudden moves either way.
+use chrono::{Duration, Utc};
+use rusqlite::Connection;
+use std::sync::Mutex;
+use tracing::{info, warn};
+
+use crate::{
+    config::AlertConfig,
+    db,
+    i18n::{self, Msg},
+    notify,
+};
+
+#[derive(Clone, Copy)]
+enum Direction {
+    Above,
+    Below,
+}
+
+pub struct AlertEngine {
+    rules: Vec<AlertConfig>,
+    // Whether each rule's above, below and change conditions are currently
+    // triggered.
+    triggered: Mutex<Vec<[bool; 3]>>,
+}
+
+impl AlertEngine {
+    pub fn new(rules: Vec<AlertConfig>) -> Self {
+        for rule in rules
+            .iter()
+            .filter(|r| r.above.is_none() && r.below.is_none() && r.change_pct.is_none())
+        {
+            warn!(
+                "Alert for '{}' has no threshold and will never fire",
+                rule.symbol
+            );
+        }
+        let triggered = Mutex::new(vec![[false; 3]; rules.len()]);
+        AlertEngine { rules, triggered }
+    }
+
+    pub fn evaluate(&self, conn: &Connection) {
+        let mut triggered = self.triggered.lock().unwrap_or_else(|p| p.into_inner());
+        for (rule, state) in self.rules.iter().zip(triggered.iter_mut()) {
+            let rate = match db::latest_rate(conn, &rule.symbol) {
+                Ok(Some(rate)) => rate,
+                Ok(None) => continue,
+                Err(e) => {
+                    warn!("Alert for '{}' skipped: {}", rule.symbol, e);
+                    continue;
+                }
+            };
+            let checks = [
+                (Direction::Above, rule.above, Msg::AlertAbove),
+                (Direction::Below, rule.below, Msg::AlertBelow),
+            ];
+            let [above_below @ .., moved] = state;
+            for ((direction, threshold, title), was_triggered) in
+                checks.into_iter().zip(above_below)
+            {
+                let Some(threshold) = threshold else {
+                    continue;
+                };
+                let (now_triggered, fire) =
+                    step(*was_triggered, direction, rate, threshold, rule.hysteresis);
+                if fire {
+                    info!("Alert: {} crossed {} ({})", rule.symbol, threshold, rate);
+                    notify::notify(
+                        &i18n::trf(title, &[&rule.symbol.to_uppercase(), &threshold]),
+                        &i18n::trf(Msg::AlertNow, &[&format!("{:.2}", rate)]),
+                    );
+                }
+                *was_triggered = now_triggered;
+            }
+            if let Some(change_pct) = rule.change_pct {
+                self.evaluate_change(conn, rule, rate, change_pct, moved);
+            }
+        }
+    }
+
+    fn evaluate_change(
+        &self,
+        conn: &Connection,
+        rule: &AlertConfig,
+        rate: f64,
+        change_pct: f64,
+        was_triggered: &mut bool,
+    ) {
+        let since = Utc::now() - Duration::minutes(rule.window_minutes.into());
+        let earlier = match db::rate_at(conn, &rule.symbol, since) {
+            Ok(Some(earlier)) if earlier > 0.0 => earlier,
+            Ok(_) => return,
+            Err(e) => {
+                warn!("Alert for '{}' skipped: {}", rule.symbol, e);
+                return;
+            }
+        };
+        let change = (rate / earlier - 1.0) * 100.0;
+        let (now_triggered, fire) = step(
+            *was_triggered,
+            Direction::Above,
+            change.abs(),
+            change_pct,
+            0.0,
+        );
+        if fire {
+            info!(
+                "Alert: {} moved {:+.2}% in {} min",
+                rule.symbol, change, rule.window_minutes
+            );
+            notify::notify(
+                &i18n::trf(
+                    Msg::AlertMoved,
+                    &[
+                        &rule.symbol.to_uppercase(),
+                        &format!("{:+.2}", change),
+                        &rule.window_minutes,
+                    ],
+                ),
+                &i18n::trf(Msg::AlertNow, &[&format!("{:.2}", rate)]),
+            );
+        }
+        *was_triggered = now_triggered;
+    }
+}
+
+// The new triggered state and whether to notify. A triggered condition stays
+// so until the rate is back on the other side of the threshold by more than
+// `hysteresis`.
+fn step(
+    triggered: bool,
+    direction: Direction,
+    rate: f64,
+    threshold: f64,
+    hysteresis: f64,
+) -> (bool, bool) {
+    let band = hysteresis.max(0.0);
+    let (holds, cleared) = match direction {
+        Direction::Above => (rate > threshold, rate < threshold - band),
+        Direction::Below => (rate < threshold, rate > threshold + band),
+    };
+    match (triggered, holds) {
+        (false, true) => (true, true),
+        (true, _) if cleared => (false, false),
+        (true, _) => (true, false),
+        (false, false) => (false, false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Notifications sent while the rate walks through `rates`.
+    fn fired(direction: Direction, rates: &[f64], threshold: f64, hysteresis: f64) -> usize {
+        let mut triggered = false;
+        let mut count = 0;
+        for rate in rates {
+            let (now, fire) = step(triggered, direction, *rate, threshold, hysteresis);
+            triggered = now;
+            count += usize::from(fire);
+        }
+        count
+    }
+
+    #[test]
+    fn hovering_at_the_threshold_notifies_once() {
+        let rates = [99.0, 101.0, 99.5, 100.5, 99.8, 101.0];
+        assert_eq!(fired(Direction::Above, &rates, 100.0, 1.0), 1);
+        // Without a band every dip re-arms the rule.
+        assert_eq!(fired(Direction::Above, &rates, 100.0, 0.0), 3);
+    }
+
+    #[test]
+    fn rearms_once_past_the_band() {
+        let rates = [101.0, 98.5, 101.0];
+        assert_eq!(fired(Direction::Above, &rates, 100.0, 1.0), 2);
+    }
+
+    #[test]
+    fn below_mirrors_above() {
+        let rates = [36.0, 34.9, 35.2, 34.8, 36.5, 34.0];
+        assert_eq!(fired(Direction::Below, &rates, 35.0, 1.0), 2);
+    }
+}
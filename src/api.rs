@@ -0,0 +1,713 @@
+// --- Local HTTP API ---
+// Opt-in via `[api] enabled = true`. Serves the current rates as JSON on
+// `GET /rates` and pushes the same document over a WebSocket on `/ws`
+// whenever an update cycle changes any of them, so dashboards and overlays
+// don't have to poll. `/overlay` is a page for OBS browser sources.
+//
+// Stream Deck plugins get a flat string map instead (`/deck`, or
+// `/ws?format=deck`) and can trigger an update with `POST /refresh`.
+// `/metrics` has the rates for Prometheus. Deliberately tiny: one thread per
+// connection, up to MAX_CONNECTIONS at once, no keep-alive.
+//
+// It binds to localhost unless `[api] bind` says otherwise. With a token set
+// (keyring `api-token`, or `[api] token`) every request must carry it, as
+// `Authorization: Bearer <token>` or, for browser sources and WebSockets that
+// can't set headers, `?token=<token>`. No CORS headers are sent, so other
+// web pages can't read the rates through the visitor's browser.
+use base64::{engine::general_purpose::STANDARD, Engine};
+use rusqlite::Connection;
+use serde_json::json;
+use sha1::{Digest, Sha1};
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+use tracing::{debug, info, warn};
+
+use crate::{
+    config::Config,
+    db, derived, fiat,
+    health::{HealthRegistry, ProviderHealth},
+    metrics, providers,
+    secrets::{self, SecretName},
+    timefmt,
+};
+
+const MAX_HEADER_BYTES: usize = 8 * 1024;
+// Open connections, WebSockets included; more are turned away with a 503.
+const MAX_CONNECTIONS: usize = 32;
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+// RFC 6455 magic value for the Sec-WebSocket-Accept header.
+const WS_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+const OVERLAY_PAGE: &str = "overlay.html";
+// A held or mashed deck key must not turn into a burst of provider requests.
+pub const REFRESH_COOLDOWN: Duration = Duration::from_secs(15);
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Full,
+    Deck,
+}
+
+struct Shared {
+    db: Arc<Mutex<Connection>>,
+    config: Arc<Config>,
+    health: Arc<HealthRegistry>,
+    clients: Mutex<Vec<(TcpStream, Format)>>,
+    // Symbols and rates last pushed; timestamps alone changing sends nothing.
+    last_published: Mutex<String>,
+    refresh: Mutex<Box<dyn Fn() + Send>>,
+    last_refresh: Mutex<Option<Instant>>,
+    token: Option<String>,
+    connections: AtomicUsize,
+}
+
+static SHARED: OnceLock<Shared> = OnceLock::new();
+
+// Holds one of the MAX_CONNECTIONS slots until the connection's thread ends.
+struct ConnectionSlot(&'static Shared);
+
+impl ConnectionSlot {
+    fn take(shared: &'static Shared) -> Option<Self> {
+        shared
+            .connections
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |open| {
+                (open < MAX_CONNECTIONS).then_some(open + 1)
+            })
+            .ok()
+            .map(|_| ConnectionSlot(shared))
+    }
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.connections.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+// `refresh` runs a manual update, as "Update Now" does.
+pub fn spawn(
+    db: Arc<Mutex<Connection>>,
+    config: Arc<Config>,
+    health: Arc<HealthRegistry>,
+    refresh: Box<dyn Fn() + Send>,
+) {
+    let token = api_token(&config);
+    let address = format!("{}:{}", config.api.bind, config.api.port);
+    let listener = match TcpListener::bind(&address) {
+        Ok(listener) => listener,
+        Err(e) => {
+            warn!("Could not start the API on {}: {}", address, e);
+            return;
+        }
+    };
+    info!("API listening on http://{}", address);
+    let loopback = listener.local_addr().is_ok_and(|a| a.ip().is_loopback());
+    if !loopback && token.is_none() {
+        warn!(
+            "The API is reachable beyond this machine without a token; \
+             set one with `bcv-tray secret set api-token`"
+        );
+    }
+    let shared = SHARED.get_or_init(|| Shared {
+        db,
+        config,
+        health,
+        clients: Mutex::new(Vec::new()),
+        last_published: Mutex::new(String::new()),
+        refresh: Mutex::new(refresh),
+        last_refresh: Mutex::new(None),
+        token,
+        connections: AtomicUsize::new(0),
+    });
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(mut stream) => {
+                    let Some(slot) = ConnectionSlot::take(shared) else {
+                        debug!("API connection refused: {} already open", MAX_CONNECTIONS);
+                        let _ = respond(
+                            &mut stream,
+                            "503 Service Unavailable",
+                            "text/plain",
+                            "too many connections",
+                        );
+                        continue;
+                    };
+                    thread::spawn(move || {
+                        let _slot = slot;
+                        if let Err(e) = handle_connection(shared, stream) {
+                            debug!("API connection ended: {}", e);
+                        }
+                    });
+                }
+                Err(e) => warn!("API accept failed: {}", e),
+            }
+        }
+    });
+}
+
+// The keyring is preferred over the config, as for the other secrets.
+pub fn api_token(config: &Config) -> Option<String> {
+    let stored = secrets::get(SecretName::ApiToken).unwrap_or_else(|e| {
+        warn!("{}", e);
+        None
+    });
+    stored
+        .or_else(|| Some(config.api.token.clone()))
+        .filter(|token| !token.is_empty())
+}
+
+// Called after every update cycle; pushes to WebSocket clients if anything changed.
+pub fn publish() {
+    let Some(shared) = SHARED.get() else {
+        return;
+    };
+    let document = rates_document(shared);
+    let fingerprint: String = document["rates"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|rate| format!("{}={};", rate["symbol"], rate["rate"]))
+        .collect();
+    {
+        let mut last = shared
+            .last_published
+            .lock()
+            .unwrap_or_else(|p| p.into_inner());
+        if *last == fingerprint {
+            return;
+        }
+        *last = fingerprint;
+    }
+    let full = ws_text_frame(&document.to_string());
+    let deck = ws_text_frame(&deck_document(shared, &document).to_string());
+    let mut clients = shared.clients.lock().unwrap_or_else(|p| p.into_inner());
+    clients.retain_mut(|(client, format)| {
+        let frame = match format {
+            Format::Full => &full,
+            Format::Deck => &deck,
+        };
+        client.write_all(frame).is_ok()
+    });
+    debug!("Pushed rates to {} WebSocket client(s)", clients.len());
+}
+
+// Symbol, name and unit of every tracked rate plus the configured and derived
+// ones, whether or not they are on screen.
+pub fn symbols(config: &Config) -> Vec<(String, String, String)> {
+    let mut symbols: Vec<(String, String, String)> = fiat::tracked()
+        .iter()
+        .flat_map(|f| f.sources.iter())
+        .map(|s| (s.symbol.to_string(), s.name.to_string(), s.unit.to_string()))
+        .collect();
+    let optional = providers::optional_sources();
+    symbols
+        .extend(optional.map(|s| (s.symbol.to_string(), s.name.to_string(), s.unit.to_string())));
+    if config.brecha_enabled() {
+        symbols.push((
+            derived::BRECHA_SYMBOL.to_string(),
+            "BRECHA".to_string(),
+            "%".to_string(),
+        ));
+    }
+    for source in providers::configured_sources() {
+        symbols.push((
+            source.symbol.clone(),
+            source.label.clone(),
+            source.unit.clone(),
+        ));
+    }
+    for (symbol, derived) in &config.derived {
+        let name = derived
+            .label
+            .clone()
+            .unwrap_or_else(|| symbol.to_uppercase());
+        symbols.push((symbol.clone(), name, derived.unit.clone()));
+    }
+    symbols.retain(|(symbol, _, _)| providers::enabled(symbol));
+    symbols
+}
+
+fn rates_document(shared: &Shared) -> serde_json::Value {
+    let conn = shared.db.lock().unwrap_or_else(|p| p.into_inner());
+    let rates: Vec<_> = symbols(&shared.config)
+        .into_iter()
+        .filter_map(|(symbol, name, unit)| {
+            let (rate, updated) = db::latest_quote(&conn, &symbol).ok().flatten()?;
+            Some(json!({
+                "symbol": symbol,
+                "name": name,
+                "rate": rate,
+                "unit": unit,
+                "updated": updated.map(|ts| ts.to_rfc3339()),
+                "updated_local": updated.map(timefmt::local),
+                "health": health_document(&shared.health.get(&symbol)),
+            }))
+        })
+        .collect();
+    json!({ "active_fiat": fiat::active().code, "rates": rates })
+}
+
+// Null for symbols that are never fetched (derived ones) or not tried yet.
+fn health_document(health: &ProviderHealth) -> serde_json::Value {
+    match (health.success_rate(), health.average_latency()) {
+        (Some(success_rate), Some(latency)) => json!({
+            "success_rate": success_rate,
+            "average_latency_ms": latency.as_millis() as u64,
+            "consecutive_failures": health.consecutive_failures,
+            "last_success": health.last_success.map(|ts| ts.to_rfc3339()),
+        }),
+        _ => serde_json::Value::Null,
+    }
+}
+
+// Flat string map for Stream Deck keys, which can only show a title:
+// `<symbol>` is the formatted rate, `<symbol>_title` adds the name on a line
+// above it, and `updated` is the local time of the newest rate.
+fn deck_document(shared: &Shared, document: &serde_json::Value) -> serde_json::Value {
+    let mut deck = serde_json::Map::new();
+    let mut newest = None;
+    for rate in document["rates"].as_array().into_iter().flatten() {
+        let (Some(symbol), Some(value)) = (rate["symbol"].as_str(), rate["rate"].as_f64()) else {
+            continue;
+        };
+        let unit = if rate["unit"] == "%" { "%" } else { "" };
+        let rounding = shared.config.symbol(symbol).rounding();
+        let value = format!("{}{}", rounding.format(value), unit);
+        let name = rate["name"].as_str().unwrap_or(symbol);
+        deck.insert(
+            format!("{}_title", symbol),
+            format!("{}\n{}", name, value).into(),
+        );
+        deck.insert(symbol.to_string(), value.into());
+        newest = newest.max(rate["updated_local"].as_str());
+    }
+    let updated = newest.and_then(|ts| ts.get(11..16)).unwrap_or("--");
+    deck.insert("updated".to_string(), updated.into());
+    deck.insert("active_fiat".to_string(), document["active_fiat"].clone());
+    deck.into()
+}
+
+fn refresh(shared: &Shared) -> bool {
+    let mut last = shared
+        .last_refresh
+        .lock()
+        .unwrap_or_else(|p| p.into_inner());
+    if last.is_some_and(|at| at.elapsed() < REFRESH_COOLDOWN) {
+        return false;
+    }
+    *last = Some(Instant::now());
+    info!("Update requested through the API");
+    (shared.refresh.lock().unwrap_or_else(|p| p.into_inner()))();
+    true
+}
+
+struct Request {
+    method: String,
+    path: String,
+    query: String,
+    headers: Vec<(String, String)>,
+}
+
+impl Request {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn query_param(&self, name: &str) -> Option<&str> {
+        self.query
+            .split('&')
+            .filter_map(|param| param.split_once('='))
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value)
+    }
+
+    fn authorized(&self, token: Option<&str>) -> bool {
+        let Some(token) = token else {
+            return true;
+        };
+        let offered = self
+            .header("Authorization")
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .or_else(|| self.query_param("token"));
+        offered.is_some_and(|offered| same_secret(offered.trim(), token))
+    }
+}
+
+// Compares every byte so the response time doesn't reveal how much matched.
+pub fn same_secret(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |diff, (x, y)| diff | (x ^ y))
+            == 0
+}
+
+fn read_request(reader: &mut impl BufRead) -> Result<Request, String> {
+    let mut lines = Vec::new();
+    let mut total = 0;
+    loop {
+        let mut line = String::new();
+        let read = reader
+            .read_line(&mut line)
+            .map_err(|e| format!("read failed: {}", e))?;
+        total += read;
+        if read == 0 || total > MAX_HEADER_BYTES {
+            return Err("incomplete or oversized request".to_string());
+        }
+        let line = line.trim_end().to_string();
+        if line.is_empty() {
+            break;
+        }
+        lines.push(line);
+    }
+    let mut request_line = lines.first().ok_or("empty request")?.split_whitespace();
+    let method = request_line.next().unwrap_or_default().to_string();
+    let target = request_line.next().unwrap_or_default();
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let headers = lines[1..]
+        .iter()
+        .filter_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            Some((key.trim().to_string(), value.trim().to_string()))
+        })
+        .collect();
+    Ok(Request {
+        method,
+        path: path.to_string(),
+        query: query.to_string(),
+        headers,
+    })
+}
+
+fn handle_connection(shared: &'static Shared, stream: TcpStream) -> Result<(), String> {
+    stream
+        .set_read_timeout(Some(READ_TIMEOUT))
+        .map_err(|e| e.to_string())?;
+    let mut reader = BufReader::new(stream.try_clone().map_err(|e| e.to_string())?);
+    let request = read_request(&mut reader)?;
+    let mut stream = stream;
+    debug!("API {} {}", request.method, request.path);
+    if !request.authorized(shared.token.as_deref()) {
+        return respond(
+            &mut stream,
+            "401 Unauthorized",
+            "text/plain",
+            "missing or wrong token",
+        );
+    }
+    if request.method == "OPTIONS" {
+        return respond(&mut stream, "204 No Content", "text/plain", "");
+    }
+    // Never on GET, so a link or an <img> on some page can't trigger updates.
+    if request.path == "/refresh" {
+        if request.method != "POST" {
+            return respond(
+                &mut stream,
+                "405 Method Not Allowed",
+                "text/plain",
+                "POST only",
+            );
+        }
+        return if refresh(shared) {
+            respond(
+                &mut stream,
+                "202 Accepted",
+                "application/json",
+                r#"{"status":"updating"}"#,
+            )
+        } else {
+            respond(
+                &mut stream,
+                "429 Too Many Requests",
+                "application/json",
+                r#"{"status":"too soon"}"#,
+            )
+        };
+    }
+    if request.method != "GET" {
+        return respond(
+            &mut stream,
+            "405 Method Not Allowed",
+            "text/plain",
+            "GET only",
+        );
+    }
+    let format = if request.query_param("format") == Some("deck") {
+        Format::Deck
+    } else {
+        Format::Full
+    };
+    match request.path.as_str() {
+        "/rates" => {
+            let body = rates_document(shared).to_string();
+            respond(&mut stream, "200 OK", "application/json", &body)
+        }
+        "/deck" => {
+            let body = deck_document(shared, &rates_document(shared)).to_string();
+            respond(&mut stream, "200 OK", "application/json", &body)
+        }
+        "/metrics" => {
+            let body = {
+                let conn = shared.db.lock().unwrap_or_else(|p| p.into_inner());
+                metrics::render(&conn, &shared.config, &shared.health)
+            };
+            respond(&mut stream, "200 OK", metrics::CONTENT_TYPE, &body)
+        }
+        "/ws" => serve_websocket(shared, &request, format, stream, reader),
+        "/overlay" => {
+            let page = crate::asset(OVERLAY_PAGE).ok_or("overlay page not embedded")?;
+            let body = String::from_utf8_lossy(&page);
+            respond(&mut stream, "200 OK", "text/html", &body)
+        }
+        _ => respond(&mut stream, "404 Not Found", "text/plain", "not found"),
+    }
+}
+
+fn respond(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &str,
+) -> Result<(), String> {
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}; charset=utf-8\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    );
+    stream
+        .write_all(response.as_bytes())
+        .map_err(|e| format!("write failed: {}", e))
+}
+
+// --- WebSocket ---
+fn serve_websocket(
+    shared: &'static Shared,
+    request: &Request,
+    format: Format,
+    mut stream: TcpStream,
+    mut reader: BufReader<TcpStream>,
+) -> Result<(), String> {
+    let Some(key) = request.header("Sec-WebSocket-Key") else {
+        return respond(
+            &mut stream,
+            "400 Bad Request",
+            "text/plain",
+            "expected a WebSocket upgrade",
+        );
+    };
+    let accept = ws_accept(key);
+    let handshake = format!(
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+         Sec-WebSocket-Accept: {}\r\n\r\n",
+        accept
+    );
+    stream
+        .write_all(handshake.as_bytes())
+        .map_err(|e| format!("handshake failed: {}", e))?;
+    // New clients get the current rates right away instead of waiting for a change.
+    let document = rates_document(shared);
+    let snapshot = match format {
+        Format::Full => document.to_string(),
+        Format::Deck => deck_document(shared, &document).to_string(),
+    };
+    stream
+        .write_all(&ws_text_frame(&snapshot))
+        .map_err(|e| format!("write failed: {}", e))?;
+    let writer = stream.try_clone().map_err(|e| e.to_string())?;
+    // A stalled client must not hold up `publish` for everyone else.
+    writer
+        .set_write_timeout(Some(READ_TIMEOUT))
+        .map_err(|e| e.to_string())?;
+    shared
+        .clients
+        .lock()
+        .unwrap_or_else(|p| p.into_inner())
+        .push((writer, format));
+
+    // Clients only ever send pings and the close frame; block here until it
+    // arrives or the connection drops. A failed write in `publish` removes it.
+    stream.set_read_timeout(None).map_err(|e| e.to_string())?;
+    loop {
+        match read_ws_frame(&mut reader)? {
+            (0x8, _) => {
+                let _ = stream.write_all(&[0x88, 0x00]);
+                let _ = stream.shutdown(std::net::Shutdown::Both);
+                return Ok(());
+            }
+            (0x9, payload) => {
+                let _ = stream.write_all(&ws_frame(0xA, &payload));
+            }
+            _ => {}
+        }
+    }
+}
+
+// The Sec-WebSocket-Accept value proving the handshake was understood.
+fn ws_accept(key: &str) -> String {
+    STANDARD.encode(Sha1::digest(format!("{}{}", key, WS_GUID).as_bytes()))
+}
+
+fn ws_text_frame(text: &str) -> Vec<u8> {
+    ws_frame(0x1, text.as_bytes())
+}
+
+// Server frames are never masked and never fragmented.
+fn ws_frame(opcode: u8, payload: &[u8]) -> Vec<u8> {
+    let mut frame = vec![0x80 | opcode];
+    match payload.len() {
+        len @ 0..=125 => frame.push(len as u8),
+        len @ 126..=0xFFFF => {
+            frame.push(126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    frame
+}
+
+// Returns (opcode, unmasked payload). Client frames are always masked.
+fn read_ws_frame(reader: &mut impl Read) -> Result<(u8, Vec<u8>), String> {
+    const MAX_CLIENT_PAYLOAD: u64 = 4096;
+
+    let mut read = |buf: &mut [u8]| {
+        reader
+            .read_exact(buf)
+            .map_err(|e| format!("read failed: {}", e))
+    };
+    let mut head = [0u8; 2];
+    read(&mut head)?;
+    let opcode = head[0] & 0x0F;
+    let len = match head[1] & 0x7F {
+        126 => {
+            let mut ext = [0u8; 2];
+            read(&mut ext)?;
+            u16::from_be_bytes(ext) as u64
+        }
+        127 => {
+            let mut ext = [0u8; 8];
+            read(&mut ext)?;
+            u64::from_be_bytes(ext)
+        }
+        len => len as u64,
+    };
+    if len > MAX_CLIENT_PAYLOAD {
+        return Err(format!("client frame of {} bytes is too large", len));
+    }
+    let mut mask = [0u8; 4];
+    if head[1] & 0x80 != 0 {
+        read(&mut mask)?;
+    }
+    let mut payload = vec![0u8; len as usize];
+    read(&mut payload)?;
+    for (i, byte) in payload.iter_mut().enumerate() {
+        *byte ^= mask[i % 4];
+    }
+    Ok((opcode, payload))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(query: &str, headers: &[(&str, &str)]) -> Request {
+        Request {
+            method: "GET".to_string(),
+            path: "/rates".to_string(),
+            query: query.to_string(),
+            headers: headers
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn no_token_allows_everything() {
+        assert!(request("", &[]).authorized(None));
+    }
+
+    #[test]
+    fn token_from_header_or_query() {
+        let token = Some("s3cret");
+        assert!(request("", &[("authorization", "Bearer s3cret")]).authorized(token));
+        assert!(request("format=deck&token=s3cret", &[]).authorized(token));
+        assert!(!request("", &[]).authorized(token));
+        assert!(!request("token=s3cre", &[]).authorized(token));
+        assert!(!request("", &[("Authorization", "Bearer other")]).authorized(token));
+    }
+
+    #[test]
+    fn websocket_accept_matches_rfc_6455_example() {
+        assert_eq!(
+            ws_accept("dGhlIHNhbXBsZSBub25jZQ=="),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+    }
+
+    // What a client sends: the server's framing plus the mask bit, the key
+    // and the payload XORed with it.
+    fn masked(frame: &[u8], header_len: usize, mask: [u8; 4]) -> Vec<u8> {
+        let mut out = frame[..header_len].to_vec();
+        out[1] |= 0x80;
+        out.extend_from_slice(&mask);
+        out.extend(
+            frame[header_len..]
+                .iter()
+                .enumerate()
+                .map(|(i, byte)| byte ^ mask[i % 4]),
+        );
+        out
+    }
+
+    #[test]
+    fn websocket_frames_round_trip() {
+        let short = ws_text_frame("hello");
+        assert_eq!(short, b"\x81\x05hello");
+        assert_eq!(
+            read_ws_frame(&mut short.as_slice()).unwrap(),
+            (0x1, b"hello".to_vec())
+        );
+        let masked_short = masked(&short, 2, [0x37, 0xfa, 0x21, 0x3d]);
+        // The RFC 6455 section 5.7 example of a masked "Hello".
+        assert_eq!(
+            masked(&ws_text_frame("Hello"), 2, [0x37, 0xfa, 0x21, 0x3d]),
+            [0x81, 0x85, 0x37, 0xfa, 0x21, 0x3d, 0x7f, 0x9f, 0x4d, 0x51, 0x58]
+        );
+        assert_eq!(
+            read_ws_frame(&mut masked_short.as_slice()).unwrap(),
+            (0x1, b"hello".to_vec())
+        );
+
+        let payload: Vec<u8> = (0..300u32).map(|i| i as u8).collect();
+        let long = ws_frame(0x9, &payload);
+        assert_eq!(long[..4], [0x89, 126, 0x01, 0x2c]);
+        assert_eq!(
+            read_ws_frame(&mut masked(&long, 4, [1, 2, 3, 4]).as_slice()).unwrap(),
+            (0x9, payload)
+        );
+    }
+
+    #[test]
+    fn oversized_client_frames_are_refused() {
+        let frame = ws_frame(0x1, &[b'x'; 5000]);
+        assert!(read_ws_frame(&mut frame.as_slice()).is_err());
+    }
+}
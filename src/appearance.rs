@@ -0,0 +1,68 @@
+// --- System Appearance ---
+// The menu bar on macOS switches between dark and light, and our icon is a
+// full-color bitmap (not a template image), so the text color has to follow.
+use image::Rgba;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static DARK_MODE: AtomicBool = AtomicBool::new(true);
+
+pub fn text_color() -> Rgba<u8> {
+    if DARK_MODE.load(Ordering::Relaxed) {
+        Rgba([255, 255, 255, 255])
+    } else {
+        Rgba([0, 0, 0, 255])
+    }
+}
+
+// The opposite of the text color, for outlines and solid backgrounds.
+pub fn contrast_color() -> Rgba<u8> {
+    if DARK_MODE.load(Ordering::Relaxed) {
+        Rgba([0, 0, 0, 255])
+    } else {
+        Rgba([255, 255, 255, 255])
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn query_dark_mode() -> bool {
+    // AppleInterfaceStyle only exists (as "Dark") while dark mode is active.
+    std::process::Command::new("defaults")
+        .args(["read", "-g", "AppleInterfaceStyle"])
+        .output()
+        .map(|out| {
+            out.status.success()
+                && String::from_utf8_lossy(&out.stdout)
+                    .trim()
+                    .eq_ignore_ascii_case("dark")
+        })
+        .unwrap_or(true)
+}
+
+// Polls the effective appearance and calls `on_change` after it flips.
+#[cfg(target_os = "macos")]
+pub fn spawn_watcher<F>(on_change: F)
+where
+    F: Fn() + Send + 'static,
+{
+    const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(3);
+
+    DARK_MODE.store(query_dark_mode(), Ordering::Relaxed);
+    std::thread::spawn(move || loop {
+        std::thread::sleep(POLL_INTERVAL);
+        let dark = query_dark_mode();
+        if DARK_MODE.swap(dark, Ordering::Relaxed) != dark {
+            tracing::info!(
+                "System appearance changed to {}, redrawing icon.",
+                if dark { "dark" } else { "light" }
+            );
+            on_change();
+        }
+    });
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn spawn_watcher<F>(_on_change: F)
+where
+    F: Fn() + Send + 'static,
+{
+}
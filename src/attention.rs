@@ -0,0 +1,64 @@
+// --- Attention Flash ---
+// A rate that jumps more than `[attention] threshold_pct` in a single update
+// makes the tray icon blink red a few times. Afterwards the icon shows a caret
+// next to that rate until an update brings a normal-sized move.
+use image::RgbaImage;
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use crate::config::AttentionConfig;
+
+// Time each blink phase stays on screen.
+pub const BLINK_INTERVAL: Duration = Duration::from_millis(350);
+
+const CARET_UP: char = '\u{f0d8}';
+const CARET_DOWN: char = '\u{f0d7}';
+
+// Percent change of the last update, per symbol, for moves over the threshold.
+static MOVES: Mutex<BTreeMap<String, f64>> = Mutex::new(BTreeMap::new());
+static FLASH_PENDING: AtomicBool = AtomicBool::new(false);
+
+// Called by the fetcher with the rate it replaced and the new one.
+pub fn record(config: &AttentionConfig, symbol: &str, previous: f64, rate: f64) {
+    if config.threshold_pct <= 0.0 || previous == 0.0 {
+        return;
+    }
+    let change = (rate / previous - 1.0) * 100.0;
+    let mut moves = MOVES.lock().unwrap_or_else(|p| p.into_inner());
+    if change.abs() >= config.threshold_pct {
+        tracing::info!("{} moved {:+.2}% in one update", symbol, change);
+        moves.insert(symbol.to_string(), change);
+        FLASH_PENDING.store(true, Ordering::Relaxed);
+    } else {
+        moves.remove(symbol);
+    }
+}
+
+// True once per batch of large moves, for the UI thread to start blinking.
+pub fn take_flash() -> bool {
+    FLASH_PENDING.swap(false, Ordering::Relaxed)
+}
+
+// Caret for a symbol whose last update was a large move.
+pub fn caret(symbol: &str) -> Option<char> {
+    let moves = MOVES.lock().unwrap_or_else(|p| p.into_inner());
+    let change = *moves.get(symbol)?;
+    Some(if change > 0.0 { CARET_UP } else { CARET_DOWN })
+}
+
+// Red-tinted copy of the icon; transparency is kept so the shape stays the same.
+pub fn tint(canvas: &RgbaImage) -> RgbaImage {
+    let mut tinted = canvas.clone();
+    for px in tinted.pixels_mut() {
+        let [r, g, b, a] = px.0;
+        let luma = ((r as u16 + g as u16 + b as u16) / 3) as u8;
+        px.0 = [255, luma / 3, luma / 3, a];
+    }
+    tinted
+}
@@ -0,0 +1,44 @@
+// --- Source Attribution ---
+// Every stored rate keeps a record of where it came from: the provider, the
+// URL fetched and the text the number was read from (the `audit` table, one
+// row per history row). `bcv-tray audit bcv` lists them, e.g. to show which
+// BCV publication an invoice used.
+//
+// Fetchers report the endpoint and the extracted text as they go, like
+// `fixtures::record`, instead of threading them through every return value.
+// Each update thread fetches one symbol at a time, so what was noted on this
+// thread belongs to the fetch that just finished.
+use std::cell::RefCell;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Source {
+    // Provider key as used in `[http.headers]` ("bcv", "binance", ...).
+    pub provider: String,
+    pub endpoint: String,
+    // The text or value the rate was parsed from.
+    pub raw: String,
+}
+
+thread_local! {
+    static PENDING: RefCell<Source> = RefCell::new(Source::default());
+}
+
+// The request about to be made; forgets what an earlier attempt extracted.
+pub fn endpoint(provider: &str, url: &str) {
+    PENDING.with(|pending| {
+        *pending.borrow_mut() = Source {
+            provider: provider.to_string(),
+            endpoint: url.to_string(),
+            raw: String::new(),
+        };
+    });
+}
+
+pub fn raw(text: &str) {
+    PENDING.with(|pending| pending.borrow_mut().raw = text.trim().to_string());
+}
+
+// What the last fetch on this thread noted, leaving nothing behind.
+pub fn take() -> Source {
+    PENDING.with(|pending| pending.take())
+}
@@ -0,0 +1,136 @@
+// --- Start at Login ---
+// Per-platform autostart entries pointing at the currently running executable.
+use std::path::PathBuf;
+
+const ENTRY_NAME: &str = "bcv-tray";
+
+fn current_exe() -> Result<PathBuf, String> {
+    std::env::current_exe().map_err(|e| format!("Could not locate executable: {}", e))
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn entry_path() -> Result<PathBuf, String> {
+    directories::BaseDirs::new()
+        .map(|dirs| {
+            dirs.config_dir()
+                .join("autostart")
+                .join(format!("{}.desktop", ENTRY_NAME))
+        })
+        .ok_or_else(|| "Could not determine config directory".to_string())
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn is_enabled() -> bool {
+    entry_path().map(|p| p.exists()).unwrap_or(false)
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn set_enabled(enabled: bool) -> Result<(), String> {
+    let path = entry_path()?;
+    if !enabled {
+        return remove_if_exists(&path);
+    }
+    let contents = format!(
+        "[Desktop Entry]\n\
+         Type=Application\n\
+         Name=BCV Tray\n\
+         Comment=Exchange rates in the system tray\n\
+         Exec=\"{}\"\n\
+         X-GNOME-Autostart-enabled=true\n",
+        current_exe()?.display()
+    );
+    write_entry(&path, &contents)
+}
+
+#[cfg(target_os = "macos")]
+fn entry_path() -> Result<PathBuf, String> {
+    directories::BaseDirs::new()
+        .map(|dirs| {
+            dirs.home_dir()
+                .join("Library/LaunchAgents")
+                .join(format!("com.ruvasqm.{}.plist", ENTRY_NAME))
+        })
+        .ok_or_else(|| "Could not determine home directory".to_string())
+}
+
+#[cfg(target_os = "macos")]
+pub fn is_enabled() -> bool {
+    entry_path().map(|p| p.exists()).unwrap_or(false)
+}
+
+#[cfg(target_os = "macos")]
+pub fn set_enabled(enabled: bool) -> Result<(), String> {
+    let path = entry_path()?;
+    if !enabled {
+        return remove_if_exists(&path);
+    }
+    let contents = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>com.ruvasqm.{}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>{}</string>
+    </array>
+    <key>RunAtLoad</key>
+    <true/>
+</dict>
+</plist>
+"#,
+        ENTRY_NAME,
+        current_exe()?.display()
+    );
+    write_entry(&path, &contents)
+}
+
+#[cfg(unix)]
+fn write_entry(path: &std::path::Path, contents: &str) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    std::fs::write(path, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+#[cfg(unix)]
+fn remove_if_exists(path: &std::path::Path) -> Result<(), String> {
+    match std::fs::remove_file(path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(format!("Failed to remove {}: {}", path.display(), e)),
+    }
+}
+
+#[cfg(target_os = "windows")]
+const RUN_KEY: &str = r"Software\Microsoft\Windows\CurrentVersion\Run";
+
+#[cfg(target_os = "windows")]
+pub fn is_enabled() -> bool {
+    use winreg::{enums::HKEY_CURRENT_USER, RegKey};
+    RegKey::predef(HKEY_CURRENT_USER)
+        .open_subkey(RUN_KEY)
+        .and_then(|key| key.get_value::<String, _>(ENTRY_NAME))
+        .is_ok()
+}
+
+#[cfg(target_os = "windows")]
+pub fn set_enabled(enabled: bool) -> Result<(), String> {
+    use winreg::{enums::HKEY_CURRENT_USER, RegKey};
+    let (key, _) = RegKey::predef(HKEY_CURRENT_USER)
+        .create_subkey(RUN_KEY)
+        .map_err(|e| format!("Failed to open Run registry key: {}", e))?;
+    if enabled {
+        let command = format!("\"{}\"", current_exe()?.display());
+        key.set_value(ENTRY_NAME, &command)
+            .map_err(|e| format!("Failed to write Run registry value: {}", e))
+    } else {
+        match key.delete_value(ENTRY_NAME) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(format!("Failed to remove Run registry value: {}", e)),
+        }
+    }
+}
@@ -0,0 +1,67 @@
+// --- Headless Browser ---
+// Some pages only fill in their rate with JavaScript, so the plain HTTP fetch
+// sees an empty placeholder. With the `browser` feature, `[scrape.*]` sources
+// that set `browser = true` are loaded in a headless Chromium (found on the
+// PATH or at `$CHROME`) and read once the page has settled. Builds without the
+// feature report such sources as failing instead.
+#[cfg(feature = "browser")]
+use std::time::Duration;
+
+// Time given to the page's scripts after load before reading it.
+#[cfg(feature = "browser")]
+const SETTLE_TIME: Duration = Duration::from_secs(3);
+#[cfg(feature = "browser")]
+const PAGE_TIMEOUT: Duration = Duration::from_secs(45);
+
+// The page's HTML after its scripts have run.
+#[cfg(feature = "browser")]
+pub fn render(url: &str) -> Result<String, String> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| format!("failed to start the browser runtime: {}", e))?;
+    runtime.block_on(async {
+        tokio::time::timeout(PAGE_TIMEOUT, render_page(url))
+            .await
+            .map_err(|_| format!("{} took longer than {:?} to render", url, PAGE_TIMEOUT))?
+    })
+}
+
+#[cfg(feature = "browser")]
+async fn render_page(url: &str) -> Result<String, String> {
+    use chromiumoxide::{Browser, BrowserConfig};
+    use futures::StreamExt;
+    use tracing::debug;
+
+    let config = BrowserConfig::builder()
+        .build()
+        .map_err(|e| format!("no usable Chromium: {}", e))?;
+    let (mut browser, mut handler) = Browser::launch(config)
+        .await
+        .map_err(|e| format!("failed to launch Chromium: {}", e))?;
+    let events = tokio::spawn(async move { while handler.next().await.is_some() {} });
+    debug!("Rendering {} in headless Chromium", url);
+    let html = async {
+        let page = browser
+            .new_page(url)
+            .await
+            .map_err(|e| format!("failed to load {}: {}", url, e))?;
+        page.wait_for_navigation()
+            .await
+            .map_err(|e| format!("failed to load {}: {}", url, e))?;
+        tokio::time::sleep(SETTLE_TIME).await;
+        page.content()
+            .await
+            .map_err(|e| format!("failed to read {}: {}", url, e))
+    }
+    .await;
+    let _ = browser.close().await;
+    let _ = browser.wait().await;
+    events.abort();
+    html
+}
+
+#[cfg(not(feature = "browser"))]
+pub fn render(_url: &str) -> Result<String, String> {
+    Err("built without the `browser` feature".to_string())
+}
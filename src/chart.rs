@@ -0,0 +1,195 @@
+// --- Chart Images ---
+// A symbol's stored history plotted as a PNG, for pasting into reports or
+// chats: `bcv-tray export --chart bcv --days 30 --out chart.png`, or the tray
+// menu's "Save Chart as PNG", which charts the first rate shown.
+use ab_glyph::{FontVec, PxScale};
+use chrono::{DateTime, Duration, Local, Utc};
+use image::{Rgba, RgbaImage};
+use imageproc::{
+    drawing::{draw_antialiased_line_segment_mut, draw_line_segment_mut, draw_text_mut},
+    pixelops::interpolate,
+};
+use rusqlite::Connection;
+use std::path::Path;
+
+use crate::{
+    api,
+    config::Config,
+    converter::{BACKGROUND, DIMMED, FOREGROUND},
+    db,
+    i18n::{self, Msg},
+    rounding::Rounding,
+};
+
+const WIDTH: u32 = 800;
+const HEIGHT: u32 = 400;
+const TITLE_SIZE: f32 = 22.0;
+const LABEL_SIZE: f32 = 14.0;
+// Room for the title above the plot, rate labels left of it and dates below.
+const TOP: f32 = 48.0;
+const LEFT: f32 = 90.0;
+const RIGHT: f32 = 20.0;
+const BOTTOM: f32 = 36.0;
+const GRID_LINES: u32 = 4;
+const LINE: Rgba<u8> = Rgba([90, 170, 255, 255]);
+const GRID: Rgba<u8> = Rgba([60, 60, 60, 255]);
+
+// The plot area: left, top, width and height in pixels.
+type Area = (f32, f32, f32, f32);
+
+pub fn save(
+    conn: &Connection,
+    config: &Config,
+    font: &FontVec,
+    symbol: &str,
+    days: u32,
+    path: &Path,
+) -> Result<(), String> {
+    let since = Utc::now() - Duration::days(days.into());
+    let history = db::history_since(conn, symbol, since).map_err(|e| e.to_string())?;
+    if history.len() < 2 {
+        return Err(format!(
+            "Not enough history for {} in the last {} days to draw a chart",
+            symbol, days
+        ));
+    }
+    let name = api::symbols(config)
+        .into_iter()
+        .find(|(s, _, _)| s == symbol)
+        .map(|(_, name, _)| name)
+        .unwrap_or_else(|| symbol.to_uppercase());
+    let title = i18n::trf(Msg::ChartTitle, &[&name, &days]);
+    render(font, &title, &history, &config.symbol(symbol).rounding())
+        .save(path)
+        .map_err(|e| format!("Could not write {}: {}", path.display(), e))
+}
+
+fn render(
+    font: &FontVec,
+    title: &str,
+    history: &[(DateTime<Utc>, f64)],
+    rounding: &Rounding,
+) -> RgbaImage {
+    let mut canvas = RgbaImage::from_pixel(WIDTH, HEIGHT, BACKGROUND);
+    let area = (
+        LEFT,
+        TOP,
+        WIDTH as f32 - LEFT - RIGHT,
+        HEIGHT as f32 - TOP - BOTTOM,
+    );
+    let (low, high) = bounds(history);
+    let label = PxScale::from(LABEL_SIZE);
+    draw_text_mut(
+        &mut canvas,
+        FOREGROUND,
+        12,
+        12,
+        PxScale::from(TITLE_SIZE),
+        font,
+        title,
+    );
+    for i in 0..=GRID_LINES {
+        let fraction = i as f32 / GRID_LINES as f32;
+        let y = area.1 + area.3 * (1.0 - fraction);
+        draw_line_segment_mut(&mut canvas, (area.0, y), (area.0 + area.2, y), GRID);
+        let value = low + (high - low) * fraction as f64;
+        let text_y = (y - LABEL_SIZE / 2.0) as i32;
+        draw_text_mut(
+            &mut canvas,
+            DIMMED,
+            8,
+            text_y,
+            label,
+            font,
+            &rounding.format(value),
+        );
+    }
+    let points = plot(history, (low, high), area);
+    for pair in points.windows(2) {
+        let (from, to) = (pair[0], pair[1]);
+        // Twice, a pixel apart, so the line reads at a glance.
+        for offset in [0, 1] {
+            draw_antialiased_line_segment_mut(
+                &mut canvas,
+                (from.0 as i32, from.1 as i32 + offset),
+                (to.0 as i32, to.1 as i32 + offset),
+                LINE,
+                interpolate,
+            );
+        }
+    }
+    let date = |ts: DateTime<Utc>| ts.with_timezone(&Local).format("%Y-%m-%d").to_string();
+    let date_y = (area.1 + area.3 + 10.0) as i32;
+    let (first, last) = (history[0].0, history[history.len() - 1].0);
+    draw_text_mut(
+        &mut canvas,
+        DIMMED,
+        area.0 as i32,
+        date_y,
+        label,
+        font,
+        &date(first),
+    );
+    let last_text = date(last);
+    let last_x = (area.0 + area.2) as i32 - last_text.len() as i32 * (LABEL_SIZE * 0.6) as i32;
+    draw_text_mut(&mut canvas, DIMMED, last_x, date_y, label, font, &last_text);
+    canvas
+}
+
+// Lowest and highest rate, widened a little so the line clears the frame. A
+// flat series gets a band around its value.
+fn bounds(history: &[(DateTime<Utc>, f64)]) -> (f64, f64) {
+    let low = history
+        .iter()
+        .map(|(_, r)| *r)
+        .fold(f64::INFINITY, f64::min);
+    let high = history
+        .iter()
+        .map(|(_, r)| *r)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let margin = if high > low {
+        (high - low) * 0.05
+    } else {
+        low.abs().max(1.0) * 0.01
+    };
+    (low - margin, high + margin)
+}
+
+// Pixel positions of the readings, spaced by time.
+fn plot(history: &[(DateTime<Utc>, f64)], (low, high): (f64, f64), area: Area) -> Vec<(f32, f32)> {
+    let (left, top, width, height) = area;
+    let start = history[0].0;
+    let span = (history[history.len() - 1].0 - start).num_seconds().max(1) as f32;
+    history
+        .iter()
+        .map(|(ts, rate)| {
+            let x = left + width * (*ts - start).num_seconds() as f32 / span;
+            let y = top + height * (1.0 - ((rate - low) / (high - low)) as f32);
+            (x, y)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plots_oldest_left_and_highest_on_top() {
+        let start = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let history = [
+            (start, 10.0),
+            (start + Duration::hours(1), 20.0),
+            (start + Duration::hours(4), 15.0),
+        ];
+        let points = plot(&history, (10.0, 20.0), (0.0, 0.0, 400.0, 100.0));
+        assert_eq!(points, vec![(0.0, 100.0), (100.0, 0.0), (400.0, 50.0)]);
+    }
+
+    #[test]
+    fn flat_history_still_has_a_range() {
+        let start = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let (low, high) = bounds(&[(start, 36.5), (start + Duration::days(1), 36.5)]);
+        assert!(low < 36.5 && high > 36.5);
+    }
+}
@@ -0,0 +1,401 @@
+// --- Command Line ---
+use chrono::{Duration, Utc};
+use clap::{Parser, Subcommand};
+use std::{
+    io::{self, BufRead, IsTerminal, Read},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    chart,
+    config::Config,
+    converter, db, diagnose, fiat, import, paths,
+    secrets::{self, SecretName},
+    summary, timefmt, tui, xlsx,
+};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "bcv-tray",
+    version,
+    about = "Venezuelan exchange rates in the system tray"
+)]
+pub struct Cli {
+    /// Attach to (or open) a console window so log output is visible on Windows
+    #[arg(long)]
+    pub console: bool,
+
+    /// Include full (redacted) HTTP response bodies in log output
+    #[arg(long)]
+    pub log_bodies: bool,
+
+    /// Show moving synthetic rates without touching the network (uses a separate database)
+    #[arg(long)]
+    pub demo: bool,
+
+    /// Keep rates in memory only; no database file is created or written
+    #[arg(long, conflicts_with = "db_path")]
+    pub ephemeral: bool,
+
+    /// Use this database file instead of bin.db in the data directory
+    #[arg(long, global = true, value_name = "PATH")]
+    pub db_path: Option<PathBuf>,
+
+    /// Seconds between scheduled updates [default: 1800]
+    #[arg(long, value_name = "SECS", value_parser = clap::value_parser!(u64).range(60..))]
+    pub interval: Option<u64>,
+
+    /// Fetch every rate once, print them and exit
+    #[arg(long)]
+    pub once: bool,
+
+    /// Don't show a tray icon; keep updating the database, API and other integrations
+    #[arg(long)]
+    pub no_tray: bool,
+
+    /// Use the named profile (profiles/<name>.toml in the config directory) instead of config.toml
+    #[arg(long, global = true)]
+    pub profile: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Run a self-test of providers, database, font and icon rendering
+    Diagnose,
+    /// Print the stored history of a symbol (bcv, binance, satoshi, brecha, ...)
+    History {
+        #[arg(required_unless_present = "snapshots")]
+        symbol: Option<String>,
+        /// How many days back to show
+        #[arg(long, default_value_t = 7)]
+        days: u32,
+        /// List recorded snapshots and their notes instead (only `symbol`'s rate if given)
+        #[arg(long)]
+        snapshots: bool,
+    },
+    /// Convert an amount at the current rates; "1.234,56", "1,234.56" and "1234,56" all work
+    Convert {
+        amount: String,
+        /// The amount is in the active currency (Bs, COP, ...) instead of USD
+        #[arg(long)]
+        local: bool,
+    },
+    /// Show where each stored rate of a symbol came from: provider, URL and the text it was read from
+    Audit {
+        symbol: String,
+        /// How many days back to show
+        #[arg(long, default_value_t = 7)]
+        days: u32,
+    },
+    /// Print the weekly digest (average, low, high and change per source over 7 days)
+    Digest {
+        /// Output an HTML page instead of plain text
+        #[arg(long)]
+        html: bool,
+    },
+    /// Write current rates and history to an Excel workbook, one sheet per symbol
+    Export {
+        #[arg(required_unless_present = "chart")]
+        path: Option<PathBuf>,
+        /// How many days of history to include
+        #[arg(long, default_value_t = 365)]
+        days: u32,
+        /// Draw this symbol's history as a PNG chart instead (bcv, binance, ...)
+        #[arg(long, conflicts_with = "path")]
+        chart: Option<String>,
+        /// Where to write the chart; <symbol>.png by default
+        #[arg(long, requires = "chart")]
+        out: Option<PathBuf>,
+    },
+    /// Add rate history exported by another tracker (CSV with a header row, or JSON)
+    Import {
+        path: PathBuf,
+        /// Symbol the rows belong to (bcv, binance, ...)
+        #[arg(long, required_unless_present = "symbol_column")]
+        symbol: Option<String>,
+        /// Column holding each row's symbol, for files with several rates
+        #[arg(long)]
+        symbol_column: Option<String>,
+        /// Column holding the date or time [default: date, fecha, timestamp, ...]
+        #[arg(long)]
+        time_column: Option<String>,
+        /// Column holding the rate [default: rate, tasa, price, precio, ...]
+        #[arg(long)]
+        rate_column: Option<String>,
+        /// chrono format of the time column, e.g. "%d/%m/%Y"
+        #[arg(long)]
+        time_format: Option<String>,
+        /// File format [default: from the extension]
+        #[arg(long, value_enum)]
+        format: Option<import::Format>,
+        /// CSV field separator
+        #[arg(long, default_value_t = ',')]
+        delimiter: char,
+    },
+    /// Show live rates with 24 h sparklines in the terminal (reads the database a running instance fills)
+    Tui,
+    /// Copy the rate database to a new file (safe while the tray app runs)
+    Backup { path: PathBuf },
+    /// Replace the rate database with a backup made by `backup`
+    Restore { path: PathBuf },
+    /// Manage API keys stored in the OS keyring
+    Secret {
+        #[command(subcommand)]
+        action: SecretAction,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SecretAction {
+    /// Store a secret; the value is read from stdin so it stays out of shell history
+    Set { name: SecretName },
+    /// Remove a stored secret
+    Clear { name: SecretName },
+    /// Report whether a secret is stored, without printing it
+    Status { name: SecretName },
+}
+
+// Runs a one-shot subcommand and returns the process exit code.
+pub fn run(command: Command, config: &Config) -> i32 {
+    let result = match command {
+        Command::Diagnose => diagnose::run(config),
+        Command::History {
+            symbol,
+            days,
+            snapshots: true,
+        } => run_snapshots(symbol.as_deref(), days),
+        Command::History { symbol, days, .. } => run_history(&symbol.unwrap_or_default(), days),
+        Command::Audit { symbol, days } => run_audit(&symbol, days),
+        Command::Convert { amount, local } => run_convert(config, &amount, local),
+        Command::Digest { html } => run_digest(config, html),
+        Command::Export {
+            chart: Some(symbol),
+            out,
+            days,
+            ..
+        } => run_chart(config, &symbol, out, days),
+        Command::Export { path, days, .. } => run_export(config, &path.unwrap_or_default(), days),
+        Command::Import {
+            path,
+            symbol,
+            symbol_column,
+            time_column,
+            rate_column,
+            time_format,
+            format,
+            delimiter,
+        } => {
+            let mapping = import::Mapping {
+                symbol: symbol.map(|s| s.to_lowercase()),
+                symbol_column,
+                time_column,
+                rate_column,
+                time_format,
+                delimiter,
+            };
+            run_import(&path, format, &mapping)
+        }
+        Command::Tui => tui::run(config),
+        Command::Backup { path } => run_backup(&path),
+        Command::Restore { path } => run_restore(&path),
+        Command::Secret { action } => run_secret(action),
+    };
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            1
+        }
+    }
+}
+
+fn run_history(symbol: &str, days: u32) -> Result<(), String> {
+    let conn = db::open_database(&paths::database_path()?)?;
+    let since = Utc::now() - Duration::days(days.into());
+    let history = db::history_since(&conn, symbol, since)
+        .map_err(|e| format!("Failed to read history: {}", e))?;
+    if history.is_empty() {
+        println!("No history for '{}' in the last {} days.", symbol, days);
+    }
+    for (recorded_at, rate) in history {
+        println!("{}  {:.4}", timefmt::local(recorded_at), rate);
+    }
+    Ok(())
+}
+
+fn run_audit(symbol: &str, days: u32) -> Result<(), String> {
+    let conn = db::open_database(&paths::database_path()?)?;
+    let since = Utc::now() - Duration::days(days.into());
+    let entries = db::audit_since(&conn, symbol, since)
+        .map_err(|e| format!("Failed to read history: {}", e))?;
+    if entries.is_empty() {
+        println!("No history for '{}' in the last {} days.", symbol, days);
+    }
+    for (recorded_at, rate, source) in entries {
+        if source.provider.is_empty() {
+            println!(
+                "{}  {:.4}  (no source recorded)",
+                timefmt::local(recorded_at),
+                rate
+            );
+            continue;
+        }
+        println!(
+            "{}  {:.4}  {}  {}  \"{}\"",
+            timefmt::local(recorded_at),
+            rate,
+            source.provider,
+            source.endpoint,
+            source.raw
+        );
+    }
+    Ok(())
+}
+
+fn run_snapshots(symbol: Option<&str>, days: u32) -> Result<(), String> {
+    let conn = db::open_database(&paths::database_path()?)?;
+    let since = Utc::now() - Duration::days(days.into());
+    let snapshots = db::snapshots_since(&conn, since)
+        .map_err(|e| format!("Failed to read snapshots: {}", e))?;
+    if snapshots.is_empty() {
+        println!("No snapshots in the last {} days.", days);
+    }
+    for snapshot in snapshots {
+        let rates: Vec<String> = snapshot
+            .rates
+            .iter()
+            .filter(|(s, _)| symbol.is_none_or(|symbol| symbol == s))
+            .map(|(s, rate)| format!("{} {:.4}", s, rate))
+            .collect();
+        println!(
+            "{}  {}  {}",
+            timefmt::local(snapshot.taken_at),
+            rates.join("  "),
+            snapshot.note
+        );
+    }
+    Ok(())
+}
+
+fn run_convert(config: &Config, amount: &str, from_local: bool) -> Result<(), String> {
+    let value = converter::parse_amount(amount)
+        .filter(|value| *value > 0.0)
+        .ok_or_else(|| format!("'{}' is not an amount", amount))?;
+    let conn = db::open_database(&paths::database_path()?)?;
+    let rates = crate::fetch_rates(&conn, config).map_err(|e| e.to_string())?;
+    let unit = if from_local {
+        fiat::active().unit
+    } else {
+        "USD"
+    };
+    println!("{} {}", value, unit);
+    for line in converter::conversion_lines(value, from_local, &rates, &config.converter) {
+        println!("  {}", line);
+    }
+    Ok(())
+}
+
+fn run_digest(config: &Config, html: bool) -> Result<(), String> {
+    let conn = db::open_database(&paths::database_path()?)?;
+    print!("{}", summary::weekly_digest(&conn, config, html)?);
+    Ok(())
+}
+
+fn run_export(config: &Config, path: &Path, days: u32) -> Result<(), String> {
+    let conn = db::open_database(&paths::database_path()?)?;
+    xlsx::export(&conn, config, path, days)?;
+    println!("Exported rates to {}.", path.display());
+    Ok(())
+}
+
+fn run_chart(config: &Config, symbol: &str, out: Option<PathBuf>, days: u32) -> Result<(), String> {
+    let conn = db::open_database(&paths::database_path()?)?;
+    let path = out.unwrap_or_else(|| PathBuf::from(format!("{}.png", symbol)));
+    chart::save(
+        &conn,
+        config,
+        &crate::load_font(&config.fonts)?.0,
+        symbol,
+        days,
+        &path,
+    )?;
+    println!("Saved {} chart to {}.", symbol, path.display());
+    Ok(())
+}
+
+fn run_import(
+    path: &Path,
+    format: Option<import::Format>,
+    mapping: &import::Mapping,
+) -> Result<(), String> {
+    let conn = db::open_database(&paths::database_path()?)?;
+    for (symbol, added) in import::run(&conn, path, format, mapping)? {
+        println!("{}: {} rows added", symbol, added);
+    }
+    Ok(())
+}
+
+fn run_backup(path: &Path) -> Result<(), String> {
+    let conn = db::open_database(&paths::database_path()?)?;
+    db::backup_to(&conn, path)?;
+    println!("Backed up the rate database to {}.", path.display());
+    Ok(())
+}
+
+fn run_restore(path: &Path) -> Result<(), String> {
+    let mut conn = db::open_database(&paths::database_path()?)?;
+    db::restore_from(&mut conn, path)?;
+    println!("Restored the rate database from {}.", path.display());
+    Ok(())
+}
+
+fn run_secret(action: SecretAction) -> Result<(), String> {
+    match action {
+        SecretAction::Set { name } => {
+            let mut value = String::new();
+            let stdin = io::stdin();
+            // Piped input is read whole, so multi-line keys (JSON files) work.
+            let read = if stdin.is_terminal() {
+                eprintln!("Enter value for {:?} and press Enter:", name);
+                stdin.lock().read_line(&mut value)
+            } else {
+                stdin.lock().read_to_string(&mut value)
+            };
+            read.map_err(|e| format!("Failed to read from stdin: {}", e))?;
+            let value = value.trim();
+            if value.is_empty() {
+                return Err("Empty value, nothing stored".to_string());
+            }
+            secrets::set(name, value)?;
+            println!("Stored {:?} in the OS keyring.", name);
+        }
+        SecretAction::Clear { name } => {
+            secrets::clear(name)?;
+            println!("Removed {:?} from the OS keyring.", name);
+        }
+        SecretAction::Status { name } => match secrets::get(name)? {
+            Some(_) => println!("{:?} is set.", name),
+            None => println!("{:?} is not set.", name),
+        },
+    }
+    Ok(())
+}
+
+// Release builds use the "windows" subsystem, which has no console at all.
+// Prefer the terminal we were started from, otherwise open a new one.
+#[cfg(target_os = "windows")]
+pub fn attach_console() {
+    use windows_sys::Win32::System::Console::{AllocConsole, AttachConsole, ATTACH_PARENT_PROCESS};
+    unsafe {
+        if AttachConsole(ATTACH_PARENT_PROCESS) == 0 && AllocConsole() == 0 {
+            return;
+        }
+    }
+    println!("Console attached.");
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn attach_console() {}
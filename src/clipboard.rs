@@ -0,0 +1,126 @@
+// --- Clipboard Watcher ---
+// Opt-in via `[clipboard] watch = true`. Copying something that reads as an
+// amount ("45.50 USD", "$12", "Bs 1.250,00") shows a notification with its
+// conversions. Clipboard text is only inspected in memory and kept out of the logs.
+//
+// `copy` puts text there for the tray's "Share Rates".
+use arboard::Clipboard;
+use rusqlite::Connection;
+use std::{
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+use tracing::{info, warn};
+
+use crate::{config::Config, converter, fiat, notify};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+// Anything longer is a sentence or a document, not an amount.
+const MAX_TEXT_LEN: usize = 32;
+
+// On X11 and Wayland the copying app serves the text to others, so the
+// clipboard handle is kept for the rest of the run.
+static OWNED: Mutex<Option<Clipboard>> = Mutex::new(None);
+
+pub fn copy(text: &str) -> Result<(), String> {
+    let mut owned = OWNED.lock().unwrap_or_else(|p| p.into_inner());
+    if owned.is_none() {
+        *owned = Some(Clipboard::new().map_err(|e| format!("No clipboard: {}", e))?);
+    }
+    owned
+        .as_mut()
+        .map_or(Ok(()), |clipboard| clipboard.set_text(text))
+        .map_err(|e| format!("Could not copy to the clipboard: {}", e))
+}
+
+pub fn paste() -> Result<String, String> {
+    let mut owned = OWNED.lock().unwrap_or_else(|p| p.into_inner());
+    if owned.is_none() {
+        *owned = Some(Clipboard::new().map_err(|e| format!("No clipboard: {}", e))?);
+    }
+    owned
+        .as_mut()
+        .map_or(Ok(String::new()), |clipboard| clipboard.get_text())
+        .map_err(|e| format!("Could not read the clipboard: {}", e))
+}
+
+pub fn spawn_watcher(db: Arc<Mutex<Connection>>, config: Arc<Config>) {
+    thread::spawn(move || {
+        let mut clipboard = match Clipboard::new() {
+            Ok(clipboard) => clipboard,
+            Err(e) => {
+                warn!("Clipboard watcher disabled: {}", e);
+                return;
+            }
+        };
+        info!("Watching the clipboard for amounts.");
+        // Whatever was copied before startup doesn't count as a new copy.
+        let mut last_text = clipboard.get_text().ok();
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            let Ok(text) = clipboard.get_text() else {
+                continue;
+            };
+            if last_text.as_deref() == Some(text.as_str()) {
+                continue;
+            }
+            let parsed = parse_amount(&text);
+            last_text = Some(text);
+            let Some((amount, from_local)) = parsed else {
+                continue;
+            };
+            let rates = crate::load_rates(&db, &config);
+            let unit = if from_local {
+                fiat::active().unit
+            } else {
+                "USD"
+            };
+            notify::notify_unlogged(
+                &format!("{:.2} {}", amount, unit),
+                &converter::conversion_lines(amount, from_local, &rates, &config.converter)
+                    .join("\n"),
+            );
+        }
+    });
+}
+
+// Returns the amount and whether it is in the active fiat rather than USD.
+// Bare numbers only count when they have a separator, so IDs and phone
+// numbers don't trigger popups.
+fn parse_amount(text: &str) -> Option<(f64, bool)> {
+    let text = text.trim();
+    if text.is_empty() || text.len() > MAX_TEXT_LEN {
+        return None;
+    }
+    let start = text.find(|c: char| c.is_ascii_digit())?;
+    let end = text.rfind(|c: char| c.is_ascii_digit())? + 1;
+    let number = &text[start..end];
+    if !number
+        .chars()
+        .all(|c| c.is_ascii_digit() || c == '.' || c == ',')
+    {
+        return None;
+    }
+    let marker: String = format!("{} {}", &text[..start], &text[end..])
+        .to_lowercase()
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '.')
+        .collect();
+    let local = match marker.as_str() {
+        "" if number.contains(['.', ',']) => None,
+        "$" | "us$" | "usd" | "usd$" => None,
+        "bs" | "bss" | "bsd" | "ves" => Some("VES"),
+        "cop" => Some("COP"),
+        "ars" => Some("ARS"),
+        _ => return None,
+    };
+    // Amounts in a fiat other than the active one have no rates to convert with.
+    let from_local = match local {
+        Some(code) if code != fiat::active().code => return None,
+        Some(_) => true,
+        None => false,
+    };
+    let amount = converter::parse_amount(number)?;
+    (amount > 0.0).then_some((amount, from_local))
+}
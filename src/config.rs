@@ -0,0 +1,1055 @@
+// --- User Configuration ---
+// Optional TOML file in the platform config directory. Every field has a
+// default, so a missing file or a partial one behaves like the built-in setup.
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::{
+    aggregate::Weighting,
+    paths,
+    rounding::{Rounding, RoundingMode},
+};
+
+const CONFIG_FILE: &str = "config.toml";
+// Named profiles are complete config files: profiles/<name>.toml.
+const PROFILES_DIR: &str = "profiles";
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct Config {
+    // "auto" follows the system locale; "es" or "en" force a language.
+    pub language: String,
+    pub tray: TrayConfig,
+    pub layout: LayoutConfig,
+    pub widget: WidgetConfig,
+    pub fonts: FontsConfig,
+    pub clicks: ClicksConfig,
+    pub logging: LoggingConfig,
+    pub clipboard: ClipboardConfig,
+    pub converter: ConverterConfig,
+    pub fiats: FiatsConfig,
+    pub providers: ProvidersConfig,
+    // Per-symbol display options, keyed by database symbol ("bcv", "binance", ...).
+    pub symbols: BTreeMap<String, SymbolConfig>,
+    // Extra symbols computed from others, keyed by the new symbol's name.
+    pub derived: BTreeMap<String, DerivedConfig>,
+    // Extra coins priced in USD by CoinMarketCap, keyed by the symbol to store
+    // them under ("eth", "xmr", ...).
+    pub cryptos: BTreeMap<String, CryptoConfig>,
+    // Rates read from public Telegram channels, keyed like `cryptos`.
+    pub telegram: BTreeMap<String, TelegramConfig>,
+    // Rates scraped from any web page, keyed like `cryptos`.
+    pub scrape: BTreeMap<String, ScrapeConfig>,
+    pub brecha: BrechaConfig,
+    pub bcv: BcvConfig,
+    pub http: HttpConfig,
+    pub proxy: ProxyConfig,
+    pub dns: DnsConfig,
+    pub binance: BinanceConfig,
+    pub zelle: ZelleConfig,
+    pub noones: NoonesConfig,
+    pub reserve: ReserveConfig,
+    pub parallel: ParallelConfig,
+    pub alerts: Vec<AlertConfig>,
+    pub depeg: DepegConfig,
+    pub attention: AttentionConfig,
+    pub trend: TrendConfig,
+    pub summary: SummaryConfig,
+    pub schedule: ScheduleConfig,
+    pub power: PowerConfig,
+    pub metered: MeteredConfig,
+    pub sheets: SheetsConfig,
+    pub api: ApiConfig,
+    pub grpc: GrpcConfig,
+    pub pushgateway: PushgatewayConfig,
+    pub overlay: OverlayConfig,
+    pub release_check: ReleaseCheckConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            language: "auto".to_string(),
+            tray: TrayConfig::default(),
+            layout: LayoutConfig::default(),
+            widget: WidgetConfig::default(),
+            fonts: FontsConfig::default(),
+            clicks: ClicksConfig::default(),
+            logging: LoggingConfig::default(),
+            clipboard: ClipboardConfig::default(),
+            converter: ConverterConfig::default(),
+            fiats: FiatsConfig::default(),
+            providers: ProvidersConfig::default(),
+            symbols: BTreeMap::new(),
+            derived: BTreeMap::new(),
+            cryptos: BTreeMap::new(),
+            telegram: BTreeMap::new(),
+            scrape: BTreeMap::new(),
+            brecha: BrechaConfig::default(),
+            bcv: BcvConfig::default(),
+            http: HttpConfig::default(),
+            proxy: ProxyConfig::default(),
+            dns: DnsConfig::default(),
+            binance: BinanceConfig::default(),
+            zelle: ZelleConfig::default(),
+            noones: NoonesConfig::default(),
+            reserve: ReserveConfig::default(),
+            parallel: ParallelConfig::default(),
+            alerts: Vec::new(),
+            depeg: DepegConfig::default(),
+            attention: AttentionConfig::default(),
+            trend: TrendConfig::default(),
+            summary: SummaryConfig::default(),
+            schedule: ScheduleConfig::default(),
+            power: PowerConfig::default(),
+            metered: MeteredConfig::default(),
+            sheets: SheetsConfig::default(),
+            api: ApiConfig::default(),
+            grpc: GrpcConfig::default(),
+            pushgateway: PushgatewayConfig::default(),
+            overlay: OverlayConfig::default(),
+            release_check: ReleaseCheckConfig::default(),
+        }
+    }
+}
+
+impl Config {
+    pub fn symbol(&self, symbol: &str) -> SymbolConfig {
+        self.symbols.get(symbol).cloned().unwrap_or_default()
+    }
+
+    // The built-in gap needs both of its inputs.
+    pub fn brecha_enabled(&self) -> bool {
+        self.brecha.enabled && self.providers.enabled("bcv") && self.providers.enabled("binance")
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct TrayConfig {
+    // Text label next to the icon where the tray host supports it (Linux
+    // appindicator label, macOS menu bar title). Off by default since it
+    // takes a lot of panel space.
+    pub show_label: bool,
+    // Windows only: a trend arrow on the mini widget's taskbar button, which
+    // stays visible when the tray icon is tucked into the overflow flyout.
+    // Opens the widget at startup.
+    pub taskbar_badge: bool,
+    // Full sentences in the tooltip instead of the compact lines, for screen
+    // readers. Windows still cuts tooltips at 127 characters.
+    pub verbose_tooltip: bool,
+    // One menu line per rate with everything the icon shows, as text.
+    pub rates_in_menu: bool,
+    // Larger digits with a one-pixel outline in the opposite color.
+    pub high_contrast: bool,
+    // Fill the icon behind the text instead of leaving it transparent.
+    pub solid_background: bool,
+    // Render text at twice the size and scale it down, for smoother digits.
+    pub supersample: bool,
+    // A "1 · 10 · 100 USD = ..." line in the tooltip for the first rate.
+    pub equivalences: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct LayoutConfig {
+    // Pixels between two rates in the tray icon.
+    pub padding: u32,
+    // Pixels between a rate's icon and its value.
+    pub icon_gap: u32,
+    // Text drawn between two rates, e.g. "|" or "·"; empty for none.
+    pub separator: String,
+    // A thin vertical line between two rates.
+    pub divider: bool,
+    // 2 stacks rates in pairs in an icon twice as tall, for panels thick
+    // enough to show it.
+    pub rows: u32,
+}
+
+impl Default for LayoutConfig {
+    fn default() -> Self {
+        LayoutConfig {
+            padding: 4,
+            icon_gap: 4,
+            separator: String::new(),
+            divider: false,
+            rows: 1,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct FontsConfig {
+    // Font file for the tray and windows instead of the bundled Roboto Mono.
+    // If it (or the bundled one) can't be loaded, an installed monospace is used.
+    pub primary: String,
+    // Font files tried in order for characters the embedded font lacks.
+    pub fallback: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ClickAction {
+    None,
+    // Update every rate, like Update Now.
+    Refresh,
+    Converter,
+    // The first rate shown, as a plain number.
+    Copy,
+    // Save the first rate's chart and open it.
+    Chart,
+}
+
+// What clicking the tray icon does. Linux tray hosts mostly don't report
+// clicks; double clicks are only reported on Windows.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct ClicksConfig {
+    pub left: ClickAction,
+    pub middle: ClickAction,
+    pub double: ClickAction,
+}
+
+impl Default for ClicksConfig {
+    fn default() -> Self {
+        ClicksConfig {
+            left: ClickAction::Converter,
+            middle: ClickAction::None,
+            double: ClickAction::None,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct WidgetConfig {
+    // Open the always-on-top mini widget at startup. It also opens on its own
+    // when there is no tray to show the icon in.
+    pub show: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct SymbolConfig {
+    // Show USD per `per` units of the quoted currency instead of the rate,
+    // e.g. `invert = true, per = 1000` reads "USD per 1000 Bs".
+    pub invert: bool,
+    pub per: f64,
+    // "half_up" or "truncate", to `round_to` (e.g. 0.05) when set.
+    pub rounding: RoundingMode,
+    pub round_to: Option<f64>,
+    // Replaces the source's icon: a built-in name ("binance.png"), a file in
+    // the assets folder, or an absolute path. "" draws the name instead.
+    pub icon: Option<String>,
+}
+
+impl Default for SymbolConfig {
+    fn default() -> Self {
+        SymbolConfig {
+            invert: false,
+            per: 1.0,
+            rounding: RoundingMode::HalfUp,
+            round_to: None,
+            icon: None,
+        }
+    }
+}
+
+impl SymbolConfig {
+    // Only affects what is shown; stored rates and conversions use the raw value.
+    pub fn display_value(&self, rate: f64) -> f64 {
+        if self.invert && rate > 0.0 {
+            self.per / rate
+        } else {
+            rate
+        }
+    }
+
+    pub fn rounding(&self) -> Rounding {
+        Rounding {
+            mode: self.rounding,
+            step: self.round_to,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct DerivedConfig {
+    // Arithmetic over other symbols, e.g. "(bcv + binance) / 2".
+    pub expr: String,
+    // Alternative to `expr`: a weighted average of other symbols, e.g.
+    // { binance = 3, bcv = 1 }. Symbols without a rate yet are left out.
+    pub weights: BTreeMap<String, f64>,
+    // Alternative to both: what a dollar actually turns into along a transfer
+    // path such as PayPal -> USDT -> VES. `via` is the rate at the end of the
+    // path and each entry in `fees` is one hop, taken in order.
+    pub via: Option<String>,
+    pub fees: Vec<FeeConfig>,
+    // USD sent per transfer, to spread the fixed fees over; 100 if unset.
+    pub amount: Option<f64>,
+    // Name shown in the tray and tooltip; defaults to the symbol in upper case.
+    pub label: Option<String>,
+    // Unit of the result; the active fiat's unit ("Bs") also makes it show up
+    // in the converter.
+    pub unit: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct FeeConfig {
+    // Percent of what is left at this hop, then a flat amount in USD.
+    pub pct: f64,
+    pub fixed: f64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct CryptoConfig {
+    // CoinMarketCap ticker; defaults to the symbol in upper case.
+    pub coin: Option<String>,
+    // Name shown in the tray and tooltip; defaults to the ticker.
+    pub label: Option<String>,
+    // Asset key of the tray icon, e.g. "eth.png" under the config's assets
+    // folder. Without one the label is drawn as text.
+    pub icon: String,
+}
+
+impl CryptoConfig {
+    pub fn coin(&self, symbol: &str) -> String {
+        self.coin.clone().unwrap_or_else(|| symbol.to_uppercase())
+    }
+
+    pub fn label(&self, symbol: &str) -> String {
+        self.label.clone().unwrap_or_else(|| self.coin(symbol))
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct TelegramConfig {
+    // Public channel name, as in t.me/<channel>.
+    pub channel: String,
+    // Regex matched against each post, newest first; the first capture group
+    // (or the whole match) is the rate.
+    pub pattern: String,
+    // Name shown in the tray and tooltip; defaults to the symbol in upper case.
+    pub label: Option<String>,
+    // Asset key of the tray icon, as for `cryptos`.
+    pub icon: String,
+    pub unit: String,
+}
+
+impl Default for TelegramConfig {
+    fn default() -> Self {
+        TelegramConfig {
+            channel: String::new(),
+            pattern: String::new(),
+            label: None,
+            icon: String::new(),
+            unit: "Bs".to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct ScrapeConfig {
+    pub url: String,
+    // CSS selector of the element holding the rate; the first match is read.
+    pub selector: String,
+    // Optional regex over that element's text, as for `[telegram]`.
+    pub pattern: String,
+    // Load the page in a headless Chromium first, for rates filled in by
+    // JavaScript. Needs a build with the `browser` feature.
+    pub browser: bool,
+    pub label: Option<String>,
+    pub icon: String,
+    pub unit: String,
+}
+
+impl Default for ScrapeConfig {
+    fn default() -> Self {
+        ScrapeConfig {
+            url: String::new(),
+            selector: String::new(),
+            pattern: String::new(),
+            browser: false,
+            label: None,
+            icon: String::new(),
+            unit: "Bs".to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct ConverterConfig {
+    // Impuesto a las Grandes Transacciones Financieras: added on payments in
+    // foreign currency, valued at the official rate. VES only.
+    pub igtf: bool,
+    pub igtf_pct: f64,
+    // Show the IGTF-adjusted amount next to every rate, not just the official one.
+    pub igtf_all_rates: bool,
+    // USD amounts listed, already converted, in the Quick Conversions submenu.
+    pub quick_amounts: Vec<f64>,
+}
+
+impl Default for ConverterConfig {
+    fn default() -> Self {
+        ConverterConfig {
+            igtf: true,
+            igtf_pct: 3.0,
+            igtf_all_rates: false,
+            quick_amounts: vec![1.0, 10.0, 20.0, 50.0, 100.0],
+        }
+    }
+}
+
+impl DerivedConfig {
+    // How the value is computed, as shown in the tooltip.
+    pub fn formula(&self) -> String {
+        if let Some(via) = &self.via {
+            let hops: Vec<String> = self
+                .fees
+                .iter()
+                .map(|fee| format!("-{}% -{}", fee.pct, fee.fixed))
+                .collect();
+            return format!("{} after {}", via, hops.join(", "));
+        }
+        if self.weights.is_empty() {
+            return self.expr.clone();
+        }
+        let terms: Vec<String> = self
+            .weights
+            .iter()
+            .map(|(symbol, weight)| format!("{} x{}", symbol, weight))
+            .collect();
+        format!("weighted {}", terms.join(", "))
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct BinanceConfig {
+    // How many of the best sell ads the P2P rate is taken from.
+    pub ads: u32,
+    pub weighting: Weighting,
+    // Poll the Binance rate this often on top of the regular cycle; 0 is off.
+    pub live_interval_secs: u64,
+    // Redraw the icon for live updates at most this often.
+    pub live_render_secs: u64,
+    // Payment methods the P2P ads must accept, per fiat code; none means any.
+    pub pay_types: BTreeMap<String, Vec<String>>,
+}
+
+impl Default for BinanceConfig {
+    fn default() -> Self {
+        BinanceConfig {
+            ads: 1,
+            weighting: Weighting::First,
+            live_interval_secs: 0,
+            live_render_secs: 30,
+            pay_types: BTreeMap::from([("VES".to_string(), vec!["PagoMovil".to_string()])]),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct FiatsConfig {
+    // Currency codes to fetch ("VES", "COP", "ARS"). The first one is shown
+    // at startup; the tray menu switches between them.
+    pub tracked: Vec<String>,
+}
+
+impl Default for FiatsConfig {
+    fn default() -> Self {
+        FiatsConfig {
+            tracked: vec!["VES".to_string()],
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct ProvidersConfig {
+    // Provider keys as in `[http.headers]` ("binance", "cmc", ...). Their
+    // rates are neither fetched nor shown.
+    pub disabled: Vec<String>,
+}
+
+impl ProvidersConfig {
+    pub fn enabled(&self, provider: &str) -> bool {
+        !self
+            .disabled
+            .iter()
+            .any(|p| p.eq_ignore_ascii_case(provider))
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct BrechaConfig {
+    // Built-in gap between the parallel (Binance) and official (BCV) rate, in %.
+    pub enabled: bool,
+}
+
+impl Default for BrechaConfig {
+    fn default() -> Self {
+        BrechaConfig { enabled: true }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct ZelleConfig {
+    // Extra VES source for the Zelle market rate, from an aggregator's JSON.
+    pub enabled: bool,
+    pub url: String,
+    // Dot-separated path to the rate in the response, e.g. "USD.VES".
+    pub field: String,
+}
+
+impl Default for ZelleConfig {
+    fn default() -> Self {
+        ZelleConfig {
+            enabled: false,
+            url: "https://api.yadio.io/exrates/USD".to_string(),
+            field: "USD.VES".to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct HttpConfig {
+    // Sent with every request unless a provider's headers set their own.
+    pub user_agent: String,
+    // Headers added to or replacing the built-in ones, per provider: "bcv",
+    // "binance", "cmc", "trm", "dolarapi", "kraken", "noones", "telegram",
+    // "zelle", "reserve" or "scrape". An empty value drops a header.
+    pub headers: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        HttpConfig {
+            user_agent:
+                "Mozilla/5.0 (X11; Ubuntu; Linux x86_64; rv:138.0) Gecko/20100101 Firefox/138.0"
+                    .to_string(),
+            headers: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct DnsConfig {
+    // Look up the providers' hosts over DNS-over-HTTPS instead of the system
+    // resolver, for ISPs that block them at the DNS level.
+    pub doh: bool,
+    // JSON API endpoint, by IP address so it can be reached without DNS.
+    pub server: String,
+}
+
+impl Default for DnsConfig {
+    fn default() -> Self {
+        DnsConfig {
+            doh: false,
+            server: "https://1.1.1.1/dns-query".to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct ProxyConfig {
+    // "socks5h://127.0.0.1:9050" (Tor), "socks5://..." or "http://...".
+    // Empty fetches everything directly.
+    pub url: String,
+    // Symbols fetched through the proxy; empty means all of them.
+    pub symbols: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct BcvConfig {
+    // Pages tried in order when www.bcv.org.ve fails or has no rate; each is
+    // read the same way as the home page. Plain http:// pages are opt-in only.
+    pub mirrors: Vec<String>,
+    // CSS selector for the element holding the rate; empty uses the built-in
+    // full path, which breaks on most layout changes.
+    pub selector: String,
+    // Tried in order when `selector` finds nothing. The USD box keeps its id
+    // through most layout changes and also appears on other pages of the site.
+    pub fallback_selectors: Vec<String>,
+}
+
+impl Default for BcvConfig {
+    fn default() -> Self {
+        BcvConfig {
+            mirrors: vec![
+                "https://www.bcv.org.ve/estadisticas/tipo-cambio-de-referencia-smc".to_string(),
+            ],
+            selector: String::new(),
+            fallback_selectors: vec!["#dolar strong".to_string()],
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct ReserveConfig {
+    // Extra VES source for the rate the Reserve app quotes. Reserve has no
+    // documented public API, so `url` has to be set to the JSON it uses.
+    pub enabled: bool,
+    pub url: String,
+    // Dot-separated path to the rate in the response, as for `[zelle]`.
+    pub field: String,
+}
+
+impl Default for ReserveConfig {
+    fn default() -> Self {
+        ReserveConfig {
+            enabled: false,
+            url: String::new(),
+            field: "rate".to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct NoonesConfig {
+    // Extra VES source: USD/VES implied by Noones P2P BTC offers in both.
+    pub enabled: bool,
+    // Offer filter for the VES side, e.g. "pago-movil"; empty means any.
+    pub payment_method: String,
+    // How many of the best offers on each side the price is taken from.
+    pub ads: u32,
+    pub weighting: Weighting,
+}
+
+impl Default for NoonesConfig {
+    fn default() -> Self {
+        NoonesConfig {
+            enabled: false,
+            payment_method: String::new(),
+            ads: 1,
+            weighting: Weighting::First,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct AttentionConfig {
+    // Blink the icon when a rate moves at least this much (in %) in one update.
+    // 0 turns it off.
+    pub threshold_pct: f64,
+    pub flashes: u32,
+}
+
+impl Default for AttentionConfig {
+    fn default() -> Self {
+        AttentionConfig {
+            threshold_pct: 5.0,
+            flashes: 3,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct TrendConfig {
+    // Glyph next to each tray value for the direction of the last `hours`.
+    pub show: bool,
+    pub hours: u32,
+    // Fitted moves smaller than this (in %) show as steady.
+    pub flat_pct: f64,
+}
+
+impl Default for TrendConfig {
+    fn default() -> Self {
+        TrendConfig {
+            show: false,
+            hours: 6,
+            flat_pct: 0.2,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct ScheduleConfig {
+    // Skip scheduled BCV fetches on weekends and the holidays below (VET dates).
+    pub bcv_business_days_only: bool,
+    // Bank holidays as "YYYY-MM-DD"; BCV publishes nothing on these either.
+    pub bcv_holidays: Vec<String>,
+    // Check BCV once a day, then every few minutes between these hours (VET)
+    // until the next rate is published.
+    pub bcv_smart_window: bool,
+    pub bcv_window_start_hour: u32,
+    pub bcv_window_end_hour: u32,
+    // The first fetch waits up to this long after startup, and each cycle up
+    // to `jitter_secs` longer than the interval, picked at random.
+    pub initial_offset_secs: u64,
+    pub jitter_secs: u64,
+    // Seconds between fetches per provider key ("bcv", "binance", "cmc", ...);
+    // the rest use the default interval.
+    pub intervals: BTreeMap<String, u64>,
+}
+
+impl Default for ScheduleConfig {
+    fn default() -> Self {
+        ScheduleConfig {
+            bcv_business_days_only: true,
+            bcv_holidays: Vec::new(),
+            bcv_smart_window: false,
+            bcv_window_start_hour: 14,
+            bcv_window_end_hour: 19,
+            initial_offset_secs: 30,
+            jitter_secs: 60,
+            intervals: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct PowerConfig {
+    // Update less often on battery or with the OS battery saver on.
+    pub throttle_on_battery: bool,
+    pub battery_interval_multiplier: u32,
+    // Symbols that aren't fetched at all until AC power is back.
+    pub pause_on_battery: Vec<String>,
+}
+
+impl Default for PowerConfig {
+    fn default() -> Self {
+        PowerConfig {
+            throttle_on_battery: true,
+            battery_interval_multiplier: 3,
+            pause_on_battery: Vec::new(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MeteredMode {
+    // Update `interval_multiplier` times less often.
+    Reduced,
+    // Only update from the menu.
+    Manual,
+    Ignore,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct MeteredConfig {
+    pub mode: MeteredMode,
+    pub interval_multiplier: u32,
+}
+
+impl Default for MeteredConfig {
+    fn default() -> Self {
+        MeteredConfig {
+            mode: MeteredMode::Reduced,
+            interval_multiplier: 4,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct SheetsConfig {
+    pub enabled: bool,
+    // The long ID in the sheet's URL; share the sheet with the service account.
+    pub spreadsheet_id: String,
+    // Tab to append to.
+    pub sheet: String,
+    // One column per symbol, after the date.
+    pub symbols: Vec<String>,
+    // Local hour from which the day's row is written, so it has closing rates.
+    pub append_hour: u32,
+    // Service account key file, if it isn't stored in the keyring.
+    pub credentials_file: String,
+}
+
+impl Default for SheetsConfig {
+    fn default() -> Self {
+        SheetsConfig {
+            enabled: false,
+            spreadsheet_id: String::new(),
+            sheet: "Rates".to_string(),
+            symbols: vec!["bcv".to_string(), "binance".to_string()],
+            append_hour: 18,
+            credentials_file: String::new(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct SummaryConfig {
+    // Once-a-day notification with every rate and its 24 h change.
+    pub daily: bool,
+    // Local time, "HH:MM"; also when the weekly digest goes out.
+    pub daily_time: String,
+    // 7-day digest per source, on `weekly_day` ("sunday", "mon", ...).
+    pub weekly: bool,
+    pub weekly_day: String,
+    // Also write the digest here; HTML when it ends in .html.
+    pub weekly_file: String,
+}
+
+impl Default for SummaryConfig {
+    fn default() -> Self {
+        SummaryConfig {
+            daily: false,
+            daily_time: "20:00".to_string(),
+            weekly: false,
+            weekly_day: "sunday".to_string(),
+            weekly_file: String::new(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct ApiConfig {
+    // Local HTTP/WebSocket API on <bind>:<port>.
+    pub enabled: bool,
+    pub port: u16,
+    // "0.0.0.0" to reach it from other machines; set a token first.
+    pub bind: String,
+    // Required on every request when set; the keyring's `api-token` wins.
+    pub token: String,
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        ApiConfig {
+            enabled: false,
+            port: 8765,
+            bind: "127.0.0.1".to_string(),
+            token: String::new(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct GrpcConfig {
+    // gRPC server on <bind>:<port>; needs a build with `--features grpc`.
+    // Uses the API token, when set.
+    pub enabled: bool,
+    pub port: u16,
+    pub bind: String,
+}
+
+impl Default for GrpcConfig {
+    fn default() -> Self {
+        GrpcConfig {
+            enabled: false,
+            port: 50051,
+            bind: "127.0.0.1".to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct PushgatewayConfig {
+    // Pushed to after every update cycle; empty disables it.
+    pub url: String,
+    pub job: String,
+    // Grouping label telling several installs apart; empty leaves it out.
+    pub instance: String,
+}
+
+impl Default for PushgatewayConfig {
+    fn default() -> Self {
+        PushgatewayConfig {
+            url: String::new(),
+            job: "bcv_tray".to_string(),
+            instance: String::new(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct OverlayConfig {
+    // Rewritten after every update when set; empty disables it.
+    pub text_file: String,
+    // `{symbol}` placeholders are replaced with that symbol's rate.
+    pub template: String,
+}
+
+impl Default for OverlayConfig {
+    fn default() -> Self {
+        OverlayConfig {
+            text_file: String::new(),
+            template: "BCV {bcv}  Binance {binance}".to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct ReleaseCheckConfig {
+    // Look for a newer GitHub release once a day. The menu item works either way.
+    pub enabled: bool,
+}
+
+impl Default for ReleaseCheckConfig {
+    fn default() -> Self {
+        ReleaseCheckConfig { enabled: true }
+    }
+}
+
+// One `[[alerts]]` entry: notify when `symbol` crosses a threshold.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct AlertConfig {
+    pub symbol: String,
+    #[serde(default)]
+    pub above: Option<f64>,
+    #[serde(default)]
+    pub below: Option<f64>,
+    // How far back past the threshold the rate must go before the alert can
+    // fire again, in the rate's own units.
+    #[serde(default)]
+    pub hysteresis: f64,
+    // Notify when the rate moves more than this percent, either way, against
+    // the rate `window_minutes` earlier.
+    #[serde(default)]
+    pub change_pct: Option<f64>,
+    #[serde(default = "default_alert_window")]
+    pub window_minutes: u32,
+}
+
+fn default_alert_window() -> u32 {
+    60
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct ParallelConfig {
+    // Symbols that share one tray slot, highest priority first. Empty shows
+    // each source separately.
+    pub chain: Vec<String>,
+    // Older readings count as stale and fall through to the next symbol.
+    pub max_age_minutes: u32,
+}
+
+impl Default for ParallelConfig {
+    fn default() -> Self {
+        ParallelConfig {
+            chain: Vec::new(),
+            max_age_minutes: 120,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct DepegConfig {
+    // Fetch USDT/USD and notify when it is more than `threshold_pct` off 1.
+    pub enabled: bool,
+    pub threshold_pct: f64,
+}
+
+impl Default for DepegConfig {
+    fn default() -> Self {
+        DepegConfig {
+            enabled: false,
+            threshold_pct: 0.5,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct ClipboardConfig {
+    // Convert amounts as they are copied. Off by default: it means reading
+    // everything that passes through the clipboard.
+    pub watch: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct LoggingConfig {
+    // tracing filter directive, e.g. "info" or "bcv_tray=debug". RUST_LOG wins if set.
+    pub level: String,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        LoggingConfig {
+            level: "info".to_string(),
+        }
+    }
+}
+
+pub fn config_path() -> Result<PathBuf, String> {
+    Ok(paths::config_dir()?.join(CONFIG_FILE))
+}
+
+pub fn profiles_dir() -> Result<PathBuf, String> {
+    Ok(paths::config_dir()?.join(PROFILES_DIR))
+}
+
+pub fn profile_path(name: &str) -> Result<PathBuf, String> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(format!(
+            "Invalid profile name '{}': use letters, digits, '-' and '_'",
+            name
+        ));
+    }
+    Ok(profiles_dir()?.join(format!("{}.toml", name)))
+}
+
+// Names of the profiles found on disk, sorted. A missing directory just means none.
+pub fn profiles() -> Vec<String> {
+    let Ok(entries) = profiles_dir()
+        .and_then(|dir| std::fs::read_dir(&dir).map_err(|e| format!("{}: {}", dir.display(), e)))
+    else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+        .filter_map(|path| Some(path.file_stem()?.to_str()?.to_string()))
+        .filter(|name| profile_path(name).is_ok())
+        .collect();
+    names.sort();
+    names
+}
+
+// Runs before logging is set up (the log level lives in here), so problems are
+// returned to the caller instead of logged. `profile` selects
+// profiles/<name>.toml instead of config.toml; unlike the main file, a named
+// profile has to exist.
+pub fn load(profile: Option<&str>) -> Result<Config, String> {
+    let path = match profile {
+        Some(name) => profile_path(name)?,
+        None => config_path()?,
+    };
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound && profile.is_none() => {
+            return Ok(Config::default())
+        }
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+    toml::from_str(&contents).map_err(|e| format!("{} could not be parsed: {}", path.display(), e))
+}
@@ -0,0 +1,386 @@
+// --- Converter Window ---
+// A small always-available calculator: type an amount, read it converted at
+// every rate the tray shows. Drawn with the embedded font into a software
+// buffer so no widget toolkit is needed beyond the window itself.
+use ab_glyph::{FontVec, PxScale};
+use image::{Rgba, RgbaImage};
+use imageproc::drawing::draw_text_mut;
+use softbuffer::{Context, Surface};
+use std::{num::NonZeroU32, rc::Rc, sync::Arc};
+use tao::{
+    dpi::{LogicalSize, PhysicalPosition},
+    event::{ElementState, KeyEvent, WindowEvent},
+    event_loop::EventLoopWindowTarget,
+    keyboard::Key,
+    window::{Window, WindowBuilder, WindowId},
+};
+
+use crate::{
+    config::ConverterConfig,
+    fiat,
+    i18n::{self, Msg},
+    rounding::Rounding,
+    state, RateInfo,
+};
+
+const WIDTH: f64 = 420.0;
+const HEIGHT: f64 = 210.0;
+const FONT_SIZE: f32 = 16.0;
+const LINE_HEIGHT: i32 = 22;
+const MARGIN: i32 = 12;
+const MAX_INPUT_LEN: usize = 15;
+
+pub const BACKGROUND: Rgba<u8> = Rgba([32, 32, 32, 255]);
+pub const FOREGROUND: Rgba<u8> = Rgba([235, 235, 235, 255]);
+pub const DIMMED: Rgba<u8> = Rgba([150, 150, 150, 255]);
+
+pub struct ConverterWindow {
+    window: Rc<Window>,
+    surface: Surface<Rc<Window>, Rc<Window>>,
+    font: Arc<FontVec>,
+    rates: Vec<RateInfo>,
+    config: ConverterConfig,
+    amount: String,
+    // false: amount is in USD and converted to the active fiat; true: the other way round.
+    from_local: bool,
+    position: Option<PhysicalPosition<i32>>,
+}
+
+impl ConverterWindow {
+    pub fn open<T>(
+        target: &EventLoopWindowTarget<T>,
+        font: Arc<FontVec>,
+        rates: Vec<RateInfo>,
+        config: ConverterConfig,
+    ) -> Result<Self, String> {
+        // Room for the 7/30-day comparison below the conversions.
+        let height = HEIGHT + (rates.len() as f64 + 1.0) * LINE_HEIGHT as f64;
+        let saved = state::get();
+        let position = state::on_screen(target, saved.converter_position);
+        let mut builder = WindowBuilder::new()
+            .with_title(i18n::tr(Msg::ConverterTitle))
+            .with_inner_size(LogicalSize::new(WIDTH, height))
+            .with_resizable(false);
+        if let Some(position) = position {
+            builder = builder.with_position(position);
+        }
+        let window = builder
+            .build(target)
+            .map_err(|e| format!("Failed to create converter window: {}", e))?;
+        let window = Rc::new(window);
+        let context = Context::new(window.clone())
+            .map_err(|e| format!("Failed to create drawing context: {}", e))?;
+        let surface = Surface::new(&context, window.clone())
+            .map_err(|e| format!("Failed to create drawing surface: {}", e))?;
+        window.set_focus();
+        Ok(Self {
+            window,
+            surface,
+            font,
+            rates,
+            config,
+            amount: "1".to_string(),
+            from_local: saved.converter_from_local,
+            position,
+        })
+    }
+
+    pub fn id(&self) -> WindowId {
+        self.window.id()
+    }
+
+    pub fn focus(&self) {
+        self.window.set_focus();
+    }
+
+    pub fn set_rates(&mut self, rates: Vec<RateInfo>) {
+        self.rates = rates;
+        self.window.request_redraw();
+    }
+
+    // Returns false once the window should be closed.
+    pub fn handle_event(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::CloseRequested => return false,
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        logical_key,
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => {
+                match logical_key {
+                    Key::Escape => return false,
+                    Key::Backspace => {
+                        self.amount.pop();
+                    }
+                    Key::Delete => self.amount.clear(),
+                    Key::Tab => self.from_local = !self.from_local,
+                    Key::Character(text) => self.push_input(text),
+                    _ => return true,
+                }
+                self.window.request_redraw();
+            }
+            WindowEvent::Moved(position) => self.position = Some(*position),
+            WindowEvent::Resized(_) | WindowEvent::ScaleFactorChanged { .. } => {
+                self.window.request_redraw();
+            }
+            _ => {}
+        }
+        true
+    }
+
+    fn push_input(&mut self, text: &str) {
+        for c in text.chars() {
+            if self.amount.len() >= MAX_INPUT_LEN {
+                break;
+            }
+            // Kept as typed; parse_amount works out which separator is the decimal one.
+            if c.is_ascii_digit() || c == '.' || c == ',' {
+                self.amount.push(c);
+            }
+        }
+    }
+
+    pub fn redraw(&mut self) -> Result<(), String> {
+        let size = self.window.inner_size();
+        let scale = self.window.scale_factor() as f32;
+        let canvas = self.render(size.width, size.height, scale);
+        present(&mut self.surface, &canvas)
+    }
+
+    fn render(&self, width: u32, height: u32, scale_factor: f32) -> RgbaImage {
+        let mut canvas = RgbaImage::from_pixel(width, height, BACKGROUND);
+        let scale = PxScale::from(FONT_SIZE * scale_factor);
+        let line_height = (LINE_HEIGHT as f32 * scale_factor) as i32;
+        let margin = (MARGIN as f32 * scale_factor) as i32;
+
+        let local_unit = fiat::active().unit;
+        let (from, to) = if self.from_local {
+            (local_unit, "USD")
+        } else {
+            ("USD", local_unit)
+        };
+        let mut lines = vec![(format!("{} {}_", self.amount, from), FOREGROUND)];
+        match parse_amount(&self.amount) {
+            Some(amount) => {
+                for line in conversion_lines(amount, self.from_local, &self.rates, &self.config) {
+                    lines.push((line, FOREGROUND));
+                }
+            }
+            None => lines.push((i18n::tr(Msg::TypeAnAmount).to_string(), DIMMED)),
+        }
+        let comparison = comparison_lines(&self.rates);
+        if !comparison.is_empty() {
+            lines.push((String::new(), DIMMED));
+            lines.extend(comparison.into_iter().map(|line| (line, DIMMED)));
+        }
+        lines.push((i18n::trf(Msg::ConverterHelp, &[&to, &from]), DIMMED));
+
+        for (i, (text, color)) in lines.iter().enumerate() {
+            let y = margin + i as i32 * line_height;
+            // The footer sits at the bottom regardless of how many rates there are.
+            let y = if i == lines.len() - 1 {
+                height as i32 - margin - line_height
+            } else {
+                y
+            };
+            draw_text_mut(
+                &mut canvas,
+                *color,
+                margin,
+                y,
+                scale,
+                self.font.as_ref(),
+                text,
+            );
+        }
+        canvas
+    }
+}
+
+// The position and direction come back next time the converter is opened.
+impl Drop for ConverterWindow {
+    fn drop(&mut self) {
+        let (position, from_local) = (self.position, self.from_local);
+        state::update(|state| {
+            if let Some(position) = position {
+                state.converter_position = Some((position.x, position.y));
+            }
+            state.converter_from_local = from_local;
+        });
+    }
+}
+
+// Copies a rendered canvas to the window; also used by the mini widget.
+pub fn present(
+    surface: &mut Surface<Rc<Window>, Rc<Window>>,
+    canvas: &RgbaImage,
+) -> Result<(), String> {
+    let (Some(width), Some(height)) = (
+        NonZeroU32::new(canvas.width()),
+        NonZeroU32::new(canvas.height()),
+    ) else {
+        return Ok(());
+    };
+    surface
+        .resize(width, height)
+        .map_err(|e| format!("Failed to resize window surface: {}", e))?;
+    let mut buffer = surface
+        .buffer_mut()
+        .map_err(|e| format!("Failed to get window buffer: {}", e))?;
+    for (dst, px) in buffer.iter_mut().zip(canvas.pixels()) {
+        let [r, g, b, _] = px.0;
+        *dst = (r as u32) << 16 | (g as u32) << 8 | b as u32;
+    }
+    buffer
+        .present()
+        .map_err(|e| format!("Failed to present window buffer: {}", e))
+}
+
+// One line per rate in the active fiat, plus (for VES) the IGTF-inclusive
+// amount at the official rate, or next to every rate with `igtf_all_rates`.
+pub fn conversion_lines(
+    amount: f64,
+    from_local: bool,
+    rates: &[RateInfo],
+    config: &ConverterConfig,
+) -> Vec<String> {
+    let local = fiat::active();
+    let mut local_rates = rates
+        .iter()
+        .filter(|r| r.unit == local.unit && r.rate > 0.0);
+    let igtf = (config.igtf && local.code == "VES").then_some(config.igtf_pct);
+    let mut lines = Vec::new();
+    // Amounts in the local currency follow that rate's rounding setting.
+    let convert = |rate: f64, rounding: Rounding| {
+        if from_local {
+            format!("{:.2} USD", amount / rate)
+        } else {
+            format!("{} {}", rounding.format(amount * rate), local.unit)
+        }
+    };
+    for rate in local_rates.clone() {
+        let mut line = format!("{}: {}", rate.currency, convert(rate.rate, rate.rounding));
+        if let Some(pct) = igtf.filter(|_| config.igtf_all_rates) {
+            let adjusted = convert(with_igtf(rate.rate, pct), rate.rounding);
+            line.push_str(&format!(" · IGTF {}", adjusted));
+        }
+        lines.push(line);
+    }
+    if let Some(pct) = igtf.filter(|_| !config.igtf_all_rates) {
+        if let Some(official) = local_rates.next() {
+            lines.push(format!(
+                "{} + IGTF {}%: {}",
+                official.currency,
+                pct,
+                convert(with_igtf(official.rate, pct), official.rounding)
+            ));
+        }
+    }
+    if lines.is_empty() {
+        lines.push(i18n::tr(Msg::NoRatesYet).to_string());
+    }
+    lines
+}
+
+// "BCV: 7d 170.10 (+4.9%) · 30d 160.00 (+11.5%)" for rates with history
+// that far back.
+pub fn comparison_lines(rates: &[RateInfo]) -> Vec<String> {
+    rates
+        .iter()
+        .filter(|r| r.rate > 0.0 && (r.week_ago.is_some() || r.month_ago.is_some()))
+        .map(|r| {
+            let past = |previous: Option<f64>| match previous {
+                Some(previous) if previous != 0.0 => format!(
+                    "{} ({:+.1}%)",
+                    r.rounding.format(previous),
+                    (r.rate / previous - 1.0) * 100.0
+                ),
+                _ => "--".to_string(),
+            };
+            i18n::trf(
+                Msg::PastComparison,
+                &[&r.currency, &past(r.week_ago), &past(r.month_ago)],
+            )
+        })
+        .collect()
+}
+
+// Accepts "1,234.56", "1.234,56" and "1234,56". A lone separator followed by
+// exactly three digits is read as grouping ("1.200" is twelve hundred), as is
+// common here.
+pub fn parse_amount(s: &str) -> Option<f64> {
+    let decimal_at = match (s.rfind('.'), s.rfind(',')) {
+        (Some(dot), Some(comma)) => Some(dot.max(comma)),
+        (Some(i), None) | (None, Some(i)) => {
+            let separator = &s[i..i + 1];
+            if s.matches(separator).count() > 1 || s.len() - i - 1 == 3 {
+                None
+            } else {
+                Some(i)
+            }
+        }
+        (None, None) => None,
+    };
+    let mut normalized = String::with_capacity(s.len());
+    for (i, c) in s.char_indices() {
+        if c.is_ascii_digit() {
+            normalized.push(c);
+        } else if Some(i) == decimal_at {
+            normalized.push('.');
+        }
+    }
+    normalized.parse().ok()
+}
+
+fn with_igtf(rate: f64, pct: f64) -> f64 {
+    rate * (1.0 + pct / 100.0)
+}
+
+// "20 USD = 3567.01 Bs · IGTF 3674.02 Bs" at the first (official) rate of the
+// active fiat, one line for each of `quick_amounts`.
+pub fn quick_lines(rates: &[RateInfo], config: &ConverterConfig) -> Vec<String> {
+    let local = fiat::active();
+    let official = rates.iter().find(|r| r.unit == local.unit && r.rate > 0.0);
+    let igtf = (config.igtf && local.code == "VES").then_some(config.igtf_pct);
+    config
+        .quick_amounts
+        .iter()
+        .map(|amount| {
+            let Some(official) = official else {
+                return format!("{} USD = --", amount);
+            };
+            let rounding = official.rounding;
+            let mut line = format!(
+                "{} USD = {} {}",
+                amount,
+                rounding.format(amount * official.rate),
+                local.unit
+            );
+            if let Some(pct) = igtf {
+                let adjusted = rounding.format(amount * with_igtf(official.rate, pct));
+                line.push_str(&format!(" · IGTF {} {}", adjusted, local.unit));
+            }
+            line
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_either_decimal_convention() {
+        assert_eq!(parse_amount("1.234,56"), Some(1234.56));
+        assert_eq!(parse_amount("1,234.56"), Some(1234.56));
+        assert_eq!(parse_amount("1234,56"), Some(1234.56));
+        assert_eq!(parse_amount("1234.5"), Some(1234.5));
+        assert_eq!(parse_amount("1.200"), Some(1200.0));
+        assert_eq!(parse_amount("1.234.567"), Some(1234567.0));
+        assert_eq!(parse_amount(""), None);
+    }
+}
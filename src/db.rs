@@ -0,0 +1,656 @@
+// --- Database ---
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::{
+    backup::Progress, params, Connection, DatabaseName, ErrorCode, OpenFlags, OptionalExtension,
+    Result as DbResult,
+};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use tracing::warn;
+
+use crate::{
+    audit::Source,
+    health::ProviderHealth,
+    i18n::{self, Msg},
+    notify,
+    paths::append_to_path,
+};
+
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub fn open_database(path: &Path) -> Result<Connection, String> {
+    open_checked(path, BUSY_TIMEOUT)
+}
+
+fn open_checked(path: &Path, busy_timeout: Duration) -> Result<Connection, String> {
+    let conn = Connection::open(path)
+        .map_err(|e| format!("Failed to open database {}: {}", path.display(), e))?;
+    // Set before the integrity check so a lock held by another process waits
+    // instead of failing at once.
+    conn.busy_timeout(busy_timeout)
+        .map_err(|e| format!("Failed to set database busy timeout: {}", e))?;
+    // Only a database SQLite calls corrupt is moved aside. Anything else (a
+    // lock that outlasted the timeout, permissions, a full disk) leaves the
+    // file alone and fails, so a healthy history is never thrown away.
+    let integrity = check_integrity(&conn).map_err(|e| {
+        format!(
+            "Could not check database {}: {}. Is another copy of the app or a backup using it?",
+            path.display(),
+            e
+        )
+    })?;
+    match integrity {
+        Integrity::Ok => {
+            configure_connection(&conn)
+                .map_err(|e| format!("Failed to configure database: {}", e))?;
+            initialize_database(&conn)
+                .map_err(|e| format!("Failed to initialize database: {}", e))?;
+            Ok(conn)
+        }
+        Integrity::Corrupt(reason) => {
+            warn!(
+                "Database {} failed integrity check: {}",
+                path.display(),
+                reason
+            );
+            drop(conn);
+            let moved_to = quarantine_database(path)?;
+            let conn = Connection::open(path)
+                .map_err(|e| format!("Failed to recreate database {}: {}", path.display(), e))?;
+            configure_connection(&conn)
+                .map_err(|e| format!("Failed to configure recreated database: {}", e))?;
+            initialize_database(&conn)
+                .map_err(|e| format!("Failed to initialize recreated database: {}", e))?;
+            notify::notify(
+                i18n::tr(Msg::DatabaseResetTitle),
+                &i18n::trf(Msg::DatabaseResetBody, &[&moved_to.display()]),
+            );
+            Ok(conn)
+        }
+    }
+}
+
+// For `--ephemeral`: the same schema with nothing written to disk. Rates live
+// as long as the process, so there is nothing to check or share with readers.
+pub fn open_in_memory() -> Result<Connection, String> {
+    let conn = Connection::open_in_memory()
+        .map_err(|e| format!("Failed to open in-memory database: {}", e))?;
+    initialize_database(&conn).map_err(|e| format!("Failed to initialize database: {}", e))?;
+    Ok(conn)
+}
+
+// WAL lets readers (CLI, API) run while the fetcher writes; the busy timeout
+// covers the remaining writer/writer overlap instead of failing immediately.
+fn configure_connection(conn: &Connection) -> DbResult<()> {
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    let mode: String =
+        conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get(0))?;
+    if !mode.eq_ignore_ascii_case("wal") {
+        warn!("Database journal_mode is '{}', WAL not available", mode);
+    }
+    conn.pragma_update(None, "synchronous", "NORMAL")?;
+    Ok(())
+}
+
+pub fn latest_rate(conn: &Connection, symbol: &str) -> DbResult<Option<f64>> {
+    Ok(latest_quote(conn, symbol)?.map(|(rate, _)| rate))
+}
+
+// The rate with when it was stored; a timestamp that doesn't parse reads as None.
+pub fn latest_quote(
+    conn: &Connection,
+    symbol: &str,
+) -> DbResult<Option<(f64, Option<DateTime<Utc>>)>> {
+    match conn.query_row(
+        "SELECT rate, last_updated FROM quotes WHERE symbol=?1 ORDER BY last_updated DESC LIMIT 1",
+        params![symbol],
+        |row| Ok((row.get::<_, f64>(0)?, row.get::<_, String>(1)?)),
+    ) {
+        Ok((rate, last_updated)) => {
+            let last_updated = DateTime::parse_from_rfc3339(&last_updated)
+                .ok()
+                .map(|ts| ts.with_timezone(&Utc));
+            Ok(Some((rate, last_updated)))
+        }
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+enum Integrity {
+    Ok,
+    Corrupt(String),
+}
+
+// Err is for failures that say nothing about the file's contents.
+fn check_integrity(conn: &Connection) -> Result<Integrity, String> {
+    // integrity_check returns a single "ok" row, or one row per problem found.
+    let problems = conn.prepare("PRAGMA integrity_check").and_then(|mut stmt| {
+        stmt.query_map([], |row| row.get::<_, String>(0))?
+            .collect::<DbResult<Vec<String>>>()
+    });
+    match problems {
+        Ok(problems) if problems.len() == 1 && problems[0] == "ok" => Ok(Integrity::Ok),
+        Ok(problems) => Ok(Integrity::Corrupt(problems.join("; "))),
+        Err(rusqlite::Error::SqliteFailure(error, message))
+            if matches!(
+                error.code,
+                ErrorCode::DatabaseCorrupt | ErrorCode::NotADatabase
+            ) =>
+        {
+            Ok(Integrity::Corrupt(
+                message.unwrap_or_else(|| error.to_string()),
+            ))
+        }
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+// Moves the corrupt file (and any journal/WAL leftovers) out of the way.
+fn quarantine_database(path: &Path) -> Result<PathBuf, String> {
+    let suffix = format!(".corrupt-{}", Utc::now().format("%Y%m%d%H%M%S"));
+    let moved_to = append_to_path(path, &suffix);
+    std::fs::rename(path, &moved_to).map_err(|e| {
+        format!(
+            "Failed to move corrupt database {} aside: {}",
+            path.display(),
+            e
+        )
+    })?;
+    for sidecar in ["-journal", "-wal", "-shm"] {
+        let src = append_to_path(path, sidecar);
+        if src.exists() {
+            let dst = append_to_path(&moved_to, sidecar);
+            if let Err(e) = std::fs::rename(&src, &dst) {
+                warn!("Failed to move {} aside: {}", src.display(), e);
+            }
+        }
+    }
+    Ok(moved_to)
+}
+
+// The quote, its history row and that row's audit source are stored together
+// or not at all.
+pub fn store_quote(conn: &Connection, symbol: &str, rate: f64, source: &Source) -> DbResult<()> {
+    let now = Utc::now();
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "INSERT OR REPLACE INTO quotes VALUES(?1,?2,?3)",
+        params![symbol, rate, now.to_rfc3339()],
+    )?;
+    // Fixed-width UTC timestamps so history rows sort and compare as text.
+    tx.execute(
+        "INSERT INTO history (symbol, rate, recorded_at) VALUES (?1, ?2, ?3)",
+        params![symbol, rate, now.to_rfc3339_opts(SecondsFormat::Secs, true)],
+    )?;
+    tx.execute(
+        "INSERT INTO audit (history_id, provider, endpoint, raw) VALUES (?1, ?2, ?3, ?4)",
+        params![
+            tx.last_insert_rowid(),
+            source.provider,
+            source.endpoint,
+            source.raw
+        ],
+    )?;
+    tx.commit()
+}
+
+// Adds history rows from another tracker, skipping any already stored for the
+// same time. Returns how many were added.
+pub fn import_history(
+    conn: &Connection,
+    symbol: &str,
+    rows: &[(DateTime<Utc>, f64)],
+) -> DbResult<usize> {
+    let tx = conn.unchecked_transaction()?;
+    let mut added = 0;
+    {
+        let mut stmt = tx.prepare(
+            "INSERT INTO history (symbol, rate, recorded_at)
+             SELECT ?1, ?2, ?3 WHERE NOT EXISTS
+                 (SELECT 1 FROM history WHERE symbol = ?1 AND recorded_at = ?3)",
+        )?;
+        for (recorded_at, rate) in rows {
+            let at = recorded_at.to_rfc3339_opts(SecondsFormat::Secs, true);
+            added += stmt.execute(params![symbol, rate, at])?;
+        }
+    }
+    tx.commit()?;
+    Ok(added)
+}
+
+// The last recorded rate at or before `at`, if history goes back that far.
+pub fn rate_at(conn: &Connection, symbol: &str, at: DateTime<Utc>) -> DbResult<Option<f64>> {
+    conn.query_row(
+        "SELECT rate FROM history WHERE symbol = ?1 AND recorded_at <= ?2
+         ORDER BY recorded_at DESC LIMIT 1",
+        params![symbol, at.to_rfc3339_opts(SecondsFormat::Secs, true)],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+// Lowest and highest recorded rate since `since`.
+pub fn range_since(
+    conn: &Connection,
+    symbol: &str,
+    since: DateTime<Utc>,
+) -> DbResult<Option<(f64, f64)>> {
+    conn.query_row(
+        "SELECT MIN(rate), MAX(rate) FROM history WHERE symbol = ?1 AND recorded_at >= ?2",
+        params![symbol, since.to_rfc3339_opts(SecondsFormat::Secs, true)],
+        |row| {
+            Ok(row
+                .get::<_, Option<f64>>(0)?
+                .zip(row.get::<_, Option<f64>>(1)?))
+        },
+    )
+}
+
+pub fn history_since(
+    conn: &Connection,
+    symbol: &str,
+    since: DateTime<Utc>,
+) -> DbResult<Vec<(DateTime<Utc>, f64)>> {
+    let mut stmt = conn.prepare(
+        "SELECT recorded_at, rate FROM history
+         WHERE symbol = ?1 AND recorded_at >= ?2 ORDER BY recorded_at",
+    )?;
+    let rows = stmt.query_map(
+        params![symbol, since.to_rfc3339_opts(SecondsFormat::Secs, true)],
+        |row| Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?)),
+    )?;
+    let mut history = Vec::new();
+    for row in rows {
+        let (recorded_at, rate) = row?;
+        match DateTime::parse_from_rfc3339(&recorded_at) {
+            Ok(ts) => history.push((ts.with_timezone(&Utc), rate)),
+            Err(e) => warn!(
+                "Skipping history row with bad timestamp '{}': {}",
+                recorded_at, e
+            ),
+        }
+    }
+    Ok(history)
+}
+
+// Stored rates since `since` with where each came from. Rates stored before
+// there was an audit table, or imported, have an empty source.
+pub fn audit_since(
+    conn: &Connection,
+    symbol: &str,
+    since: DateTime<Utc>,
+) -> DbResult<Vec<(DateTime<Utc>, f64, Source)>> {
+    let mut stmt = conn.prepare(
+        "SELECT h.recorded_at, h.rate, a.provider, a.endpoint, a.raw
+         FROM history h LEFT JOIN audit a ON a.history_id = h.id
+         WHERE h.symbol = ?1 AND h.recorded_at >= ?2 ORDER BY h.recorded_at",
+    )?;
+    let rows = stmt.query_map(
+        params![symbol, since.to_rfc3339_opts(SecondsFormat::Secs, true)],
+        |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, f64>(1)?,
+                Source {
+                    provider: row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+                    endpoint: row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+                    raw: row.get::<_, Option<String>>(4)?.unwrap_or_default(),
+                },
+            ))
+        },
+    )?;
+    let mut entries = Vec::new();
+    for row in rows {
+        let (recorded_at, rate, source) = row?;
+        match DateTime::parse_from_rfc3339(&recorded_at) {
+            Ok(ts) => entries.push((ts.with_timezone(&Utc), rate, source)),
+            Err(e) => warn!(
+                "Skipping history row with bad timestamp '{}': {}",
+                recorded_at, e
+            ),
+        }
+    }
+    Ok(entries)
+}
+
+// --- Snapshots ---
+// The user's own bookmarks ("paid rent at this rate"): every current quote
+// frozen together with a note.
+pub struct Snapshot {
+    pub taken_at: DateTime<Utc>,
+    pub note: String,
+    pub rates: Vec<(String, f64)>,
+}
+
+pub fn record_snapshot(conn: &Connection, note: &str) -> DbResult<()> {
+    let now = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+    conn.execute(
+        "INSERT INTO snapshots (taken_at, note) VALUES (?1, ?2)",
+        params![now, note],
+    )?;
+    conn.execute(
+        "INSERT INTO snapshot_rates (snapshot_id, symbol, rate)
+         SELECT ?1, symbol, rate FROM quotes",
+        params![conn.last_insert_rowid()],
+    )?;
+    Ok(())
+}
+
+pub fn snapshots_since(conn: &Connection, since: DateTime<Utc>) -> DbResult<Vec<Snapshot>> {
+    let mut stmt = conn.prepare(
+        "SELECT s.id, s.taken_at, s.note, r.symbol, r.rate
+         FROM snapshots s LEFT JOIN snapshot_rates r ON r.snapshot_id = s.id
+         WHERE s.taken_at >= ?1 ORDER BY s.taken_at, s.id, r.symbol",
+    )?;
+    let rows = stmt.query_map(
+        params![since.to_rfc3339_opts(SecondsFormat::Secs, true)],
+        |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, Option<String>>(3)?,
+                row.get::<_, Option<f64>>(4)?,
+            ))
+        },
+    )?;
+    let mut snapshots: Vec<(i64, Snapshot)> = Vec::new();
+    for row in rows {
+        let (id, taken_at, note, symbol, rate) = row?;
+        if snapshots.last().map(|(last, _)| *last) != Some(id) {
+            let Ok(ts) = DateTime::parse_from_rfc3339(&taken_at) else {
+                warn!("Skipping snapshot with bad timestamp '{}'", taken_at);
+                continue;
+            };
+            snapshots.push((
+                id,
+                Snapshot {
+                    taken_at: ts.with_timezone(&Utc),
+                    note,
+                    rates: Vec::new(),
+                },
+            ));
+        }
+        if let (Some((_, snapshot)), Some(symbol), Some(rate)) =
+            (snapshots.last_mut(), symbol, rate)
+        {
+            snapshot.rates.push((symbol, rate));
+        }
+    }
+    Ok(snapshots
+        .into_iter()
+        .map(|(_, snapshot)| snapshot)
+        .collect())
+}
+
+// --- Provider Health ---
+// A copy of the fetching instance's HealthRegistry, replaced after every
+// cycle, so `bcv-tray tui` in another process can show it.
+pub fn store_health(conn: &Connection, health: &BTreeMap<String, ProviderHealth>) -> DbResult<()> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute("DELETE FROM provider_health", [])?;
+    for (symbol, provider) in health {
+        tx.execute(
+            "INSERT INTO provider_health (symbol, attempts, successes, consecutive_failures,
+                total_latency_ms, last_success, last_error)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                symbol,
+                provider.attempts as i64,
+                provider.successes as i64,
+                provider.consecutive_failures,
+                provider.total_latency.as_millis() as i64,
+                provider
+                    .last_success
+                    .map(|ts| ts.to_rfc3339_opts(SecondsFormat::Secs, true)),
+                provider.last_error,
+            ],
+        )?;
+    }
+    tx.commit()
+}
+
+pub fn load_health(conn: &Connection) -> DbResult<BTreeMap<String, ProviderHealth>> {
+    let mut stmt = conn.prepare(
+        "SELECT symbol, attempts, successes, consecutive_failures, total_latency_ms,
+            last_success, last_error
+         FROM provider_health",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        let last_success: Option<String> = row.get(5)?;
+        Ok((
+            row.get::<_, String>(0)?,
+            ProviderHealth {
+                attempts: row.get::<_, i64>(1)? as u64,
+                successes: row.get::<_, i64>(2)? as u64,
+                consecutive_failures: row.get(3)?,
+                total_latency: Duration::from_millis(row.get::<_, i64>(4)? as u64),
+                last_success: last_success
+                    .and_then(|ts| DateTime::parse_from_rfc3339(&ts).ok())
+                    .map(|ts| ts.with_timezone(&Utc)),
+                last_error: row.get(6)?,
+            },
+        ))
+    })?;
+    rows.collect()
+}
+
+// --- Backup and Restore ---
+// SQLite's online backup API copies page by page and retries around the
+// fetcher's writes, so both are safe while the tray app is running.
+pub fn backup_to(conn: &Connection, dest: &Path) -> Result<(), String> {
+    if dest.exists() {
+        return Err(format!("{} already exists", dest.display()));
+    }
+    conn.backup(DatabaseName::Main, dest, None)
+        .map_err(|e| format!("Failed to back up to {}: {}", dest.display(), e))
+}
+
+// Replaces everything in `conn` with the contents of `src`, after checking
+// that `src` is an intact bcv-tray database.
+pub fn restore_from(conn: &mut Connection, src: &Path) -> Result<(), String> {
+    let source = Connection::open_with_flags(src, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| format!("Failed to open {}: {}", src.display(), e))?;
+    match check_integrity(&source) {
+        Ok(Integrity::Ok) => {}
+        Ok(Integrity::Corrupt(reason)) => {
+            return Err(format!("{} is damaged: {}", src.display(), reason));
+        }
+        Err(e) => return Err(format!("Could not check {}: {}", src.display(), e)),
+    }
+    let has_quotes: bool = source
+        .query_row(
+            "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'quotes')",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Failed to read {}: {}", src.display(), e))?;
+    if !has_quotes {
+        return Err(format!("{} is not a bcv-tray database", src.display()));
+    }
+    drop(source);
+    conn.restore(DatabaseName::Main, src, None::<fn(Progress)>)
+        .map_err(|e| format!("Failed to restore from {}: {}", src.display(), e))?;
+    // Backups from older versions may predate the history table.
+    initialize_database(conn).map_err(|e| format!("Failed to upgrade restored database: {}", e))
+}
+
+fn initialize_database(conn: &Connection) -> DbResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS quotes (
+            symbol TEXT PRIMARY KEY, rate REAL NOT NULL, last_updated TEXT NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS history (
+            id INTEGER PRIMARY KEY, symbol TEXT NOT NULL, rate REAL NOT NULL,
+            recorded_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS history_symbol_time ON history (symbol, recorded_at)",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS audit (
+            history_id INTEGER PRIMARY KEY REFERENCES history (id), provider TEXT NOT NULL,
+            endpoint TEXT NOT NULL, raw TEXT NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS snapshots (
+            id INTEGER PRIMARY KEY, taken_at TEXT NOT NULL, note TEXT NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS snapshot_rates (
+            snapshot_id INTEGER NOT NULL REFERENCES snapshots (id), symbol TEXT NOT NULL,
+            rate REAL NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS provider_health (
+            symbol TEXT PRIMARY KEY, attempts INTEGER NOT NULL, successes INTEGER NOT NULL,
+            consecutive_failures INTEGER NOT NULL, total_latency_ms INTEGER NOT NULL,
+            last_success TEXT, last_error TEXT
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("bcv-tray-db-test-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    // A database whose schema still reads fine but whose history pages are
+    // overwritten, so only the integrity check notices.
+    fn write_corrupt_database(path: &Path) {
+        let conn = Connection::open(path).unwrap();
+        initialize_database(&conn).unwrap();
+        let source = Source {
+            provider: "bcv".to_string(),
+            endpoint: "https://www.bcv.org.ve/".to_string(),
+            raw: "99,00".to_string(),
+        };
+        for _ in 0..500 {
+            store_quote(&conn, "bcv", 99.0, &source).unwrap();
+        }
+        let page_size: u64 = conn
+            .query_row("PRAGMA page_size", [], |row| row.get(0))
+            .unwrap();
+        drop(conn);
+        let mut bytes = std::fs::read(path).unwrap();
+        let start = (page_size * 10) as usize;
+        assert!(bytes.len() > start + page_size as usize);
+        bytes[start..start + page_size as usize].fill(0xA5);
+        std::fs::write(path, bytes).unwrap();
+    }
+
+    #[test]
+    fn failed_audit_write_stores_nothing() {
+        let conn = open_in_memory().unwrap();
+        conn.execute("DROP TABLE audit", []).unwrap();
+        let source = Source {
+            provider: "bcv".to_string(),
+            endpoint: "https://www.bcv.org.ve/".to_string(),
+            raw: "36,50".to_string(),
+        };
+
+        assert!(store_quote(&conn, "bcv", 36.5, &source).is_err());
+        assert_eq!(latest_rate(&conn, "bcv").unwrap(), None);
+        let history: i64 = conn
+            .query_row("SELECT COUNT(*) FROM history", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(history, 0);
+    }
+
+    #[test]
+    fn damaged_backup_is_not_restored() {
+        let dir = test_dir("restore");
+        let backup = dir.join("backup.db");
+        write_corrupt_database(&backup);
+        let source = Source {
+            provider: "bcv".to_string(),
+            endpoint: "https://www.bcv.org.ve/".to_string(),
+            raw: "36,50".to_string(),
+        };
+        let mut live = open_in_memory().unwrap();
+        store_quote(&live, "bcv", 36.5, &source).unwrap();
+
+        let err = restore_from(&mut live, &backup).unwrap_err();
+        assert!(err.contains("is damaged"), "{}", err);
+        assert_eq!(latest_rate(&live, "bcv").unwrap(), Some(36.5));
+        let history: i64 = live
+            .query_row("SELECT COUNT(*) FROM history", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(history, 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn corrupt_database_is_quarantined() {
+        let dir = test_dir("quarantine");
+        let path = dir.join("bin.db");
+        write_corrupt_database(&path);
+
+        let conn = open_checked(&path, Duration::from_millis(100)).unwrap();
+        assert_eq!(latest_rate(&conn, "bcv").unwrap(), None);
+        let files: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        assert!(
+            files.iter().any(|name| name.starts_with("bin.db.corrupt-")),
+            "{:?}",
+            files
+        );
+
+        drop(conn);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn locked_database_is_not_quarantined() {
+        let dir = test_dir("locked");
+        let path = dir.join("bin.db");
+        let holder = Connection::open(&path).unwrap();
+        holder
+            .execute_batch("CREATE TABLE t (x); BEGIN EXCLUSIVE; INSERT INTO t VALUES (1);")
+            .unwrap();
+
+        let err = open_checked(&path, Duration::from_millis(100)).unwrap_err();
+        assert!(err.contains("Could not check database"), "{}", err);
+        let files: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        assert!(
+            files.iter().all(|name| !name.contains("corrupt")),
+            "{:?}",
+            files
+        );
+        assert!(path.exists());
+
+        drop(holder);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,41 @@
+// --- Demo Mode ---
+// Synthetic rates for screenshots and UI work without network access. Each
+// symbol drifts slowly around a plausible base value so the icon visibly moves.
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub const UPDATE_INTERVAL: Duration = Duration::from_secs(10);
+
+// (symbol, base rate, relative swing, period in seconds)
+// eth and xmr only show up when configured under `[cryptos]`, usdt_usd with
+// `[depeg]`, zelle, noones
+// and reserve with their own sections.
+const DEMO_RATES: [(&str, f64, f64, f64); 13] = [
+    ("bcv", 178.35, 0.01, 600.0),
+    ("binance", 203.50, 0.03, 240.0),
+    ("satoshi", 1050.0, 0.05, 420.0),
+    ("trm", 4050.0, 0.01, 900.0),
+    ("binance_cop", 4120.0, 0.02, 300.0),
+    ("ars_oficial", 1180.0, 0.01, 900.0),
+    ("binance_ars", 1235.0, 0.03, 300.0),
+    ("zelle", 198.0, 0.02, 360.0),
+    ("noones", 215.0, 0.03, 300.0),
+    ("reserve", 206.0, 0.02, 420.0),
+    ("eth", 2500.0, 0.04, 360.0),
+    ("xmr", 165.0, 0.04, 480.0),
+    ("usdt_usd", 1.0, 0.002, 600.0),
+];
+
+pub fn rate(symbol: &str) -> Result<f64, String> {
+    let (_, base, swing, period) = DEMO_RATES
+        .iter()
+        .find(|(s, ..)| *s == symbol)
+        .ok_or_else(|| format!("No demo rate for '{}'", symbol))?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| e.to_string())?;
+    let t = now.as_secs_f64();
+    // A slow wave plus a little sub-second jitter so consecutive cycles differ.
+    let wave = (t * std::f64::consts::TAU / period).sin();
+    let jitter = (now.subsec_nanos() % 1000) as f64 / 1000.0 - 0.5;
+    Ok(base * (1.0 + swing * wave + swing * 0.1 * jitter))
+}
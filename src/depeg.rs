@@ -0,0 +1,80 @@
+// --- USDT De-peg Monitor ---
+// Every parallel VES quote is in USDT and read as if it were dollars. With
+// `[depeg] enabled = true` the USDT/USD spot price is fetched each cycle and
+// stored like any other rate, and a notification goes out when it strays more
+// than `threshold_pct` from 1. Like `[[alerts]]`, it fires once and re-arms
+// when the price is back within range.
+use rusqlite::Connection;
+use std::sync::Mutex;
+use tracing::{info, warn};
+
+use crate::{
+    config::DepegConfig,
+    db,
+    i18n::{self, Msg},
+    notify,
+};
+
+pub const SYMBOL: &str = "usdt_usd";
+
+pub struct DepegMonitor {
+    config: DepegConfig,
+    triggered: Mutex<bool>,
+}
+
+impl DepegMonitor {
+    pub fn new(config: DepegConfig) -> Self {
+        DepegMonitor {
+            config,
+            triggered: Mutex::new(false),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    pub fn evaluate(&self, conn: &Connection) {
+        if !self.config.enabled {
+            return;
+        }
+        let price = match db::latest_rate(conn, SYMBOL) {
+            Ok(Some(price)) => price,
+            Ok(None) => return,
+            Err(e) => {
+                warn!("De-peg check skipped: {}", e);
+                return;
+            }
+        };
+        let drift = drift_pct(price);
+        let holds = drift.abs() > self.config.threshold_pct;
+        let mut triggered = self.triggered.lock().unwrap_or_else(|p| p.into_inner());
+        if holds && !*triggered {
+            info!("USDT is off its peg: {} USD ({:+.2}%)", price, drift);
+            notify::notify(
+                i18n::tr(Msg::DepegTitle),
+                &i18n::trf(
+                    Msg::DepegBody,
+                    &[&format!("{:.4}", price), &format!("{:+.2}", drift)],
+                ),
+            );
+        }
+        *triggered = holds;
+    }
+}
+
+// Percent away from one dollar.
+fn drift_pct(price: f64) -> f64 {
+    (price - 1.0) * 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drift_is_signed() {
+        assert!((drift_pct(0.985) + 1.5).abs() < 1e-9);
+        assert!((drift_pct(1.002) - 0.2).abs() < 1e-9);
+    }
+}
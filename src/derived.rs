@@ -0,0 +1,191 @@
+// --- Derived Symbols ---
+// Config-defined symbols computed from the latest stored rates after each update
+// cycle and stored like any fetched quote, so the tray, converter and history
+// treat them the same as real sources.
+use rusqlite::Connection;
+use tracing::{info, warn};
+
+use crate::{
+    aggregate,
+    audit::Source,
+    config::Config,
+    db,
+    expr::Expr,
+    fiat,
+    i18n::{self, Msg},
+    notify,
+};
+
+pub const BRECHA_SYMBOL: &str = "brecha";
+// How far the parallel rate sits above the official one, in percent.
+pub const BRECHA_EXPR: &str = "(binance / bcv - 1) * 100";
+// USD sent per transfer when `amount` isn't set.
+const DEFAULT_TRANSFER_AMOUNT: f64 = 100.0;
+
+pub struct DerivedSymbol {
+    pub symbol: String,
+    formula: Formula,
+}
+
+enum Formula {
+    Expr(Expr),
+    Weighted(Vec<(String, f64)>),
+    // Rate at the end of the path, USD sent, and (percent, fixed) per hop.
+    Transfer(String, f64, Vec<(f64, f64)>),
+}
+
+impl Formula {
+    fn symbols(&self) -> Vec<String> {
+        match self {
+            Formula::Expr(expr) => expr.symbols().into_iter().map(String::from).collect(),
+            Formula::Weighted(weights) => weights.iter().map(|(s, _)| s.clone()).collect(),
+            Formula::Transfer(via, ..) => vec![via.clone()],
+        }
+    }
+
+    fn eval(&self, lookup: &impl Fn(&str) -> Option<f64>) -> Result<f64, String> {
+        match self {
+            Formula::Expr(expr) => expr.eval(lookup),
+            Formula::Weighted(weights) => {
+                let values: Vec<(f64, f64)> = weights
+                    .iter()
+                    .filter_map(|(symbol, weight)| Some((lookup(symbol)?, *weight)))
+                    .collect();
+                aggregate::weighted_mean(&values)
+                    .ok_or_else(|| "none of the weighted symbols has a rate".to_string())
+            }
+            Formula::Transfer(via, amount, fees) => {
+                let rate = lookup(via).ok_or_else(|| format!("no rate for '{}' yet", via))?;
+                transfer_rate(rate, *amount, fees)
+            }
+        }
+    }
+}
+
+// `rate` scaled by the share of `amount` that survives every hop's fees.
+fn transfer_rate(rate: f64, amount: f64, fees: &[(f64, f64)]) -> Result<f64, String> {
+    let received = fees.iter().fold(amount, |left, (pct, fixed)| {
+        left * (1.0 - pct / 100.0) - fixed
+    });
+    if received <= 0.0 {
+        return Err(format!("the fees eat all of {} USD", amount));
+    }
+    Ok(rate * received / amount)
+}
+
+// Invalid expressions are reported once here and left out of every cycle.
+pub fn from_config(config: &Config) -> Vec<DerivedSymbol> {
+    let mut derived = Vec::new();
+    // First, so user-defined symbols can build on it within the same cycle.
+    if config.brecha_enabled() {
+        derived.push(DerivedSymbol {
+            symbol: BRECHA_SYMBOL.to_string(),
+            formula: Formula::Expr(
+                Expr::parse(BRECHA_EXPR).expect("built-in brecha expression parses"),
+            ),
+        });
+    }
+    let is_known = |name: &str| {
+        fiat::all_sources().any(|source| source.symbol == name)
+            || (config.brecha_enabled() && name == BRECHA_SYMBOL)
+            || config.derived.contains_key(name)
+            || config.cryptos.contains_key(name)
+            || config.telegram.contains_key(name)
+            || crate::providers::optional_sources().any(|source| source.symbol == name)
+    };
+    for (symbol, derived_config) in &config.derived {
+        let parsed = if let Some(via) = &derived_config.via {
+            let amount = derived_config.amount.unwrap_or(DEFAULT_TRANSFER_AMOUNT);
+            let fees = derived_config
+                .fees
+                .iter()
+                .map(|fee| (fee.pct, fee.fixed))
+                .collect();
+            if amount > 0.0 {
+                Ok(Formula::Transfer(via.clone(), amount, fees))
+            } else {
+                Err("amount must be above 0".to_string())
+            }
+        } else if derived_config.weights.is_empty() {
+            Expr::parse(&derived_config.expr).map(Formula::Expr)
+        } else {
+            let weights = derived_config
+                .weights
+                .iter()
+                .map(|(symbol, weight)| (symbol.clone(), *weight))
+                .collect();
+            Ok(Formula::Weighted(weights))
+        };
+        let parsed = parsed.and_then(|formula| {
+            match formula.symbols().into_iter().find(|name| !is_known(name)) {
+                Some(unknown) => Err(format!("unknown symbol '{}'", unknown)),
+                None => Ok(formula),
+            }
+        });
+        match parsed {
+            Ok(formula) => derived.push(DerivedSymbol {
+                symbol: symbol.clone(),
+                formula,
+            }),
+            Err(e) => {
+                let message = format!("Derived symbol '{}' ignored: {}", symbol, e);
+                warn!("{}", message);
+                notify::notify(i18n::tr(Msg::InvalidDerivedSymbol), &message);
+            }
+        }
+    }
+    derived
+}
+
+// Symbols are evaluated in name order, so one derived symbol can build on
+// another defined before it alphabetically (later ones use last cycle's value).
+pub fn update_all(conn: &Connection, derived: &[DerivedSymbol]) {
+    for item in derived {
+        let lookup = |name: &str| db::latest_rate(conn, name).ok().flatten();
+        let value = match item.formula.eval(&lookup) {
+            Ok(value) => value,
+            Err(e) => {
+                warn!("Derived symbol '{}' not updated: {}", item.symbol, e);
+                continue;
+            }
+        };
+        // The inputs are what a derived rate was read from.
+        let inputs: Vec<String> = item
+            .formula
+            .symbols()
+            .iter()
+            .filter_map(|symbol| Some(format!("{}={}", symbol, lookup(symbol)?)))
+            .collect();
+        let source = Source {
+            provider: "derived".to_string(),
+            endpoint: String::new(),
+            raw: inputs.join(" "),
+        };
+        match db::store_quote(conn, &item.symbol, value, &source) {
+            Ok(()) => info!("Updated {}: {}", item.symbol, value),
+            Err(e) => warn!("Failed to store derived symbol '{}': {}", item.symbol, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transfer_applies_hops_in_order() {
+        // 100 USD, -5.4% and -0.30, then -2%: (94.6 - 0.3) * 0.98 = 92.414.
+        let rate = transfer_rate(200.0, 100.0, &[(5.4, 0.3), (2.0, 0.0)]).unwrap();
+        assert!((rate - 184.828).abs() < 1e-9, "got {}", rate);
+    }
+
+    #[test]
+    fn transfer_without_fees_is_the_rate() {
+        assert_eq!(transfer_rate(200.0, 100.0, &[]), Ok(200.0));
+    }
+
+    #[test]
+    fn transfer_fees_larger_than_amount_fail() {
+        assert!(transfer_rate(200.0, 10.0, &[(0.0, 15.0)]).is_err());
+    }
+}
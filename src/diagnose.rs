@@ -0,0 +1,162 @@
+// --- Self-Test ---
+// `bcv-tray diagnose`: one pass over everything that can break, printed as a
+// report users can paste into a bug report.
+use chrono::Utc;
+use rusqlite::{params, Connection};
+use std::{sync::Arc, time::Instant};
+
+use crate::{config::Config, db, fiat, paths, providers};
+
+struct Report {
+    failures: usize,
+}
+
+impl Report {
+    fn check<T>(
+        &mut self,
+        name: &str,
+        result: Result<T, String>,
+        describe: impl FnOnce(T) -> String,
+    ) {
+        match result {
+            Ok(value) => println!("[PASS] {:<14} {}", name, describe(value)),
+            Err(e) => {
+                self.failures += 1;
+                println!("[FAIL] {:<14} {}", name, e);
+            }
+        }
+    }
+}
+
+pub fn run(config: &Config) -> Result<(), String> {
+    let mut report = Report { failures: 0 };
+    println!("bcv-tray {} self-test", env!("CARGO_PKG_VERSION"));
+
+    let font = crate::load_font(&config.fonts);
+    report.check(
+        "Font",
+        font.as_ref()
+            .map(|(_, name)| name.clone())
+            .map_err(Clone::clone),
+        |name| name,
+    );
+
+    let db_path = paths::database_path();
+    let conn = db_path
+        .clone()
+        .and_then(|path| db::open_database(&path).map(|conn| (path, conn)));
+    match conn {
+        Ok((path, conn)) => {
+            report.check("Database", check_database(&conn), |_| {
+                format!("read/write ok at {}", path.display())
+            });
+            if let Ok((font, _)) = font {
+                let font = Arc::new(font);
+                report.check(
+                    "Icon",
+                    crate::generate_tray_icon_image(&font, &conn, config)
+                        .map_err(|e| e.to_string()),
+                    |render| format!("rendered: {}", render.tooltip),
+                );
+            }
+        }
+        Err(e) => report.check::<()>("Database", Err(e), |_| String::new()),
+    }
+
+    // Every source the tray would fetch, through the same client, proxy, DoH
+    // and header settings as a regular update.
+    match providers::http_client() {
+        Ok(client) => {
+            let cmc_keys = crate::load_cmc_keys();
+            for (name, symbol) in sources() {
+                if providers::uses_cmc(&symbol) && cmc_keys.is_empty() {
+                    println!("[SKIP] {:<14} no CMC API key configured", name);
+                } else {
+                    report.check(
+                        &name,
+                        timed(|| providers::fetch(&symbol, &client, &cmc_keys)),
+                        describe_rate,
+                    );
+                }
+            }
+        }
+        Err(e) => report.check::<()>("HTTP client", Err(e), |_| String::new()),
+    }
+
+    if report.failures == 0 {
+        println!("All checks passed.");
+        Ok(())
+    } else {
+        Err(format!("{} check(s) failed", report.failures))
+    }
+}
+
+// (name, symbol) for the tracked fiats' sources, then the config's own and
+// the optional ones.
+fn sources() -> Vec<(String, String)> {
+    let tracked = fiat::tracked();
+    let fiat_sources = tracked.iter().flat_map(|fiat| {
+        fiat.sources.iter().map(move |source| {
+            let name = if tracked.len() > 1 {
+                format!("{} ({})", source.name, fiat.code)
+            } else {
+                source.name.to_string()
+            };
+            (name, source.symbol.to_string())
+        })
+    });
+    let configured = providers::configured_sources()
+        .iter()
+        .map(|source| (source.label.clone(), source.symbol.clone()));
+    let optional = providers::optional_sources()
+        .map(|source| (source.name.to_string(), source.symbol.to_string()));
+    fiat_sources.chain(configured).chain(optional).collect()
+}
+
+fn timed(fetch: impl FnOnce() -> Result<f64, String>) -> Result<(f64, u128), String> {
+    let started = Instant::now();
+    fetch().map(|rate| (rate, started.elapsed().as_millis()))
+}
+
+fn describe_rate((rate, millis): (f64, u128)) -> String {
+    format!("{:.2} in {} ms", rate, millis)
+}
+
+// Writes inside a transaction that is rolled back, so real data is untouched.
+// Not through db::store_quote, which opens a transaction of its own.
+fn check_database(conn: &Connection) -> Result<(), String> {
+    let tx = conn
+        .unchecked_transaction()
+        .map_err(|e| format!("begin failed: {}", e))?;
+    tx.execute(
+        "INSERT OR REPLACE INTO quotes VALUES ('__diagnose__', 1.0, ?1)",
+        params![Utc::now().to_rfc3339()],
+    )
+    .map_err(|e| format!("write failed: {}", e))?;
+    let rate: f64 = tx
+        .query_row(
+            "SELECT rate FROM quotes WHERE symbol = '__diagnose__'",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("read failed: {}", e))?;
+    tx.rollback()
+        .map_err(|e| format!("rollback failed: {}", e))?;
+    if rate == 1.0 {
+        Ok(())
+    } else {
+        Err(format!("read back {} instead of 1.0", rate))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn database_check_leaves_no_rows() {
+        let conn = db::open_in_memory().unwrap();
+        assert_eq!(check_database(&conn), Ok(()));
+        assert_eq!(db::latest_rate(&conn, "__diagnose__").unwrap(), None);
+    }
+}
@@ -0,0 +1,101 @@
+// --- DNS over HTTPS ---
+// Some ISPs answer DNS queries for exchange and news domains with bogus
+// addresses. With `[dns] doh = true` the providers' host names are looked up
+// through a DoH server's JSON API instead, and fetches connect to those
+// addresses. The server is given by IP so reaching it needs no DNS itself.
+use reqwest::blocking::Client;
+use serde::Deserialize;
+use std::net::IpAddr;
+use tracing::debug;
+
+use crate::redact;
+
+// DNS record types in the answer.
+const TYPE_A: u16 = 1;
+const TYPE_AAAA: u16 = 28;
+
+#[derive(Deserialize)]
+struct DohResponse {
+    #[serde(rename = "Status")]
+    status: u32,
+    #[serde(rename = "Answer", default)]
+    answer: Vec<DohAnswer>,
+}
+
+#[derive(Deserialize)]
+struct DohAnswer {
+    #[serde(rename = "type")]
+    record_type: u16,
+    data: String,
+}
+
+// IPv4 and IPv6 addresses of `host`, IPv4 first.
+pub fn lookup(client: &Client, server: &str, host: &str) -> Result<Vec<IpAddr>, String> {
+    let mut addresses = Vec::new();
+    for record_type in ["A", "AAAA"] {
+        debug!("DoH lookup of {} {} via {}", host, record_type, server);
+        let body = client
+            .get(server)
+            .query(&[("name", host), ("type", record_type)])
+            .header("Accept", "application/dns-json")
+            .send()
+            .and_then(|response| response.error_for_status())
+            .and_then(|response| response.text())
+            .map_err(|e| {
+                format!(
+                    "DoH lookup of {} failed: {}",
+                    host,
+                    redact::redact(&e.to_string())
+                )
+            })?;
+        addresses.extend(parse_answer(&body)?);
+    }
+    if addresses.is_empty() {
+        return Err(format!("DoH server has no address for {}", host));
+    }
+    Ok(addresses)
+}
+
+fn parse_answer(body: &str) -> Result<Vec<IpAddr>, String> {
+    let response: DohResponse =
+        serde_json::from_str(body).map_err(|e| format!("unexpected DoH response: {}", e))?;
+    // 3 is NXDOMAIN; anything but 0 means no usable answer.
+    if response.status != 0 {
+        return Err(format!(
+            "DoH lookup failed with DNS status {}",
+            response.status
+        ));
+    }
+    // CNAME records come along in the answer; only addresses are kept.
+    Ok(response
+        .answer
+        .iter()
+        .filter(|answer| matches!(answer.record_type, TYPE_A | TYPE_AAAA))
+        .filter_map(|answer| answer.data.parse().ok())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_addresses_and_skips_cnames() {
+        let body = r#"{"Status":0,"Answer":[
+            {"name":"p2p.binance.com","type":5,"TTL":60,"data":"d1.cloudfront.net."},
+            {"name":"d1.cloudfront.net","type":1,"TTL":60,"data":"13.32.1.10"},
+            {"name":"d1.cloudfront.net","type":28,"TTL":60,"data":"2600:9000::1"}]}"#;
+        let addresses = parse_answer(body).unwrap();
+        assert_eq!(addresses.len(), 2);
+        assert_eq!(addresses[0].to_string(), "13.32.1.10");
+    }
+
+    #[test]
+    fn no_such_domain_is_an_error() {
+        assert!(parse_answer(r#"{"Status":3}"#).is_err());
+        assert_eq!(
+            parse_answer(r#"{"Status":0}"#).unwrap(),
+            Vec::<IpAddr>::new()
+        );
+    }
+}
@@ -0,0 +1,246 @@
+// --- Arithmetic Expressions ---
+// Just enough for derived symbols: numbers, symbol names, + - * /, unary minus
+// and parentheses with the usual precedence. Parsed once at startup so typos
+// are reported before the first update cycle.
+use std::{iter::Peekable, str::Chars};
+
+#[derive(Debug, Clone)]
+pub enum Expr {
+    Number(f64),
+    Symbol(String),
+    Neg(Box<Expr>),
+    Binary(Box<Expr>, Op, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Op(char),
+    Open,
+    Close,
+}
+
+impl Expr {
+    pub fn parse(source: &str) -> Result<Expr, String> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.sum()?;
+        match parser.tokens.get(parser.pos) {
+            None => Ok(expr),
+            Some(token) => Err(format!("unexpected {:?} in '{}'", token, source)),
+        }
+    }
+
+    // Symbols that must have a rate before this can be evaluated.
+    pub fn symbols(&self) -> Vec<&str> {
+        match self {
+            Expr::Number(_) => Vec::new(),
+            Expr::Symbol(name) => vec![name.as_str()],
+            Expr::Neg(inner) => inner.symbols(),
+            Expr::Binary(lhs, _, rhs) => {
+                let mut symbols = lhs.symbols();
+                symbols.extend(rhs.symbols());
+                symbols
+            }
+        }
+    }
+
+    pub fn eval(&self, lookup: &impl Fn(&str) -> Option<f64>) -> Result<f64, String> {
+        let value = match self {
+            Expr::Number(value) => *value,
+            Expr::Symbol(name) => lookup(name).ok_or_else(|| format!("no rate for '{}'", name))?,
+            Expr::Neg(inner) => -inner.eval(lookup)?,
+            Expr::Binary(lhs, op, rhs) => {
+                let (lhs, rhs) = (lhs.eval(lookup)?, rhs.eval(lookup)?);
+                match op {
+                    Op::Add => lhs + rhs,
+                    Op::Sub => lhs - rhs,
+                    Op::Mul => lhs * rhs,
+                    Op::Div => lhs / rhs,
+                }
+            }
+        };
+        if value.is_finite() {
+            Ok(value)
+        } else {
+            Err("result is not a finite number (division by zero?)".to_string())
+        }
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '0'..='9' | '.' => {
+                let text = take_while(&mut chars, |c| c.is_ascii_digit() || c == '.');
+                let value = text
+                    .parse()
+                    .map_err(|_| format!("invalid number '{}'", text))?;
+                tokens.push(Token::Number(value));
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let name = take_while(&mut chars, |c| c.is_ascii_alphanumeric() || c == '_');
+                tokens.push(Token::Ident(name));
+            }
+            '+' | '-' | '*' | '/' => {
+                chars.next();
+                tokens.push(Token::Op(c));
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            _ => return Err(format!("unexpected character '{}' in '{}'", c, source)),
+        }
+    }
+    Ok(tokens)
+}
+
+fn take_while(chars: &mut Peekable<Chars>, accept: impl Fn(char) -> bool) -> String {
+    let mut text = String::new();
+    while let Some(&c) = chars.peek().filter(|c| accept(**c)) {
+        text.push(c);
+        chars.next();
+    }
+    text
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn peek_op(&self, ops: &[char]) -> Option<char> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Op(c)) if ops.contains(c) => Some(*c),
+            _ => None,
+        }
+    }
+
+    fn sum(&mut self) -> Result<Expr, String> {
+        let mut expr = self.product()?;
+        while let Some(c) = self.peek_op(&['+', '-']) {
+            self.pos += 1;
+            let op = if c == '+' { Op::Add } else { Op::Sub };
+            expr = Expr::Binary(Box::new(expr), op, Box::new(self.product()?));
+        }
+        Ok(expr)
+    }
+
+    fn product(&mut self) -> Result<Expr, String> {
+        let mut expr = self.factor()?;
+        while let Some(c) = self.peek_op(&['*', '/']) {
+            self.pos += 1;
+            let op = if c == '*' { Op::Mul } else { Op::Div };
+            expr = Expr::Binary(Box::new(expr), op, Box::new(self.factor()?));
+        }
+        Ok(expr)
+    }
+
+    fn factor(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Number(value)) => Ok(Expr::Number(value)),
+            Some(Token::Ident(name)) => Ok(Expr::Symbol(name)),
+            Some(Token::Op('-')) => Ok(Expr::Neg(Box::new(self.factor()?))),
+            Some(Token::Open) => {
+                let expr = self.sum()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(expr),
+                    _ => Err("missing ')'".to_string()),
+                }
+            }
+            Some(token) => Err(format!("unexpected {:?}", token)),
+            None => Err("expression ended early".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(source: &str) -> Result<f64, String> {
+        let rates = |name: &str| match name {
+            "a" => Some(2.0),
+            "b" => Some(3.0),
+            "zero" => Some(0.0),
+            _ => None,
+        };
+        Expr::parse(source)?.eval(&rates)
+    }
+
+    #[test]
+    fn products_bind_tighter_than_sums() {
+        assert_eq!(eval("1 + 2 * 3"), Ok(7.0));
+        assert_eq!(eval("8 / 2 / 2"), Ok(2.0));
+        assert_eq!(eval("2 - 3 - 4"), Ok(-5.0));
+    }
+
+    #[test]
+    fn unary_minus_applies_to_the_next_factor() {
+        assert_eq!(
+            format!("{:?}", Expr::parse("-a * b").unwrap()),
+            r#"Binary(Neg(Symbol("a")), Mul, Symbol("b"))"#
+        );
+        assert_eq!(eval("-a * b"), Ok(-6.0));
+        assert_eq!(eval("a - -b"), Ok(5.0));
+    }
+
+    #[test]
+    fn parentheses_group_and_must_close() {
+        assert_eq!(eval("(1 + 2) * 3"), Ok(9.0));
+        assert_eq!(eval("((a))"), Ok(2.0));
+        assert_eq!(Expr::parse("(1 + 2").unwrap_err(), "missing ')'");
+        assert!(Expr::parse("1 + 2)")
+            .unwrap_err()
+            .contains("unexpected Close"));
+        assert_eq!(Expr::parse("1 +").unwrap_err(), "expression ended early");
+    }
+
+    #[test]
+    fn leftover_tokens_and_bad_numbers_are_rejected() {
+        let err = Expr::parse("2x").unwrap_err();
+        assert!(err.contains("unexpected Ident(\"x\")"), "{}", err);
+        assert_eq!(Expr::parse("1.2.3").unwrap_err(), "invalid number '1.2.3'");
+        assert!(Expr::parse("a % b").unwrap_err().contains("'%'"));
+    }
+
+    #[test]
+    fn division_by_zero_and_missing_rates_fail() {
+        let err = eval("a / zero").unwrap_err();
+        assert!(err.contains("division by zero"), "{}", err);
+        assert_eq!(eval("a * c"), Err("no rate for 'c'".to_string()));
+    }
+
+    #[test]
+    fn lists_every_symbol_in_order() {
+        let expr = Expr::parse("(bcv + binance) / 2 - -bcv * 1.5").unwrap();
+        assert_eq!(expr.symbols(), vec!["bcv", "binance", "bcv"]);
+        assert!(Expr::parse("1 + 2").unwrap().symbols().is_empty());
+    }
+}
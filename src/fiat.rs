@@ -0,0 +1,142 @@
+// --- Fiat Profiles ---
+// Each fiat has its own set of sources. All tracked fiats are fetched every
+// cycle; the active one is what the tray, converter and clipboard show.
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    OnceLock,
+};
+use tracing::warn;
+
+pub struct Source {
+    // Short name shown in the tray tooltip and status menu.
+    pub name: &'static str,
+    pub icon: &'static str,
+    // Key in the database and in `providers::fetch`.
+    pub symbol: &'static str,
+    // Where the rate comes from, for the tooltip.
+    pub provider: &'static str,
+    // What the rate is quoted in, per USD.
+    pub unit: &'static str,
+}
+
+pub struct Fiat {
+    pub code: &'static str,
+    pub unit: &'static str,
+    pub sources: &'static [Source],
+}
+
+pub const FIATS: [Fiat; 3] = [
+    Fiat {
+        code: "VES",
+        unit: "Bs",
+        sources: &[
+            Source {
+                name: "BCV",
+                icon: "ved.png",
+                symbol: "bcv",
+                provider: "Banco Central de Venezuela",
+                unit: "Bs",
+            },
+            Source {
+                name: "BIN",
+                icon: "binance.png",
+                symbol: "binance",
+                provider: "Binance P2P",
+                unit: "Bs",
+            },
+            Source {
+                name: "SAT",
+                icon: "satoshi.png",
+                symbol: "satoshi",
+                provider: "CoinMarketCap",
+                unit: "sats",
+            },
+        ],
+    },
+    Fiat {
+        code: "COP",
+        unit: "COP",
+        sources: &[
+            Source {
+                name: "TRM",
+                icon: "cop.png",
+                symbol: "trm",
+                provider: "Banco de la República (TRM)",
+                unit: "COP",
+            },
+            Source {
+                name: "BIN",
+                icon: "binance.png",
+                symbol: "binance_cop",
+                provider: "Binance P2P",
+                unit: "COP",
+            },
+        ],
+    },
+    Fiat {
+        code: "ARS",
+        unit: "ARS",
+        sources: &[
+            Source {
+                name: "OFI",
+                icon: "ars.png",
+                symbol: "ars_oficial",
+                provider: "DolarApi (oficial)",
+                unit: "ARS",
+            },
+            Source {
+                name: "BIN",
+                icon: "binance.png",
+                symbol: "binance_ars",
+                provider: "Binance P2P",
+                unit: "ARS",
+            },
+        ],
+    },
+];
+
+static TRACKED: OnceLock<Vec<&'static Fiat>> = OnceLock::new();
+static ACTIVE: AtomicUsize = AtomicUsize::new(0);
+
+// `codes` comes from the config; unknown codes are skipped and an empty list
+// falls back to VES alone.
+pub fn init(codes: &[String]) {
+    let mut tracked = Vec::new();
+    for code in codes {
+        match find(code) {
+            Some(fiat) if !tracked.iter().any(|f: &&Fiat| f.code == fiat.code) => {
+                tracked.push(fiat)
+            }
+            Some(_) => {}
+            None => warn!("Unknown fiat '{}' in config, ignoring it", code),
+        }
+    }
+    if tracked.is_empty() {
+        tracked.push(&FIATS[0]);
+    }
+    let _ = TRACKED.set(tracked);
+}
+
+fn find(code: &str) -> Option<&'static Fiat> {
+    FIATS.iter().find(|f| f.code.eq_ignore_ascii_case(code))
+}
+
+pub fn tracked() -> &'static [&'static Fiat] {
+    TRACKED.get_or_init(|| vec![&FIATS[0]])
+}
+
+pub fn active() -> &'static Fiat {
+    let tracked = tracked();
+    tracked[ACTIVE.load(Ordering::Relaxed).min(tracked.len() - 1)]
+}
+
+pub fn set_active(code: &str) {
+    if let Some(index) = tracked().iter().position(|f| f.code == code) {
+        ACTIVE.store(index, Ordering::Relaxed);
+    }
+}
+
+// Every symbol any fiat can fetch, tracked or not.
+pub fn all_sources() -> impl Iterator<Item = &'static Source> {
+    FIATS.iter().flat_map(|f| f.sources.iter())
+}
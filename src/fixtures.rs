@@ -0,0 +1,27 @@
+// --- Fixture Recording ---
+// With the `record-fixtures` feature and BCV_TRAY_RECORD_DIR set, every raw
+// provider response is saved so it can be trimmed into tests/fixtures/.
+
+#[cfg(feature = "record-fixtures")]
+pub fn record(provider: &str, extension: &str, body: &str) {
+    const RECORD_DIR_ENV_VAR: &str = "BCV_TRAY_RECORD_DIR";
+
+    let Ok(dir) = std::env::var(RECORD_DIR_ENV_VAR) else {
+        return;
+    };
+    let dir = std::path::PathBuf::from(dir);
+    let path = dir.join(format!(
+        "{}_{}.{}",
+        provider,
+        chrono::Utc::now().format("%Y%m%dT%H%M%S"),
+        extension
+    ));
+    let result = std::fs::create_dir_all(&dir).and_then(|()| std::fs::write(&path, body));
+    match result {
+        Ok(()) => tracing::info!("Recorded {} response to {}", provider, path.display()),
+        Err(e) => tracing::warn!("Failed to record {} response: {}", provider, e),
+    }
+}
+
+#[cfg(not(feature = "record-fixtures"))]
+pub fn record(_provider: &str, _extension: &str, _body: &str) {}
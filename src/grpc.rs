@@ -0,0 +1,426 @@
+// --- gRPC Service ---
+// With the `grpc` feature and `[grpc] enabled = true`, the rates are also
+// served over gRPC for systems that embed the app: `GetRates`, `StreamRates`
+// (the current rates, then again whenever an update cycle changes one) and
+// `Refresh`, mirroring the HTTP API's `/rates`, `/ws` and `POST /refresh`.
+// The messages and routing are written out by hand on prost and tonic, so
+// building needs no protoc; clients can use proto/bcvtray.proto. The API token,
+// when set, is required as `authorization: Bearer <token>` metadata.
+#[cfg(feature = "grpc")]
+pub use server::{publish, spawn};
+
+#[cfg(not(feature = "grpc"))]
+use {
+    crate::config::Config,
+    rusqlite::Connection,
+    std::sync::{Arc, Mutex},
+    tracing::warn,
+};
+
+#[cfg(not(feature = "grpc"))]
+pub fn spawn(_db: Arc<Mutex<Connection>>, _config: Arc<Config>, _refresh: Box<dyn Fn() + Send>) {
+    warn!("[grpc] is enabled, but this build lacks the `grpc` feature");
+}
+
+#[cfg(not(feature = "grpc"))]
+pub fn publish() {}
+
+#[cfg(feature = "grpc")]
+mod server {
+    use rusqlite::Connection;
+    use std::{
+        convert::Infallible,
+        net::SocketAddr,
+        sync::{Arc, Mutex, OnceLock},
+        thread,
+        time::Instant,
+    };
+    use tokio::sync::watch;
+    use tokio_stream::{once, wrappers::WatchStream, StreamExt};
+    use tonic::{
+        body::BoxBody,
+        codec::ProstCodec,
+        codegen::{http, Body, BoxFuture, BoxStream, Context, Poll, Service, StdError},
+        server::{Grpc, NamedService, ServerStreamingService, UnaryService},
+        service::{interceptor::InterceptedService, Interceptor},
+        transport::Server,
+        Request, Response, Status,
+    };
+    use tracing::{info, warn};
+
+    use crate::{api, config::Config, db, fiat};
+
+    // --- Messages (see proto/bcvtray.proto) ---
+    #[derive(Clone, Copy, PartialEq, prost::Message)]
+    pub struct Empty {}
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Rate {
+        #[prost(string, tag = "1")]
+        pub symbol: String,
+        #[prost(string, tag = "2")]
+        pub name: String,
+        #[prost(double, tag = "3")]
+        pub rate: f64,
+        #[prost(string, tag = "4")]
+        pub unit: String,
+        #[prost(string, tag = "5")]
+        pub updated: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct RatesReply {
+        #[prost(string, tag = "1")]
+        pub active_fiat: String,
+        #[prost(message, repeated, tag = "2")]
+        pub rates: Vec<Rate>,
+    }
+
+    #[derive(Clone, Copy, PartialEq, prost::Message)]
+    pub struct RefreshReply {
+        #[prost(bool, tag = "1")]
+        pub started: bool,
+    }
+
+    struct Shared {
+        db: Arc<Mutex<Connection>>,
+        config: Arc<Config>,
+        refresh: Mutex<Box<dyn Fn() + Send>>,
+        last_refresh: Mutex<Option<Instant>>,
+        // Latest rates published; StreamRates subscribers wait on changes.
+        rates: watch::Sender<RatesReply>,
+    }
+
+    static SHARED: OnceLock<Shared> = OnceLock::new();
+
+    // `refresh` runs a manual update, as "Update Now" does.
+    pub fn spawn(db: Arc<Mutex<Connection>>, config: Arc<Config>, refresh: Box<dyn Fn() + Send>) {
+        let address = format!("{}:{}", config.grpc.bind, config.grpc.port);
+        let address: SocketAddr = match address.parse() {
+            Ok(address) => address,
+            Err(e) => {
+                warn!("Could not start gRPC on {}: {}", address, e);
+                return;
+            }
+        };
+        let token = api::api_token(&config);
+        if !address.ip().is_loopback() && token.is_none() {
+            warn!(
+                "gRPC is reachable beyond this machine without a token; \
+                 set one with `bcv-tray secret set api-token`"
+            );
+        }
+        let shared = SHARED.get_or_init(|| {
+            let rates = rates_reply(&db, &config);
+            Shared {
+                db,
+                config,
+                refresh: Mutex::new(refresh),
+                last_refresh: Mutex::new(None),
+                rates: watch::channel(rates).0,
+            }
+        });
+        thread::spawn(move || {
+            let runtime = match tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+            {
+                Ok(runtime) => runtime,
+                Err(e) => {
+                    warn!("Could not start the gRPC runtime: {}", e);
+                    return;
+                }
+            };
+            let service = InterceptedService::new(RatesService(shared), TokenCheck(token));
+            info!("gRPC listening on {}", address);
+            let served = runtime.block_on(Server::builder().add_service(service).serve(address));
+            if let Err(e) = served {
+                warn!("gRPC server stopped: {}", e);
+            }
+        });
+    }
+
+    // Called after every update cycle; wakes StreamRates subscribers if a
+    // rate changed. Timestamps alone changing sends nothing, as on `/ws`.
+    pub fn publish() {
+        let Some(shared) = SHARED.get() else {
+            return;
+        };
+        let reply = rates_reply(&shared.db, &shared.config);
+        shared
+            .rates
+            .send_if_modified(|current| replace_if_changed(current, reply));
+    }
+
+    // Takes `reply` and returns true if any symbol or rate differs.
+    fn replace_if_changed(current: &mut RatesReply, reply: RatesReply) -> bool {
+        let key = |reply: &RatesReply| -> Vec<(String, u64)> {
+            reply
+                .rates
+                .iter()
+                .map(|rate| (rate.symbol.clone(), rate.rate.to_bits()))
+                .collect()
+        };
+        let changed = key(current) != key(&reply);
+        if changed {
+            *current = reply;
+        }
+        changed
+    }
+
+    // Checks the `authorization` metadata against the API token, if one is set.
+    #[derive(Clone)]
+    struct TokenCheck(Option<String>);
+
+    impl Interceptor for TokenCheck {
+        fn call(&mut self, request: Request<()>) -> Result<Request<()>, Status> {
+            let Some(token) = &self.0 else {
+                return Ok(request);
+            };
+            let offered = request
+                .metadata()
+                .get("authorization")
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.strip_prefix("Bearer "));
+            match offered {
+                Some(offered) if api::same_secret(offered.trim(), token) => Ok(request),
+                _ => Err(Status::unauthenticated("missing or wrong token")),
+            }
+        }
+    }
+
+    // Same rates as the HTTP API's `/rates`.
+    fn rates_reply(db: &Mutex<Connection>, config: &Config) -> RatesReply {
+        let conn = db.lock().unwrap_or_else(|p| p.into_inner());
+        let rates = api::symbols(config)
+            .into_iter()
+            .filter_map(|(symbol, name, unit)| {
+                let (rate, updated) = db::latest_quote(&conn, &symbol).ok().flatten()?;
+                Some(Rate {
+                    symbol,
+                    name,
+                    rate,
+                    unit,
+                    updated: updated.map(|ts| ts.to_rfc3339()).unwrap_or_default(),
+                })
+            })
+            .collect();
+        RatesReply {
+            active_fiat: fiat::active().code.to_string(),
+            rates,
+        }
+    }
+
+    fn refresh(shared: &Shared) -> bool {
+        let mut last = shared
+            .last_refresh
+            .lock()
+            .unwrap_or_else(|p| p.into_inner());
+        if last.is_some_and(|at| at.elapsed() < api::REFRESH_COOLDOWN) {
+            return false;
+        }
+        *last = Some(Instant::now());
+        info!("Update requested over gRPC");
+        (shared.refresh.lock().unwrap_or_else(|p| p.into_inner()))();
+        true
+    }
+
+    // --- Service ---
+    // What tonic-build would generate for proto/bcvtray.proto: routes each
+    // method's path to its handler below.
+    #[derive(Clone)]
+    struct RatesService(&'static Shared);
+
+    impl NamedService for RatesService {
+        const NAME: &'static str = "bcvtray.v1.Rates";
+    }
+
+    impl<B> Service<http::Request<B>> for RatesService
+    where
+        B: Body + Send + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        type Response = http::Response<BoxBody>;
+        type Error = Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, request: http::Request<B>) -> Self::Future {
+            let shared = self.0;
+            match request.uri().path() {
+                "/bcvtray.v1.Rates/GetRates" => Box::pin(async move {
+                    let mut grpc = Grpc::new(ProstCodec::default());
+                    Ok(grpc.unary(GetRates(shared), request).await)
+                }),
+                "/bcvtray.v1.Rates/StreamRates" => Box::pin(async move {
+                    let mut grpc = Grpc::new(ProstCodec::default());
+                    Ok(grpc.server_streaming(StreamRates(shared), request).await)
+                }),
+                "/bcvtray.v1.Rates/Refresh" => Box::pin(async move {
+                    let mut grpc = Grpc::new(ProstCodec::default());
+                    Ok(grpc.unary(Refresh(shared), request).await)
+                }),
+                _ => Box::pin(async { Ok(Status::unimplemented("no such method").into_http()) }),
+            }
+        }
+    }
+
+    struct GetRates(&'static Shared);
+
+    impl UnaryService<Empty> for GetRates {
+        type Response = RatesReply;
+        type Future = BoxFuture<Response<RatesReply>, Status>;
+
+        fn call(&mut self, _request: Request<Empty>) -> Self::Future {
+            let shared = self.0;
+            Box::pin(async move { Ok(Response::new(rates_reply(&shared.db, &shared.config))) })
+        }
+    }
+
+    struct StreamRates(&'static Shared);
+
+    impl ServerStreamingService<Empty> for StreamRates {
+        type Response = RatesReply;
+        type ResponseStream = BoxStream<RatesReply>;
+        type Future = BoxFuture<Response<Self::ResponseStream>, Status>;
+
+        fn call(&mut self, _request: Request<Empty>) -> Self::Future {
+            let shared = self.0;
+            Box::pin(async move {
+                // New subscribers get the current rates right away.
+                let changes = WatchStream::from_changes(shared.rates.subscribe());
+                let current = rates_reply(&shared.db, &shared.config);
+                let stream: Self::ResponseStream = Box::pin(once(current).chain(changes).map(Ok));
+                Ok(Response::new(stream))
+            })
+        }
+    }
+
+    struct Refresh(&'static Shared);
+
+    impl UnaryService<Empty> for Refresh {
+        type Response = RefreshReply;
+        type Future = BoxFuture<Response<RefreshReply>, Status>;
+
+        fn call(&mut self, _request: Request<Empty>) -> Self::Future {
+            let started = refresh(self.0);
+            Box::pin(async move { Ok(Response::new(RefreshReply { started })) })
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        fn rate(symbol: &str, rate: f64, updated: &str) -> Rate {
+            Rate {
+                symbol: symbol.to_string(),
+                name: symbol.to_uppercase(),
+                rate,
+                unit: "Bs".to_string(),
+                updated: updated.to_string(),
+            }
+        }
+
+        fn reply(rates: Vec<Rate>) -> RatesReply {
+            RatesReply {
+                active_fiat: "VES".to_string(),
+                rates,
+            }
+        }
+
+        fn shared(refreshes: &'static AtomicUsize) -> &'static Shared {
+            Box::leak(Box::new(Shared {
+                db: Arc::new(Mutex::new(db::open_in_memory().unwrap())),
+                config: Arc::new(Config::default()),
+                refresh: Mutex::new(Box::new(move || {
+                    refreshes.fetch_add(1, Ordering::SeqCst);
+                })),
+                last_refresh: Mutex::new(None),
+                rates: watch::channel(reply(Vec::new())).0,
+            }))
+        }
+
+        fn call(service: &mut RatesService, path: &str, body: &str) -> http::Response<BoxBody> {
+            let request = http::Request::builder()
+                .uri(format!("http://localhost{}", path))
+                .header("content-type", "application/grpc")
+                .body(body.to_string())
+                .unwrap();
+            tokio::runtime::Builder::new_current_thread()
+                .build()
+                .unwrap()
+                .block_on(service.call(request))
+                .unwrap()
+        }
+
+        fn check(token: Option<&str>, offered: Option<&str>) -> Result<(), tonic::Code> {
+            let mut request = Request::new(());
+            if let Some(offered) = offered {
+                request
+                    .metadata_mut()
+                    .insert("authorization", offered.parse().unwrap());
+            }
+            TokenCheck(token.map(str::to_string))
+                .call(request)
+                .map(|_| ())
+                .map_err(|status| status.code())
+        }
+
+        #[test]
+        fn token_check_wants_the_right_bearer_token() {
+            let unauthenticated = Err(tonic::Code::Unauthenticated);
+            assert_eq!(check(Some("s3cret"), None), unauthenticated);
+            assert_eq!(check(Some("s3cret"), Some("Bearer wrong")), unauthenticated);
+            assert_eq!(check(Some("s3cret"), Some("s3cret")), unauthenticated);
+            assert_eq!(check(Some("s3cret"), Some("Bearer s3cret")), Ok(()));
+            assert_eq!(check(None, None), Ok(()));
+        }
+
+        #[test]
+        fn only_rate_changes_are_published() {
+            let (sender, mut receiver) =
+                watch::channel(reply(vec![rate("bcv", 36.5, "2026-10-16T12:00:00Z")]));
+            receiver.mark_unchanged();
+
+            let newer = reply(vec![rate("bcv", 36.5, "2026-10-16T13:00:00Z")]);
+            assert!(!sender.send_if_modified(|current| replace_if_changed(current, newer)));
+            assert!(!receiver.has_changed().unwrap());
+
+            let moved = reply(vec![rate("bcv", 37.0, "2026-10-16T14:00:00Z")]);
+            assert!(sender.send_if_modified(|current| replace_if_changed(current, moved)));
+            assert_eq!(receiver.borrow_and_update().rates[0].rate, 37.0);
+
+            let added = reply(vec![
+                rate("bcv", 37.0, "2026-10-16T14:00:00Z"),
+                rate("binance", 40.0, "2026-10-16T14:00:00Z"),
+            ]);
+            assert!(sender.send_if_modified(|current| replace_if_changed(current, added)));
+            assert_eq!(receiver.borrow().rates.len(), 2);
+        }
+
+        #[test]
+        fn unknown_methods_are_unimplemented() {
+            static REFRESHES: AtomicUsize = AtomicUsize::new(0);
+            let mut service = RatesService(shared(&REFRESHES));
+            let response = call(&mut service, "/bcvtray.v1.Rates/Delete", "");
+            let status = Status::from_header_map(response.headers()).unwrap();
+            assert_eq!(status.code(), tonic::Code::Unimplemented);
+        }
+
+        #[test]
+        fn refresh_is_routed_and_rate_limited() {
+            static REFRESHES: AtomicUsize = AtomicUsize::new(0);
+            let mut service = RatesService(shared(&REFRESHES));
+            // One gRPC frame: not compressed, zero-length `Empty` message.
+            let empty = "\0\0\0\0\0";
+            call(&mut service, "/bcvtray.v1.Rates/Refresh", empty);
+            call(&mut service, "/bcvtray.v1.Rates/Refresh", empty);
+            assert_eq!(REFRESHES.load(Ordering::SeqCst), 1);
+        }
+    }
+}
@@ -0,0 +1,98 @@
+// --- Provider Health ---
+// In-memory counters per provider, reset on restart. The fetching instance
+// mirrors them into the database for `bcv-tray tui`.
+use chrono::{DateTime, Utc};
+use std::{collections::BTreeMap, sync::Mutex, time::Duration};
+
+use crate::{
+    i18n::{self, Msg},
+    timefmt,
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct ProviderHealth {
+    pub attempts: u64,
+    pub successes: u64,
+    pub consecutive_failures: u32,
+    pub total_latency: Duration,
+    pub last_success: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+impl ProviderHealth {
+    pub fn success_rate(&self) -> Option<f64> {
+        (self.attempts > 0).then(|| self.successes as f64 / self.attempts as f64)
+    }
+
+    pub fn average_latency(&self) -> Option<Duration> {
+        (self.attempts > 0).then(|| self.total_latency / self.attempts as u32)
+    }
+
+    pub fn summary(&self) -> String {
+        match (self.success_rate(), self.average_latency()) {
+            (Some(rate), Some(latency)) => {
+                let mut summary = i18n::trf(
+                    Msg::HealthSummary,
+                    &[
+                        &format!("{:.0}", rate * 100.0),
+                        &latency.as_millis(),
+                        &self.consecutive_failures,
+                    ],
+                );
+                if let Some(last_success) = self.last_success {
+                    summary.push_str(", ");
+                    summary.push_str(&i18n::trf(Msg::LastOk, &[&timefmt::relative(last_success)]));
+                }
+                summary
+            }
+            _ => i18n::tr(Msg::NoAttemptsYet).to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct HealthRegistry {
+    providers: Mutex<BTreeMap<String, ProviderHealth>>,
+}
+
+impl HealthRegistry {
+    pub fn from_stored(providers: BTreeMap<String, ProviderHealth>) -> Self {
+        HealthRegistry {
+            providers: Mutex::new(providers),
+        }
+    }
+
+    pub fn record(&self, provider: &str, latency: Duration, result: Result<(), &str>) {
+        let mut providers = self.providers.lock().unwrap_or_else(|p| p.into_inner());
+        let health = providers.entry(provider.to_string()).or_default();
+        health.attempts += 1;
+        health.total_latency += latency;
+        match result {
+            Ok(()) => {
+                health.successes += 1;
+                health.consecutive_failures = 0;
+                health.last_success = Some(Utc::now());
+            }
+            Err(e) => {
+                health.consecutive_failures += 1;
+                health.last_error = Some(e.to_string());
+            }
+        }
+    }
+
+    pub fn all(&self) -> BTreeMap<String, ProviderHealth> {
+        self.providers
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .clone()
+    }
+
+    pub fn get(&self, provider: &str) -> ProviderHealth {
+        self.providers
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .get(provider)
+            .cloned()
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,369 @@
+// --- Localization ---
+// Spanish and English string tables for everything the user sees in the tray,
+// the converter and notifications. Logs and CLI output stay in English so bug
+// reports read the same for everyone.
+use std::{fmt::Display, sync::OnceLock};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lang {
+    En,
+    Es,
+}
+
+static LANG: OnceLock<Lang> = OnceLock::new();
+
+// `setting` is the config value: "auto" (system locale), "es" or "en".
+pub fn init(setting: &str) {
+    let lang = match setting.to_ascii_lowercase().as_str() {
+        "es" => Lang::Es,
+        "en" => Lang::En,
+        _ => {
+            let locale = sys_locale::get_locale().unwrap_or_default();
+            if locale.to_ascii_lowercase().starts_with("es") {
+                Lang::Es
+            } else {
+                Lang::En
+            }
+        }
+    };
+    let _ = LANG.set(lang);
+}
+
+pub fn lang() -> Lang {
+    LANG.get().copied().unwrap_or(Lang::En)
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Msg {
+    // Menu
+    UpdateNow,
+    RefreshOne,
+    PauseUpdates,
+    PausedFor,
+    PauseOneHour,
+    PauseFourHours,
+    PauseUntilTomorrow,
+    ResumeUpdates,
+    RefreshSource,
+    Converter,
+    QuickConversions,
+    MiniWidget,
+    RecordSnapshot,
+    SaveChart,
+    ShareRates,
+    Currency,
+    Profile,
+    DefaultProfile,
+    ProviderStatus,
+    StartAtLogin,
+    OpenLog,
+    BackUpDatabase,
+    ApiKeys,
+    SecretCmcApiKey,
+    SecretGoogleServiceAccount,
+    SecretApiToken,
+    SecretNoonesApi,
+    SetFromClipboard,
+    ClearSecret,
+    CheckForUpdates,
+    DownloadVersion,
+    About,
+    Quit,
+    MeteredReduced,
+    MeteredManual,
+    // Provider health
+    NoAttemptsYet,
+    HealthSummary,
+    LastOk,
+    // Tooltip and icon
+    Loading,
+    ErrorUpdatingRates,
+    NoData,
+    UsdPer,
+    UpdatedAgo,
+    PreviousSession,
+    ParallelFallback,
+    TodayRange,
+    // Spoken (screen reader friendly) rate lines
+    SpokenUp,
+    SpokenDown,
+    SpokenUnchanged,
+    SpokenTrendUp,
+    SpokenTrendDown,
+    SpokenTrendFlat,
+    SpokenSharpMove,
+    SpokenRange,
+    SpokenUpdated,
+    SpokenSource,
+    PastComparison,
+    // Relative times
+    JustNow,
+    MinutesAgo,
+    HoursAgo,
+    DaysAgo,
+    // Notifications
+    CouldNotStart,
+    InvalidConfiguration,
+    NoTrayTitle,
+    NoTrayBody,
+    MemoryOnly,
+    CouldNotOpenLog,
+    CouldNotChangeStartAtLogin,
+    CouldNotOpenConverter,
+    CouldNotSwitchProfile,
+    InvalidDerivedSymbol,
+    DatabaseResetTitle,
+    DatabaseResetBody,
+    AlertAbove,
+    AlertBelow,
+    AlertMoved,
+    AlertNow,
+    DepegTitle,
+    DepegBody,
+    UpdateAvailableTitle,
+    UpdateAvailableBody,
+    UpToDate,
+    CurrentVersion,
+    UpdateCheckFailed,
+    BackupSaved,
+    BackupFailed,
+    SecretSaved,
+    SecretCleared,
+    SecretFailed,
+    SecretNeedsRestart,
+    ClipboardEmpty,
+    ChartSaved,
+    ChartFailed,
+    ChartTitle,
+    RatesCopied,
+    RateCopied,
+    CopyFailed,
+    SnapshotSaved,
+    DailySummaryTitle,
+    WeeklyDigestTitle,
+    WeeklyDigestPeriod,
+    WeeklyDigestColumns,
+    WeeklyDigestLine,
+    WeeklyDigestEmpty,
+    SnapshotFailed,
+    // Converter window
+    ConverterTitle,
+    TypeAnAmount,
+    NoRatesYet,
+    ConverterHelp,
+    // Snapshot window
+    RecordSnapshotTitle,
+    SnapshotPrompt,
+    SnapshotHelp,
+}
+
+pub fn tr(msg: Msg) -> &'static str {
+    let (en, es) = match msg {
+        Msg::UpdateNow => ("Update Now", "Actualizar ahora"),
+        Msg::RefreshOne => ("Update One", "Actualizar una"),
+        Msg::PauseUpdates => ("Pause Updates", "Pausar actualizaciones"),
+        Msg::PausedFor => (
+            "Updates Paused ({} left)",
+            "Actualizaciones en pausa (faltan {})",
+        ),
+        Msg::PauseOneHour => ("For 1 Hour", "Por 1 hora"),
+        Msg::PauseFourHours => ("For 4 Hours", "Por 4 horas"),
+        Msg::PauseUntilTomorrow => ("Until Tomorrow Morning", "Hasta mañana"),
+        Msg::ResumeUpdates => ("Resume Now", "Reanudar ahora"),
+        Msg::RefreshSource => ("Update {}", "Actualizar {}"),
+        Msg::Converter => ("Converter...", "Convertidor..."),
+        Msg::QuickConversions => ("Quick Conversions", "Conversiones rápidas"),
+        Msg::MiniWidget => ("Mini Widget", "Mini widget"),
+        Msg::RecordSnapshot => ("Record Snapshot...", "Guardar instantánea..."),
+        Msg::SaveChart => ("Save Chart as PNG", "Guardar gráfico como PNG"),
+        Msg::ShareRates => ("Share Rates", "Compartir tasas"),
+        Msg::Currency => ("Currency", "Moneda"),
+        Msg::Profile => ("Profile", "Perfil"),
+        Msg::DefaultProfile => ("Default", "Predeterminado"),
+        Msg::ProviderStatus => ("Provider Status", "Estado de las fuentes"),
+        Msg::StartAtLogin => ("Start at Login", "Iniciar con la sesión"),
+        Msg::OpenLog => ("Open Log", "Abrir registro"),
+        Msg::BackUpDatabase => ("Back Up Database", "Respaldar base de datos"),
+        Msg::ApiKeys => ("API Keys", "Claves de API"),
+        Msg::SecretCmcApiKey => ("CoinMarketCap", "CoinMarketCap"),
+        Msg::SecretGoogleServiceAccount => {
+            ("Google Service Account", "Cuenta de servicio de Google")
+        }
+        Msg::SecretApiToken => ("Local API Token", "Token de la API local"),
+        Msg::SecretNoonesApi => ("Noones", "Noones"),
+        Msg::SetFromClipboard => ("Set from Clipboard", "Tomar del portapapeles"),
+        Msg::ClearSecret => ("Clear", "Borrar"),
+        Msg::CheckForUpdates => ("Check for Updates", "Buscar actualizaciones"),
+        Msg::DownloadVersion => ("Download {}...", "Descargar {}..."),
+        Msg::About => ("About", "Acerca de"),
+        Msg::Quit => ("Quit", "Salir"),
+        Msg::MeteredReduced => (
+            "Metered connection: updating less often",
+            "Conexión medida: actualizando con menos frecuencia",
+        ),
+        Msg::MeteredManual => (
+            "Metered connection: use Update Now",
+            "Conexión medida: usa Actualizar ahora",
+        ),
+        Msg::NoAttemptsYet => ("no attempts yet", "sin intentos aún"),
+        Msg::HealthSummary => (
+            "{}% ok, {} ms avg, {} failing",
+            "{}% ok, {} ms prom., {} fallos seguidos",
+        ),
+        Msg::Loading => (
+            "Exchange Rates - Loading...",
+            "Tasas de cambio - Cargando...",
+        ),
+        Msg::ErrorUpdatingRates => ("Error updating rates", "Error al actualizar las tasas"),
+        Msg::NoData => ("No data", "Sin datos"),
+        Msg::UsdPer => ("{} USD per {} {}", "{} USD por {} {}"),
+        Msg::UpdatedAgo => ("Updated {}", "Actualizado {}"),
+        Msg::PreviousSession => (
+            "Rates from the last session, updating...",
+            "Tasas de la sesión anterior, actualizando...",
+        ),
+        Msg::ParallelFallback => (
+            "Parallel rate from {}; no fresh data from {}",
+            "Tasa paralela de {}; sin datos recientes de {}",
+        ),
+        Msg::TodayRange => ("today {}–{}", "hoy {}–{}"),
+        Msg::SpokenUp => ("up {}% in 24 hours", "sube {}% en 24 horas"),
+        Msg::SpokenDown => ("down {}% in 24 hours", "baja {}% en 24 horas"),
+        Msg::SpokenUnchanged => ("unchanged in 24 hours", "sin cambios en 24 horas"),
+        Msg::SpokenTrendUp => ("trending up", "tendencia al alza"),
+        Msg::SpokenTrendDown => ("trending down", "tendencia a la baja"),
+        Msg::SpokenTrendFlat => ("steady", "estable"),
+        Msg::SpokenSharpMove => (
+            "sharp move in the last update",
+            "movimiento brusco en la última actualización",
+        ),
+        Msg::SpokenRange => ("today between {} and {}", "hoy entre {} y {}"),
+        Msg::SpokenUpdated => ("updated {}", "actualizado {}"),
+        Msg::SpokenSource => ("source {}", "fuente {}"),
+        Msg::PastComparison => ("{}: 7d {} · 30d {}", "{}: 7d {} · 30d {}"),
+        Msg::LastOk => ("last ok {}", "último ok {}"),
+        Msg::JustNow => ("just now", "justo ahora"),
+        Msg::MinutesAgo => ("{} min ago", "hace {} min"),
+        Msg::HoursAgo => ("{} h ago", "hace {} h"),
+        Msg::DaysAgo => ("{} d ago", "hace {} d"),
+        Msg::CouldNotStart => ("Could not start", "No se pudo iniciar"),
+        Msg::InvalidConfiguration => ("Invalid configuration", "Configuración inválida"),
+        Msg::NoTrayTitle => ("No system tray available", "No hay bandeja del sistema"),
+        Msg::NoTrayBody => (
+            "Rates will keep updating in the background and are stored in {}. \
+             Install a tray/AppIndicator extension and restart to see the icon.",
+            "Las tasas se seguirán actualizando en segundo plano y se guardan en {}. \
+             Instala una extensión de bandeja/AppIndicator y reinicia para ver el ícono.",
+        ),
+        Msg::MemoryOnly => ("memory only", "memoria únicamente"),
+        Msg::CouldNotOpenLog => ("Could not open log", "No se pudo abrir el registro"),
+        Msg::CouldNotChangeStartAtLogin => (
+            "Could not change Start at Login",
+            "No se pudo cambiar el inicio con la sesión",
+        ),
+        Msg::CouldNotOpenConverter => (
+            "Could not open converter",
+            "No se pudo abrir el convertidor",
+        ),
+        Msg::CouldNotSwitchProfile => ("Could not switch profile", "No se pudo cambiar de perfil"),
+        Msg::InvalidDerivedSymbol => ("Invalid derived symbol", "Símbolo derivado inválido"),
+        Msg::DatabaseResetTitle => (
+            "Rate database was reset",
+            "Se reinició la base de datos de tasas",
+        ),
+        Msg::DatabaseResetBody => (
+            "The database was corrupt and has been moved to {}. Rates will be fetched again.",
+            "La base de datos estaba dañada y se movió a {}. Las tasas se descargarán de nuevo.",
+        ),
+        Msg::AlertAbove => ("{} above {}", "{} por encima de {}"),
+        Msg::AlertBelow => ("{} below {}", "{} por debajo de {}"),
+        Msg::AlertMoved => ("{} moved {}% in {} min", "{} se movió {}% en {} min"),
+        Msg::AlertNow => ("Now at {}", "Ahora en {}"),
+        Msg::DepegTitle => ("USDT is off its peg", "USDT perdió la paridad"),
+        Msg::DepegBody => (
+            "1 USDT = {} USD ({}%). Parallel rates are quoted in USDT.",
+            "1 USDT = {} USD ({}%). Las tasas paralelas se cotizan en USDT.",
+        ),
+        Msg::UpdateAvailableTitle => ("Update available", "Actualización disponible"),
+        Msg::UpdateAvailableBody => (
+            "BCV Tray {} is available (you have {}). Use the tray menu to download it.",
+            "BCV Tray {} está disponible (tienes la {}). Descárgala desde el menú de la bandeja.",
+        ),
+        Msg::UpToDate => ("BCV Tray is up to date", "BCV Tray está al día"),
+        Msg::CurrentVersion => ("You have version {}.", "Tienes la versión {}."),
+        Msg::UpdateCheckFailed => (
+            "Could not check for updates",
+            "No se pudo buscar actualizaciones",
+        ),
+        Msg::BackupSaved => ("Database backed up", "Base de datos respaldada"),
+        Msg::BackupFailed => ("Backup failed", "No se pudo respaldar"),
+        Msg::SecretSaved => ("{} saved", "{} guardado"),
+        Msg::SecretCleared => ("{} cleared", "{} borrado"),
+        Msg::SecretFailed => ("Could not change {}", "No se pudo cambiar {}"),
+        Msg::SecretNeedsRestart => (
+            "Restart BCV Tray to use the change.",
+            "Reinicia BCV Tray para usar el cambio.",
+        ),
+        Msg::ClipboardEmpty => ("The clipboard is empty", "El portapapeles está vacío"),
+        Msg::ChartSaved => ("Chart saved", "Gráfico guardado"),
+        Msg::ChartFailed => ("Could not save the chart", "No se pudo guardar el gráfico"),
+        Msg::ChartTitle => ("{} — last {} days", "{} — últimos {} días"),
+        Msg::RatesCopied => (
+            "Rates copied to the clipboard",
+            "Tasas copiadas al portapapeles",
+        ),
+        Msg::RateCopied => ("Rate copied", "Tasa copiada"),
+        Msg::CopyFailed => (
+            "Could not copy the rates",
+            "No se pudieron copiar las tasas",
+        ),
+        Msg::SnapshotSaved => ("Snapshot saved", "Instantánea guardada"),
+        Msg::DailySummaryTitle => ("Today's rates", "Tasas de hoy"),
+        Msg::WeeklyDigestTitle => ("Weekly digest", "Resumen semanal"),
+        Msg::WeeklyDigestPeriod => ("Week {}", "Semana {}"),
+        Msg::WeeklyDigestColumns => (
+            "Source,Average,Low,High,Change",
+            "Fuente,Promedio,Mínimo,Máximo,Cambio",
+        ),
+        Msg::WeeklyDigestLine => ("{}: avg {} ({}–{}) {}", "{}: prom. {} ({}–{}) {}"),
+        Msg::WeeklyDigestEmpty => ("No history this week.", "Sin historial esta semana."),
+        Msg::SnapshotFailed => (
+            "Could not save snapshot",
+            "No se pudo guardar la instantánea",
+        ),
+        Msg::ConverterTitle => ("BCV Tray Converter", "Convertidor BCV Tray"),
+        Msg::TypeAnAmount => ("Type an amount", "Escribe un monto"),
+        Msg::NoRatesYet => ("No rates available yet", "Aún no hay tasas"),
+        Msg::ConverterHelp => (
+            "Tab: {} -> {}  ·  Esc: close",
+            "Tab: {} -> {}  ·  Esc: cerrar",
+        ),
+        Msg::RecordSnapshotTitle => ("Record snapshot", "Guardar instantánea"),
+        Msg::SnapshotPrompt => (
+            "Note for the current rates:",
+            "Nota para las tasas actuales:",
+        ),
+        Msg::SnapshotHelp => (
+            "Enter: save  ·  Esc: cancel",
+            "Enter: guardar  ·  Esc: cancelar",
+        ),
+    };
+    match lang() {
+        Lang::En => en,
+        Lang::Es => es,
+    }
+}
+
+// `tr` with each `{}` replaced by the next argument, in order.
+pub fn trf(msg: Msg, args: &[&dyn Display]) -> String {
+    let mut parts = tr(msg).split("{}");
+    let mut out = parts.next().unwrap_or_default().to_string();
+    let mut args = args.iter();
+    for part in parts {
+        if let Some(arg) = args.next() {
+            out.push_str(&arg.to_string());
+        }
+        out.push_str(part);
+    }
+    out
+}
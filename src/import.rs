@@ -0,0 +1,292 @@
+// --- History Import ---
+// `bcv-tray import` reads rate history exported by other trackers, as CSV
+// with a header row or as a JSON array of objects. Columns are found by
+// common names (date/fecha, rate/tasa/precio, ...) unless given explicitly,
+// and rows already in the history are skipped, so importing twice is safe.
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use clap::ValueEnum;
+use rusqlite::Connection;
+use serde_json::Value;
+use std::{collections::BTreeMap, fs, path::Path};
+
+use crate::db;
+
+const TIME_COLUMNS: &[&str] = &[
+    "date",
+    "fecha",
+    "time",
+    "timestamp",
+    "datetime",
+    "recorded_at",
+];
+const RATE_COLUMNS: &[&str] = &[
+    "rate", "tasa", "price", "precio", "value", "valor", "usd", "promedio",
+];
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Format {
+    Csv,
+    Json,
+}
+
+pub struct Mapping {
+    // Every row goes to this symbol, unless `symbol_column` is set.
+    pub symbol: Option<String>,
+    pub symbol_column: Option<String>,
+    pub time_column: Option<String>,
+    pub rate_column: Option<String>,
+    // chrono format for the time column when the usual ones don't match.
+    pub time_format: Option<String>,
+    pub delimiter: char,
+}
+
+// Imports `path` and returns rows added per symbol.
+pub fn run(
+    conn: &Connection,
+    path: &Path,
+    format: Option<Format>,
+    mapping: &Mapping,
+) -> Result<BTreeMap<String, usize>, String> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let format = format.unwrap_or(match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) if ext.eq_ignore_ascii_case("json") => Format::Json,
+        _ => Format::Csv,
+    });
+    let records = match format {
+        Format::Csv => csv_records(&text, mapping.delimiter)?,
+        Format::Json => json_records(&text)?,
+    };
+    let mut by_symbol: BTreeMap<String, Vec<(DateTime<Utc>, f64)>> = BTreeMap::new();
+    for (line, record) in records.iter().enumerate() {
+        let (symbol, at, rate) =
+            parse_record(record, mapping).map_err(|e| format!("Record {}: {}", line + 1, e))?;
+        by_symbol.entry(symbol).or_default().push((at, rate));
+    }
+    if by_symbol.is_empty() {
+        return Err(format!("No records found in {}", path.display()));
+    }
+    by_symbol
+        .into_iter()
+        .map(|(symbol, rows)| {
+            db::import_history(conn, &symbol, &rows)
+                .map(|added| (symbol, added))
+                .map_err(|e| format!("Failed to store history: {}", e))
+        })
+        .collect()
+}
+
+// Column names are compared lowercased.
+type Record = BTreeMap<String, String>;
+
+fn parse_record(
+    record: &Record,
+    mapping: &Mapping,
+) -> Result<(String, DateTime<Utc>, f64), String> {
+    let symbol = match (&mapping.symbol_column, &mapping.symbol) {
+        (Some(column), _) => field(record, Some(column), &[])?.to_lowercase(),
+        (None, Some(symbol)) => symbol.clone(),
+        (None, None) => return Err("no --symbol or --symbol-column given".to_string()),
+    };
+    let time = field(record, mapping.time_column.as_ref(), TIME_COLUMNS)?;
+    let at = parse_time(time, mapping.time_format.as_deref())
+        .ok_or_else(|| format!("unrecognized time '{}'", time))?;
+    let rate = field(record, mapping.rate_column.as_ref(), RATE_COLUMNS)?;
+    let rate = parse_rate(rate).ok_or_else(|| format!("unrecognized rate '{}'", rate))?;
+    Ok((symbol, at, rate))
+}
+
+fn field<'a>(
+    record: &'a Record,
+    column: Option<&String>,
+    guesses: &[&str],
+) -> Result<&'a str, String> {
+    let found = match column {
+        Some(column) => record.get(&column.to_lowercase()),
+        None => guesses.iter().find_map(|name| record.get(*name)),
+    };
+    found.map(|value| value.trim()).ok_or_else(|| match column {
+        Some(column) => format!("no column '{}'", column),
+        None => format!("no column named any of {}", guesses.join(", ")),
+    })
+}
+
+// RFC 3339, Unix seconds, or common date and date-time layouts. Times
+// without a zone are local.
+fn parse_time(text: &str, format: Option<&str>) -> Option<DateTime<Utc>> {
+    let local = |naive: NaiveDateTime| {
+        Local
+            .from_local_datetime(&naive)
+            .earliest()
+            .map(|at| at.with_timezone(&Utc))
+    };
+    if let Some(format) = format {
+        return NaiveDateTime::parse_from_str(text, format)
+            .ok()
+            .or_else(|| {
+                NaiveDate::parse_from_str(text, format)
+                    .ok()
+                    .and_then(|d| d.and_hms_opt(0, 0, 0))
+            })
+            .and_then(local);
+    }
+    if let Ok(at) = DateTime::parse_from_rfc3339(text) {
+        return Some(at.with_timezone(&Utc));
+    }
+    if let Ok(seconds) = text.parse::<i64>() {
+        return DateTime::from_timestamp(seconds, 0);
+    }
+    [
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%d %H:%M",
+        "%d/%m/%Y %H:%M:%S",
+        "%d/%m/%Y %H:%M",
+    ]
+    .iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+    .or_else(|| {
+        ["%Y-%m-%d", "%d/%m/%Y", "%d-%m-%Y"]
+            .iter()
+            .find_map(|format| NaiveDate::parse_from_str(text, format).ok())
+            .and_then(|d| d.and_hms_opt(0, 0, 0))
+    })
+    .and_then(local)
+}
+
+// "36.52", "36,52" and "1.234,56" (thousands dots, decimal comma) all work.
+pub fn parse_rate(text: &str) -> Option<f64> {
+    let text = text.trim_start_matches(|c: char| !c.is_ascii_digit() && c != '-');
+    let text = text.trim_end_matches(|c: char| !c.is_ascii_digit());
+    let normalized = match (text.rfind(','), text.rfind('.')) {
+        (Some(comma), Some(dot)) if comma > dot => text.replace('.', "").replace(',', "."),
+        (Some(_), Some(_)) => text.replace(',', ""),
+        (Some(_), None) => text.replace(',', "."),
+        _ => text.to_string(),
+    };
+    normalized
+        .parse()
+        .ok()
+        .filter(|rate: &f64| rate.is_finite() && *rate > 0.0)
+}
+
+// --- Readers ---
+
+fn csv_records(text: &str, delimiter: char) -> Result<Vec<Record>, String> {
+    let mut rows = csv_rows(text.trim_start_matches('\u{feff}'), delimiter).into_iter();
+    let header: Vec<String> = rows
+        .next()
+        .ok_or("The file is empty")?
+        .into_iter()
+        .map(|name| name.trim().to_lowercase())
+        .collect();
+    Ok(rows
+        .filter(|row| row.iter().any(|cell| !cell.trim().is_empty()))
+        .map(|row| header.iter().cloned().zip(row).collect())
+        .collect())
+}
+
+// Splits CSV into rows of fields, honouring double quotes ("" is a quote).
+fn csv_rows(text: &str, delimiter: char) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' => quoted = !quoted,
+            c if c == delimiter && !quoted => row.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    rows
+}
+
+// An array of objects, or an object holding one (e.g. {"history": [...]}).
+fn json_records(text: &str) -> Result<Vec<Record>, String> {
+    let value: Value = serde_json::from_str(text).map_err(|e| format!("Invalid JSON: {}", e))?;
+    let items = match &value {
+        Value::Array(items) => items,
+        Value::Object(map) => map
+            .values()
+            .find_map(Value::as_array)
+            .ok_or("Expected an array of records")?,
+        _ => return Err("Expected an array of records".to_string()),
+    };
+    Ok(items
+        .iter()
+        .filter_map(Value::as_object)
+        .map(|object| {
+            object
+                .iter()
+                .map(|(key, value)| {
+                    let text = match value {
+                        Value::String(text) => text.clone(),
+                        other => other.to_string(),
+                    };
+                    (key.to_lowercase(), text)
+                })
+                .collect()
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rates_in_either_decimal_style() {
+        assert_eq!(parse_rate("36.52"), Some(36.52));
+        assert_eq!(parse_rate("36,52"), Some(36.52));
+        assert_eq!(parse_rate("1.234,56"), Some(1234.56));
+        assert_eq!(parse_rate("1,234.56"), Some(1234.56));
+        assert_eq!(parse_rate("Bs. 36,52"), Some(36.52));
+        assert_eq!(parse_rate("n/a"), None);
+    }
+
+    #[test]
+    fn csv_quotes_and_header_case() {
+        let records = csv_records("Fecha;Tasa\n\"01/02/2024\";\"36,2\"\n", ';').unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0]["fecha"], "01/02/2024");
+        assert_eq!(records[0]["tasa"], "36,2");
+    }
+
+    #[test]
+    fn csv_rows_keep_delimiters_inside_quotes() {
+        let rows = csv_rows("a,b\n\"1,5\",\"say \"\"hi\"\"\"\n", ',');
+        assert_eq!(rows[1], vec!["1,5", "say \"hi\""]);
+    }
+
+    #[test]
+    fn json_nested_array() {
+        let records =
+            json_records(r#"{"history": [{"Date": "2024-02-01", "price": 36.2}]}"#).unwrap();
+        assert_eq!(records[0]["date"], "2024-02-01");
+        assert_eq!(records[0]["price"], "36.2");
+    }
+
+    #[test]
+    fn times_in_common_layouts() {
+        let utc = parse_time("2024-02-01T12:00:00Z", None).unwrap();
+        assert_eq!(utc.to_rfc3339(), "2024-02-01T12:00:00+00:00");
+        assert_eq!(parse_time("1706788800", None), Some(utc));
+        assert!(parse_time("01/02/2024", None).is_some());
+        assert!(parse_time("Feb 1 2024", Some("%b %d %Y")).is_some());
+        assert!(parse_time("yesterday", None).is_none());
+    }
+}
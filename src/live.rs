@@ -0,0 +1,57 @@
+// --- Binance Live Mode ---
+// Binance P2P has no WebSocket feed (and spot has no USDT/VES pair), so "live"
+// means polling the active fiat's Binance source on its own, much shorter
+// interval: `[binance] live_interval_secs`. The icon is re-rendered at most
+// every `live_render_secs`, however often the polls land, and metered and
+// battery settings stretch the interval like they do the regular cycle.
+use std::{
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+use tracing::{debug, info};
+
+use crate::{
+    config::BinanceConfig,
+    fiat, metered, power, providers,
+    update::{self, UpdateContext},
+};
+
+const TRIGGER: &str = "live";
+// Anything faster is asking to be rate limited.
+const MIN_INTERVAL: Duration = Duration::from_secs(5);
+
+pub fn spawn(ctx: Arc<UpdateContext>, config: &BinanceConfig, redraw: impl Fn() + Send + 'static) {
+    if config.live_interval_secs == 0 || !providers::enabled("binance") {
+        return;
+    }
+    let interval = Duration::from_secs(config.live_interval_secs).max(MIN_INTERVAL);
+    let render_cap = Duration::from_secs(config.live_render_secs);
+    info!("Binance live mode: polling every {:?}", interval);
+    thread::spawn(move || {
+        let mut last_redraw: Option<Instant> = None;
+        loop {
+            power::wait(metered::stretch(interval, &ctx.metered), &ctx.power);
+            let Some(symbol) = binance_symbol() else {
+                continue;
+            };
+            if let Err(e) = update::perform_symbol_update(&ctx, symbol, TRIGGER) {
+                debug!("Live {} poll failed: {}", symbol, e);
+                continue;
+            }
+            if last_redraw.is_none_or(|at| at.elapsed() >= render_cap) {
+                last_redraw = Some(Instant::now());
+                redraw();
+            }
+        }
+    });
+}
+
+// The active fiat's Binance P2P source, if it has one.
+fn binance_symbol() -> Option<&'static str> {
+    fiat::active()
+        .sources
+        .iter()
+        .find(|source| source.symbol.starts_with("binance"))
+        .map(|source| source.symbol)
+}
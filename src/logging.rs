@@ -0,0 +1,71 @@
+// --- Logging ---
+use std::path::PathBuf;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+
+use crate::{config::LoggingConfig, paths};
+
+const LOG_FILE_PREFIX: &str = "bcv-tray";
+const LOG_FILE_SUFFIX: &str = "log";
+const MAX_LOG_FILES: usize = 7;
+
+pub fn log_dir() -> Result<PathBuf, String> {
+    Ok(paths::data_dir()?.join("logs"))
+}
+
+pub fn init(config: &LoggingConfig) {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| {
+        EnvFilter::try_new(&config.level).unwrap_or_else(|e| {
+            eprintln!("Invalid log level '{}': {}. Using 'info'.", config.level, e);
+            EnvFilter::new("info")
+        })
+    });
+
+    // Blocking writer on purpose: the event loop exits the process directly,
+    // so a background writer's buffered lines would be lost on quit.
+    let file_layer = match file_appender() {
+        Ok(appender) => Some(fmt::layer().with_writer(appender).with_ansi(false)),
+        Err(e) => {
+            eprintln!("File logging disabled: {}", e);
+            None
+        }
+    };
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer().with_target(false))
+        .with(file_layer)
+        .init();
+}
+
+fn file_appender() -> Result<RollingFileAppender, String> {
+    let dir = log_dir()?;
+    RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(LOG_FILE_PREFIX)
+        .filename_suffix(LOG_FILE_SUFFIX)
+        .max_log_files(MAX_LOG_FILES)
+        .build(&dir)
+        .map_err(|e| format!("could not create log file in {}: {}", dir.display(), e))
+}
+
+// The most recently written log file (names carry the date, so newest sorts last).
+pub fn current_log_file() -> Result<PathBuf, String> {
+    let dir = log_dir()?;
+    let entries = std::fs::read_dir(&dir)
+        .map_err(|e| format!("Could not read log directory {}: {}", dir.display(), e))?;
+    entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with(LOG_FILE_PREFIX) && n.ends_with(LOG_FILE_SUFFIX))
+        })
+        .max()
+        .ok_or_else(|| format!("No log files in {}", dir.display()))
+}
+
+pub fn open_current_log() -> Result<(), String> {
+    let path = current_log_file()?;
+    open::that_detached(&path).map_err(|e| format!("Could not open {}: {}", path.display(), e))
+}
@@ -1,13 +1,11 @@
 #![windows_subsystem = "windows"] // Hide console window on Windows release builds
 
+use ab_glyph::{point, Font, FontVec, PxScale, ScaleFont};
 use image::{load_from_memory, Rgba, RgbaImage};
-use imageproc::drawing::draw_text_mut;
-use rusqlite::{params, Connection, Result as DbResult};
-use rusttype::{Font, Scale};
+use rusqlite::{Connection, Result as DbResult};
 use std::{
     env,
     fmt::Debug,
-    path::PathBuf,
     sync::{Arc, Mutex},
     thread,
     time::Duration,
@@ -17,122 +15,172 @@ use tao::{
     event_loop::{ControlFlow, EventLoopBuilder},
 };
 use tray_icon::{
-    menu::{AboutMetadata, Menu, MenuEvent, MenuItem, PredefinedMenuItem},
-    Icon as TrayIconImage, TrayIcon, TrayIconBuilder, TrayIconEvent,
+    menu::{AboutMetadata, CheckMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu},
+    Icon as TrayIconImage, MouseButton, MouseButtonState, TrayIcon, TrayIconBuilder, TrayIconEvent,
 };
 
-use chrono::Utc;
-use reqwest::blocking::Client;
-// --- MODIFIED ---: Added imports for Serialize and specific headers
-use reqwest::header::{
-    HeaderMap,
-    HeaderValue,
-    ACCEPT,
-    ACCEPT_ENCODING,
-    ACCEPT_LANGUAGE,
-    CACHE_CONTROL,
-    CONNECTION,
-    CONTENT_TYPE,
-    HOST,
-    ORIGIN,
-    PRAGMA,
-    TE,
-    USER_AGENT, // USER_AGENT added for specificity
-};
+use clap::Parser;
+use config::ClickAction;
+use converter::ConverterWindow;
+use health::HealthRegistry;
+use i18n::Msg;
 use rust_embed::RustEmbed;
-use scraper::{Html, Selector}; // For BCV
-use serde::{Deserialize, Serialize}; // --- MODIFIED ---: Added Serialize
+use secrets::SecretName;
+use snapshot::{NoteAction, NoteWindow};
+use tracing::{debug, error, info, warn};
+use update::UpdateContext;
+use widget::WidgetWindow;
+
+mod aggregate;
+mod alerts;
+mod api;
+mod appearance;
+mod attention;
+mod audit;
+mod autostart;
+mod browser;
+mod chart;
+mod cli;
+mod clipboard;
+mod config;
+mod converter;
+mod db;
+mod demo;
+mod depeg;
+mod derived;
+mod diagnose;
+mod doh;
+mod expr;
+mod fiat;
+mod fixtures;
+mod grpc;
+mod health;
+mod i18n;
+mod import;
+mod live;
+mod logging;
+mod metered;
+mod metrics;
+mod notify;
+mod overlay;
+mod parallel;
+mod paths;
+mod power;
+mod providers;
+mod redact;
+mod release_check;
+mod rounding;
+mod schedule;
+mod secrets;
+mod share;
+mod sheets;
+mod snapshot;
+mod snooze;
+mod state;
+mod summary;
+mod taskbar;
+mod text;
+mod timefmt;
+mod tray_host;
+mod trend;
+mod tui;
+mod update;
+mod widget;
+mod xlsx;
 
 // --- Asset Embedding ---
 #[derive(RustEmbed)]
 #[folder = "assets/"]
 struct Assets;
 
+// A file under `assets/` in the config directory replaces the embedded one
+// with the same path (e.g. assets/ved.png, assets/fonts/...), so icons and
+// the font can be reskinned without rebuilding.
+fn asset(key: &str) -> Option<std::borrow::Cow<'static, [u8]>> {
+    if let Ok(dir) = paths::assets_dir() {
+        let path = dir.join(key);
+        match std::fs::read(&path) {
+            Ok(data) => {
+                debug!("Using {} instead of the embedded asset", path.display());
+                return Some(data.into());
+            }
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                warn!("Could not read {}: {}", path.display(), e);
+            }
+            Err(_) => {}
+        }
+    }
+    Assets::get(key).map(|file| file.data)
+}
+
 // --- Configuration ---
 const FONT_PATH: &str = "fonts/RobotoMonoNerdFont-Bold.ttf";
 const ICON_HEIGHT: u32 = 16;
 const PADDING: u32 = 4;
+// Text size relative to ICON_HEIGHT with `[tray] high_contrast`.
+const HIGH_CONTRAST_TEXT_SCALE: f32 = 1.3;
+// One pixel of outline on every side.
+const OUTLINE_OFFSETS: [(i32, i32); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
 const UPDATE_INTERVAL_SECONDS: u64 = 1800;
+// USD amounts in the `[tray] equivalences` tooltip line.
+const EQUIVALENCE_AMOUNTS: [u32; 3] = [1, 10, 100];
+const TICK_INTERVAL: Duration = Duration::from_secs(60);
+// History covered by the tray menu's "Save Chart as PNG".
+const CHART_DAYS: u32 = 30;
 
-const BCV_URL: &str = "https://www.bcv.org.ve/";
-const BCV_CSS_SELECTOR: &str = "html > body > div:nth-of-type(4) > div:nth-of-type(1) > div:nth-of-type(2) > div:nth-of-type(1) > div:nth-of-type(1) > div:nth-of-type(1) > section:nth-of-type(1) > div:nth-of-type(1) > div:nth-of-type(2) > div:nth-of-type(1) > div:nth-of-type(7) > div:nth-of-type(1) > div:nth-of-type(1) > div:nth-of-type(2) > strong";
-
-const BINANCE_P2P_URL: &str = "https://p2p.binance.com/bapi/c2c/v2/friendly/c2c/adv/search";
-
-const CMC_BASE_URL: &str = "https://pro-api.coinmarketcap.com/v2/cryptocurrency/quotes/latest";
-const CMC_BTC_ID: &str = "1";
 const CMC_API_KEY_ENV_VAR: &str = "CMC_PRO_API_KEY";
-const SATS_PER_BTC: f64 = 100_000_000.0;
 
-const CURRENCY_MAPPINGS: [(&str, &str, &str); 3] = [
-    ("BCV", "ved.png", "bcv"),
-    ("BIN", "binance.png", "binance"),
-    ("SAT", "satoshi.png", "satoshi"),
-];
+// Computed rather than fetched, so it is left out of the provider status menu.
+// Only shown while VES is the active fiat, since it compares two VES sources.
+const BRECHA_SOURCE: fiat::Source = fiat::Source {
+    name: "BRECHA",
+    icon: "brecha.png",
+    symbol: derived::BRECHA_SYMBOL,
+    provider: derived::BRECHA_EXPR,
+    unit: "%",
+};
 
 // --- Data Structures ---
 #[derive(Debug, Clone)]
 struct RateInfo {
     currency: String,
+    symbol: String,
     rate: f64,
     icon_asset_path: String,
+    unit: String,
+    last_updated: Option<chrono::DateTime<chrono::Utc>>,
+    // Provider name, or the expression for computed symbols.
+    provider: String,
+    // Percent change against the last stored rate from 24 hours earlier.
+    day_change: Option<f64>,
+    // Lowest and highest rate recorded since local midnight.
+    day_range: Option<(f64, f64)>,
+    // Last stored rates from 7 and 30 days earlier, shown in the converter.
+    week_ago: Option<f64>,
+    month_ago: Option<f64>,
+    // Percent change along the fitted line over `[trend] hours`, when shown.
+    trend: Option<f64>,
+    rounding: rounding::Rounding,
 }
 
-#[derive(Serialize, Debug)]
-#[serde(rename_all = "camelCase")]
-struct BinanceP2PRequestPayload {
-    asset: String,
-    fiat: String,
-    merchant_check: bool,
-    page: u32,
-    pay_types: Vec<String>,
-    publisher_type: Option<String>, // Will be serialized as null if None
-    rows: u32,
-    trade_type: String,
-}
-
-#[derive(Deserialize, Debug)]
-struct BinanceResponse {
-    code: String,
-    // message: Option<String>, // Not strictly needed for price extraction
-    // messageDetail: Option<String>, // Not strictly needed
-    data: Option<Vec<BinanceAdvContainer>>,
-    success: bool,
-}
-
-#[derive(Deserialize, Debug)]
-struct BinanceAdvContainer {
-    adv: BinanceAdv,
-}
-
-#[derive(Deserialize, Debug)]
-struct BinanceAdv {
-    price: String, // Price is a string in the JSON
-                   // ... other fields like advNo, tradeType etc. can be added if needed
-}
-
-// CMC Data Structures (unchanged)
-#[derive(Deserialize, Debug)]
-struct CmcResponse {
-    data: CmcData,
-}
-#[derive(Deserialize, Debug)]
-struct CmcData {
-    #[serde(rename = "1")]
-    btc: BtcQuoteContainer,
-}
-#[derive(Deserialize, Debug)]
-struct BtcQuoteContainer {
-    quote: UsdQuote,
-}
-#[derive(Deserialize, Debug)]
-struct UsdQuote {
-    #[serde(rename = "USD")]
-    usd: PriceInfo,
-}
-#[derive(Deserialize, Debug)]
-struct PriceInfo {
-    price: f64,
+struct TrayRender {
+    icon: TrayIconImage,
+    tooltip: String,
+    // Compact text for hosts that can show a label next to the icon.
+    label: String,
+    // Everything the icon shows, one sentence per rate, for the menu.
+    rate_lines: Vec<String>,
+    // Red-tinted variant for the attention flash; fallback icons have none.
+    flash_icon: Option<TrayIconImage>,
 }
 
 #[allow(dead_code)]
@@ -140,49 +188,142 @@ enum UserEvent {
     TrayIconEvent(tray_icon::TrayIconEvent),
     MenuEvent(tray_icon::menu::MenuEvent),
     UpdateTray,
+    // Periodic redraw so relative times ("5 min ago") stay current.
+    Tick,
+    RebuildTray,
+    // Attention flash phase: true shows the tinted icon, false the normal one.
+    Flash(bool),
+    ReleaseAvailable(release_check::Release),
 }
 
-fn get_database_path() -> Result<PathBuf, String> {
-    dirs::home_dir()
-        .ok_or_else(|| "Could not find home directory".to_string())
-        .map(|mut path| {
-            path.push(".local/share/money/bin.db");
-            path
-        })
+// Startup failures happen before the tray exists, so stderr alone is invisible
+// to anyone who launched the app from a menu or autostart.
+fn exit_with_startup_error(message: &str) -> ! {
+    notify::error_dialog(i18n::tr(Msg::CouldNotStart), message);
+    std::process::exit(1);
+}
+
+// `[fonts] primary`, else the bundled font (or its replacement in the assets
+// folder), else an installed monospace, so a missing or broken font file
+// doesn't keep the app from starting. Returns the font with where it came from.
+fn load_font(fonts: &config::FontsConfig) -> Result<(FontVec, String), String> {
+    if !fonts.primary.is_empty() {
+        match text::read_font(&fonts.primary) {
+            Ok(font) => return Ok((font, fonts.primary.clone())),
+            Err(e) => warn!("Could not load font {}: {}", fonts.primary, e),
+        }
+    }
+    match asset(FONT_PATH) {
+        Some(data) => match FontVec::try_from_vec(data.into_owned()) {
+            Ok(font) => return Ok((font, FONT_PATH.to_string())),
+            Err(_) => warn!("Failed to parse font {}", FONT_PATH),
+        },
+        None => warn!("Font not found: {}", FONT_PATH),
+    }
+    text::system_monospace()
 }
 
 fn main() {
-    let font_file = Assets::get(FONT_PATH)
-        .unwrap_or_else(|| panic!("Critical Error: Embedded font not found: {}", FONT_PATH));
-    let font_data = font_file.data.into_owned();
-    let font = Arc::new(Font::try_from_vec(font_data).expect("Failed to parse embedded font"));
-    println!("Embedded font '{}' loaded successfully.", FONT_PATH);
-
-    let db_path = get_database_path().unwrap_or_else(|e| {
-        eprintln!("Critical Error getting database path: {}", e);
-        std::process::exit(1);
-    });
-    let db_path_str = db_path.to_str().unwrap_or_default().to_string();
-
-    let http_client = Arc::new(
-        Client::builder()
-            .user_agent(
-                // This is the default user agent for the client
-                "Mozilla/5.0 (X11; Ubuntu; Linux x86_64; rv:138.0) Gecko/20100101 Firefox/138.0",
-            )
-            .timeout(Duration::from_secs(15))
-            .danger_accept_invalid_certs(true) // Note: For BCV, might be needed. For Binance, likely not.
-            .build()
-            .expect("Failed to build HTTP client"),
-    );
+    let cli = cli::Cli::parse();
+    if cli.console {
+        cli::attach_console();
+    }
+    let (config, config_error) = match config::load(cli.profile.as_deref()) {
+        Ok(config) => (config, None),
+        Err(e) => (config::Config::default(), Some(e)),
+    };
+    logging::init(&config.logging);
+    i18n::init(&config.language);
+    fiat::init(&config.fiats.tracked);
+    if let Some(code) = state::get().fiat {
+        fiat::set_active(&code);
+    }
+    text::init_fallbacks(&config.fonts.fallback);
+    providers::configure_binance(&config.binance);
+    providers::configure_bcv(&config.bcv);
+    providers::configure_providers(&config.providers);
+    providers::configure_http(&config.http);
+    providers::configure_proxy(&config.proxy);
+    providers::configure_dns(&config.dns);
+    providers::configure_sources(&config);
+    providers::configure_optional(&config.zelle, &config.noones, &config.reserve);
+    check_icons(&config);
+    if let Some(e) = config_error {
+        warn!("{}. Using default configuration.", e);
+        notify::notify(i18n::tr(Msg::InvalidConfiguration), &e);
+    }
+    if let Some(profile) = &cli.profile {
+        info!("Using profile '{}'", profile);
+    }
+    let config = Arc::new(config);
+    redact::set_log_bodies(cli.log_bodies);
+    if let Some(path) = cli.db_path.clone() {
+        paths::set_database_path(path);
+    }
+    if let Some(command) = cli.command {
+        std::process::exit(cli::run(command, &config));
+    }
+    notify::init();
 
-    let cmc_api_key = Arc::new(env::var(CMC_API_KEY_ENV_VAR).unwrap_or_else(|_| {
-        eprintln!(
-            "Warning: Env var {} not set. Satoshi updates will be skipped.",
-            CMC_API_KEY_ENV_VAR
-        );
-        String::new()
-    }));
+    let (font, font_name) =
+        load_font(&config.fonts).unwrap_or_else(|e| exit_with_startup_error(&e));
+    info!("Using font '{}'", font_name);
+    let font = Arc::new(font);
+
+    if cli.demo {
+        info!("Demo mode: using synthetic rates, no network requests will be made.");
+    }
+    // None with --ephemeral: rates are kept in memory only.
+    let db_path = if cli.ephemeral {
+        info!("Ephemeral mode: rates are kept in memory and not saved.");
+        None
+    } else if cli.demo {
+        Some(paths::demo_database_path().unwrap_or_else(|e| exit_with_startup_error(&e)))
+    } else {
+        Some(paths::database_path().unwrap_or_else(|e| exit_with_startup_error(&e)))
+    };
+
+    let http_client = providers::http_client().unwrap_or_else(|e| exit_with_startup_error(&e));
+
+    let cmc_keys = if cli.demo {
+        providers::CmcKeys::default()
+    } else {
+        load_cmc_keys()
+    };
+    cmc_keys.iter().for_each(redact::register_secret);
+
+    let update_interval = match cli.interval {
+        Some(secs) => Duration::from_secs(secs),
+        None if cli.demo => demo::UPDATE_INTERVAL,
+        None => Duration::from_secs(UPDATE_INTERVAL_SECONDS),
+    };
+    let db_conn = match &db_path {
+        Some(path) => db::open_database(path),
+        None => db::open_in_memory(),
+    }
+    .unwrap_or_else(|e| exit_with_startup_error(&e));
+    let db_conn_mutex = Arc::new(Mutex::new(db_conn));
+    let update_ctx = Arc::new(UpdateContext {
+        db: Arc::clone(&db_conn_mutex),
+        http_client,
+        cmc_keys,
+        health: Arc::new(HealthRegistry::default()),
+        demo: cli.demo,
+        derived: derived::from_config(&config),
+        alerts: alerts::AlertEngine::new(config.alerts.clone()),
+        depeg: depeg::DepegMonitor::new(config.depeg.clone()),
+        attention: config.attention.clone(),
+        schedule: schedule::Schedule::from_config(&config.schedule, update_interval),
+        power: config.power.clone(),
+        metered: config.metered.clone(),
+        sheets: sheets::SheetsExporter::new(config.sheets.clone()),
+        pushgateway: metrics::Pushgateway::new(Arc::clone(&config)),
+        overlay: config.overlay.clone(),
+    });
+    // A fetch for scripts; no event loop, tray or background threads needed.
+    if cli.once {
+        std::process::exit(run_once(&update_ctx, &config));
+    }
 
     let event_loop = EventLoopBuilder::<UserEvent>::with_user_event().build();
     let proxy = event_loop.create_proxy();
@@ -202,13 +343,166 @@ fn main() {
     }));
 
     let tray_menu = Menu::new();
-    let quit_i = MenuItem::new("Quit", true, None);
-    let update_now_i = MenuItem::new("Update Now", true, None);
+    let quit_i = MenuItem::new(i18n::tr(Msg::Quit), true, None);
+    let update_now_i = MenuItem::new(i18n::tr(Msg::UpdateNow), true, None);
+    let converter_i = MenuItem::new(i18n::tr(Msg::Converter), true, None);
+    let widget_i = CheckMenuItem::new(i18n::tr(Msg::MiniWidget), true, false, None);
+    let snapshot_i = MenuItem::new(i18n::tr(Msg::RecordSnapshot), true, None);
+    let chart_i = MenuItem::new(i18n::tr(Msg::SaveChart), true, None);
+    let share_i = MenuItem::new(i18n::tr(Msg::ShareRates), true, None);
+    // Filled in on every UpdateTray; the items are informational only.
+    let quick_m = Submenu::new(
+        i18n::tr(Msg::QuickConversions),
+        !config.converter.quick_amounts.is_empty(),
+    );
+    let quick_items: Vec<MenuItem> = config
+        .converter
+        .quick_amounts
+        .iter()
+        .map(|amount| MenuItem::new(format!("{} USD = --", amount), false, None))
+        .collect();
+    for item in &quick_items {
+        let _ = quick_m.append(item);
+    }
+    let autostart_i = CheckMenuItem::new(
+        i18n::tr(Msg::StartAtLogin),
+        true,
+        autostart::is_enabled(),
+        None,
+    );
+    let open_log_i = MenuItem::new(i18n::tr(Msg::OpenLog), true, None);
+    let backup_i = MenuItem::new(i18n::tr(Msg::BackUpDatabase), true, None);
+    // Same as `bcv-tray secret set/clear`; keys are read at startup, so a
+    // change applies after a restart.
+    let secrets_m = Submenu::new(i18n::tr(Msg::ApiKeys), true);
+    let secret_items: Vec<(SecretName, MenuItem, MenuItem)> = SecretName::ALL
+        .iter()
+        .map(|&name| {
+            let set = MenuItem::new(i18n::tr(Msg::SetFromClipboard), true, None);
+            let clear = MenuItem::new(i18n::tr(Msg::ClearSecret), true, None);
+            let submenu = Submenu::new(i18n::tr(secret_label(name)), true);
+            let _ = submenu.append_items(&[&set, &clear]);
+            let _ = secrets_m.append(&submenu);
+            (name, set, clear)
+        })
+        .collect();
+    let release_i = MenuItem::new(i18n::tr(Msg::CheckForUpdates), true, None);
+    let status_m = Submenu::new(i18n::tr(Msg::ProviderStatus), true);
+    let multi_fiat = fiat::tracked().len() > 1;
+    let configured = providers::configured_sources()
+        .iter()
+        .map(|source| (source.label.clone(), source.symbol.as_str()));
+    let optional =
+        providers::optional_sources().map(|source| (source.name.to_string(), source.symbol));
+    let status_items: Vec<(String, &str, MenuItem)> = fiat::tracked()
+        .iter()
+        .flat_map(|f| f.sources.iter().map(move |source| (f.code, source)))
+        .map(|(code, source)| {
+            // Several fiats share source names (BIN), so qualify them.
+            let name = if multi_fiat {
+                format!("{} ({})", source.name, code)
+            } else {
+                source.name.to_string()
+            };
+            (name, source.symbol)
+        })
+        .chain(configured)
+        .chain(optional)
+        .filter(|(_, symbol)| providers::enabled(symbol))
+        .map(|(name, symbol)| {
+            let item = MenuItem::new(
+                format!("{}: {}", name, i18n::tr(Msg::NoAttemptsYet)),
+                false,
+                None,
+            );
+            (name, symbol, item)
+        })
+        .collect();
+    for (_, _, item) in &status_items {
+        let _ = status_m.append(item);
+    }
+    // One item per source that fetches just that one, like Update Now does all.
+    let refresh_m = Submenu::new(i18n::tr(Msg::RefreshOne), true);
+    let refresh_items: Vec<(&str, MenuItem)> = status_items
+        .iter()
+        .map(|(name, symbol, _)| {
+            let text = i18n::trf(Msg::RefreshSource, &[name]);
+            (*symbol, MenuItem::new(text, true, None))
+        })
+        .collect();
+    for (_, item) in &refresh_items {
+        let _ = refresh_m.append(item);
+    }
+    let pause_m = Submenu::new(i18n::tr(Msg::PauseUpdates), true);
+    let pause_items = [
+        (Msg::PauseOneHour, snooze::Length::Hours(1)),
+        (Msg::PauseFourHours, snooze::Length::Hours(4)),
+        (Msg::PauseUntilTomorrow, snooze::Length::UntilTomorrow),
+    ]
+    .map(|(text, length)| (length, MenuItem::new(i18n::tr(text), true, None)));
+    let resume_i = MenuItem::new(i18n::tr(Msg::ResumeUpdates), false, None);
+    for (_, item) in &pause_items {
+        let _ = pause_m.append(item);
+    }
+    let _ = pause_m.append_items(&[&PredefinedMenuItem::separator(), &resume_i]);
+    // Whether the menu last showed a pause, to notice when it runs out.
+    let mut snoozed = false;
+    let fiat_m = Submenu::new(i18n::tr(Msg::Currency), multi_fiat);
+    let fiat_items: Vec<(&str, CheckMenuItem)> = fiat::tracked()
+        .iter()
+        .map(|f| {
+            let active = f.code == fiat::active().code;
+            (f.code, CheckMenuItem::new(f.code, true, active, None))
+        })
+        .collect();
+    for (_, item) in &fiat_items {
+        let _ = fiat_m.append(item);
+    }
+    // Switching restarts the app with `--profile`, so every thread picks up
+    // the new sources, formatting and alerts. `None` is the plain config.toml.
+    let profile_names = config::profiles();
+    let profile_m = Submenu::new(i18n::tr(Msg::Profile), !profile_names.is_empty());
+    let profile_items: Vec<(Option<String>, CheckMenuItem)> = std::iter::once(None)
+        .chain(profile_names.into_iter().map(Some))
+        .map(|name| {
+            let label = name.as_deref().unwrap_or(i18n::tr(Msg::DefaultProfile));
+            let active = name == cli.profile;
+            let item = CheckMenuItem::new(label, true, active, None);
+            (name, item)
+        })
+        .collect();
+    for (_, item) in &profile_items {
+        let _ = profile_m.append(item);
+    }
+    // Only in the menu while the connection is metered; see the UpdateTray handler.
+    let metered_text = match config.metered.mode {
+        config::MeteredMode::Manual => Msg::MeteredManual,
+        _ => Msg::MeteredReduced,
+    };
+    let metered_i = MenuItem::new(i18n::tr(metered_text), false, None);
+    let mut metered_shown = false;
     let _ = tray_menu.append_items(&[
         &update_now_i,
+        &refresh_m,
+        &pause_m,
+        &converter_i,
+        &quick_m,
+        &widget_i,
+        &snapshot_i,
+        &share_i,
+        &chart_i,
+        &PredefinedMenuItem::separator(),
+        &fiat_m,
+        &profile_m,
+        &status_m,
+        &autostart_i,
+        &open_log_i,
+        &backup_i,
+        &secrets_m,
+        &release_i,
         &PredefinedMenuItem::separator(),
         &PredefinedMenuItem::about(
-            None,
+            Some(i18n::tr(Msg::About)),
             Some(AboutMetadata {
                 // --- MODIFIED ---: Updated name to include Binance
                 name: Some("BCV/DOR/SAT/BIN Tray".to_string()),
@@ -219,393 +513,1073 @@ fn main() {
         &PredefinedMenuItem::separator(),
         &quit_i,
     ]);
+    // Filled with one line per rate on every UpdateTray, above this separator.
+    let mut rate_items: Vec<MenuItem> = Vec::new();
+    if config.tray.rates_in_menu {
+        let _ = tray_menu.prepend(&PredefinedMenuItem::separator());
+    }
 
     let mut tray_icon: Option<TrayIcon> = None;
+    // Kept so the tray can be rebuilt with its current look after a host restart.
+    let mut last_icon: Option<TrayIconImage> = None;
+    let mut last_tooltip = i18n::tr(Msg::Loading).to_string();
+    let mut last_label: Option<String> = None;
+    let mut flash_icon: Option<TrayIconImage> = None;
+    let mut headless = false;
+    let mut converter: Option<ConverterWindow> = None;
+    let mut widget: Option<WidgetWindow> = None;
+    let mut snapshot_note: Option<NoteWindow> = None;
+    let mut available_release: Option<release_check::Release> = None;
 
-    let db_conn = Connection::open(&db_path_str).expect("Failed to open database");
-    initialize_database(&db_conn).expect("Failed to initialize database table");
-    let db_conn_mutex = Arc::new(Mutex::new(db_conn));
+    let proxy_clone_appearance = proxy.clone();
+    appearance::spawn_watcher(move || {
+        proxy_clone_appearance
+            .send_event(UserEvent::UpdateTray)
+            .ok();
+    });
+
+    let proxy_clone_host = proxy.clone();
+    tray_host::spawn_watcher(move || {
+        proxy_clone_host.send_event(UserEvent::RebuildTray).ok();
+    });
+
+    if config.release_check.enabled && !cli.demo {
+        let proxy_clone_release = proxy.clone();
+        thread::spawn(move || loop {
+            match release_check::check_latest() {
+                Ok(Some(release)) => {
+                    proxy_clone_release
+                        .send_event(UserEvent::ReleaseAvailable(release))
+                        .ok();
+                }
+                Ok(None) => debug!("No newer release available."),
+                Err(e) => warn!("{}", e),
+            }
+            thread::sleep(release_check::CHECK_INTERVAL);
+        });
+    }
+
+    // Runs a manual update off the caller's thread, for the API and gRPC.
+    let remote_refresh = |origin: &'static str| -> Box<dyn Fn() + Send> {
+        let proxy_remote = proxy.clone();
+        let update_ctx_remote = Arc::clone(&update_ctx);
+        Box::new(move || {
+            let proxy_remote = proxy_remote.clone();
+            let update_ctx_remote = Arc::clone(&update_ctx_remote);
+            thread::spawn(move || {
+                if let Err(e) = update::perform_data_update(&update_ctx_remote, "manual") {
+                    warn!("{} Update: Data update process failed: {}", origin, e);
+                }
+                proxy_remote.send_event(UserEvent::UpdateTray).ok();
+            });
+        })
+    };
+
+    if config.api.enabled {
+        api::spawn(
+            Arc::clone(&db_conn_mutex),
+            Arc::clone(&config),
+            Arc::clone(&update_ctx.health),
+            remote_refresh("API"),
+        );
+    }
 
+    if config.grpc.enabled {
+        grpc::spawn(
+            Arc::clone(&db_conn_mutex),
+            Arc::clone(&config),
+            remote_refresh("gRPC"),
+        );
+    }
+
+    if config.clipboard.watch {
+        clipboard::spawn_watcher(Arc::clone(&db_conn_mutex), Arc::clone(&config));
+    }
+
+    if config.summary.daily || config.summary.weekly {
+        summary::spawn(Arc::clone(&db_conn_mutex), Arc::clone(&config));
+    }
+
+    // Random delays so installs started together (or at the top of the hour)
+    // don't all hit the sources in the same second. None in demo mode.
+    let (initial_offset, cycle_jitter) = if cli.demo {
+        (Duration::ZERO, Duration::ZERO)
+    } else {
+        (
+            Duration::from_secs(config.schedule.initial_offset_secs),
+            Duration::from_secs(config.schedule.jitter_secs),
+        )
+    };
+
+    // Demo rates ignore `[schedule] intervals`.
+    let cycle_interval = if cli.demo {
+        update_interval
+    } else {
+        update_ctx.schedule.cycle_interval()
+    };
+    // The initial trigger below does the first fetch.
     let proxy_clone_update = proxy.clone();
-    let db_conn_mutex_bg = Arc::clone(&db_conn_mutex);
-    let http_client_bg = Arc::clone(&http_client);
-    let cmc_api_key_bg = Arc::clone(&cmc_api_key);
+    let update_ctx_bg = Arc::clone(&update_ctx);
     thread::spawn(move || loop {
-        println!("Background Task: Triggering data update...");
-        match perform_data_update(&db_conn_mutex_bg, &http_client_bg, &cmc_api_key_bg) {
-            Ok(_) => println!("Background Task: Data update process completed."),
-            Err(e) => eprintln!("Background Task: Data update process failed: {}", e),
+        let interval = metered::stretch(cycle_interval, &update_ctx_bg.metered);
+        power::wait(
+            interval + schedule::jitter(cycle_jitter),
+            &update_ctx_bg.power,
+        );
+        info!("Background Task: Triggering data update...");
+        match update::perform_data_update(&update_ctx_bg, schedule::BACKGROUND_TRIGGER) {
+            Ok(_) => info!("Background Task: Data update process completed."),
+            Err(e) => warn!("Background Task: Data update process failed: {}", e),
         }
         proxy_clone_update.send_event(UserEvent::UpdateTray).ok();
-        thread::sleep(Duration::from_secs(UPDATE_INTERVAL_SECONDS));
     });
 
+    if update_ctx.schedule.smart_window() && !cli.demo {
+        let proxy_clone_window = proxy.clone();
+        let update_ctx_window = Arc::clone(&update_ctx);
+        thread::spawn(move || loop {
+            thread::sleep(schedule::WINDOW_POLL_INTERVAL);
+            if !update_ctx_window.schedule.in_bcv_window(chrono::Utc::now()) {
+                continue;
+            }
+            if let Err(e) = update::perform_symbol_update(
+                &update_ctx_window,
+                schedule::BCV_SYMBOL,
+                schedule::WINDOW_TRIGGER,
+            ) {
+                warn!("BCV window check failed: {}", e);
+            }
+            proxy_clone_window.send_event(UserEvent::UpdateTray).ok();
+        });
+    }
+
+    if !cli.demo {
+        let proxy_clone_live = proxy.clone();
+        live::spawn(Arc::clone(&update_ctx), &config.binance, move || {
+            proxy_clone_live.send_event(UserEvent::UpdateTray).ok();
+        });
+    }
+
     let proxy_clone_init = proxy.clone();
-    let db_conn_mutex_init = Arc::clone(&db_conn_mutex);
-    let http_client_init = Arc::clone(&http_client);
-    let cmc_api_key_init = Arc::clone(&cmc_api_key);
+    let update_ctx_init = Arc::clone(&update_ctx);
     thread::spawn(move || {
-        thread::sleep(Duration::from_secs(2));
-        println!("Initial Trigger: Triggering data update...");
-        match perform_data_update(&db_conn_mutex_init, &http_client_init, &cmc_api_key_init) {
-            Ok(_) => println!("Initial Trigger: Data update process completed."),
-            Err(e) => eprintln!("Initial Trigger: Data update process failed: {}", e),
+        thread::sleep(Duration::from_secs(2) + schedule::jitter(initial_offset));
+        info!("Initial Trigger: Triggering data update...");
+        match update::perform_data_update(&update_ctx_init, "initial") {
+            Ok(_) => info!("Initial Trigger: Data update process completed."),
+            Err(e) => warn!("Initial Trigger: Data update process failed: {}", e),
         }
         proxy_clone_init.send_event(UserEvent::UpdateTray).ok();
     });
 
+    let proxy_clone_tick = proxy.clone();
+    thread::spawn(move || loop {
+        thread::sleep(TICK_INTERVAL);
+        proxy_clone_tick.send_event(UserEvent::Tick).ok();
+    });
+
     let font_clone_main_loop = Arc::clone(&font);
-    event_loop.run(move |event, _, control_flow| {
+    event_loop.run(move |event, event_loop_target, control_flow| {
         *control_flow = ControlFlow::Wait;
         match event {
             Event::NewEvents(tao::event::StartCause::Init) => {
-                println!("App started, creating initial placeholder tray icon...");
-                let initial_icon = create_fallback_icon(&font_clone_main_loop, "...");
-                tray_icon = Some(
-                    TrayIconBuilder::new()
-                        .with_menu(Box::new(tray_menu.clone()))
-                        .with_tooltip("Exchange Rates - Loading...")
-                        .with_icon(initial_icon)
-                        .build()
-                        .expect("Failed to build tray icon"),
-                );
-                println!("Placeholder tray icon created.");
+                if cli.no_tray {
+                    info!("Running without a tray icon (--no-tray).");
+                    headless = true;
+                } else {
+                    // The previous session's rates, dimmed, rather than a placeholder
+                    // while the first fetch runs; "..." only on a first ever start.
+                    let previous = {
+                        let db_guard = db_conn_mutex.lock().unwrap_or_else(|p| p.into_inner());
+                        generate_tray_icon_image(&font_clone_main_loop, &db_guard, &config)
+                    }
+                    .ok()
+                    .filter(|render| !render.rate_lines.is_empty());
+                    let initial_icon = match previous {
+                        Some(render) => {
+                            info!("App started, showing the last known rates...");
+                            last_tooltip = render.tooltip;
+                            render.icon
+                        }
+                        None => {
+                            info!("App started, creating initial placeholder tray icon...");
+                            create_fallback_icon(&font_clone_main_loop, "...")
+                        }
+                    };
+                    last_icon = Some(initial_icon.clone());
+                    match build_tray_icon(&tray_menu, initial_icon, &last_tooltip) {
+                        Ok(tray) => {
+                            tray_icon = Some(tray);
+                            info!("Initial tray icon created.");
+                            // Fills in the label, menu lines and widget from the same rates.
+                            proxy.send_event(UserEvent::UpdateTray).ok();
+                        }
+                        Err(e) => {
+                            // e.g. stock GNOME without an AppIndicator extension. Keep
+                            // running so the database stays current for other consumers.
+                            warn!("Failed to build tray icon: {}. Running headless.", e);
+                            headless = true;
+                            let stored_in = db_path
+                                .as_ref()
+                                .map(|path| path.display().to_string())
+                                .unwrap_or_else(|| i18n::tr(Msg::MemoryOnly).to_string());
+                            notify::notify(
+                                i18n::tr(Msg::NoTrayTitle),
+                                &i18n::trf(Msg::NoTrayBody, &[&stored_in]),
+                            );
+                        }
+                    }
+                }
+                let taskbar_badge = cfg!(target_os = "windows") && config.tray.taskbar_badge;
+                // Headless after a failed tray gets the widget so the rates stay visible.
+                let no_tray_host = headless && !cli.no_tray;
+                if config.widget.show || state::get().widget_open || no_tray_host || taskbar_badge {
+                    widget = open_widget(
+                        event_loop_target,
+                        &font_clone_main_loop,
+                        &db_conn_mutex,
+                        &config,
+                    );
+                    widget_i.set_checked(widget.is_some());
+                }
+                request_macos_redraw();
+            }
+            Event::UserEvent(UserEvent::RebuildTray) => {
+                // A host showing up later also lets a headless session get its icon.
+                if cli.no_tray || (tray_icon.is_none() && !headless) {
+                    return;
+                }
+                let Some(icon) = last_icon.clone() else {
+                    return;
+                };
+                // Drop the stale registration first so the host doesn't see two items.
+                tray_icon.take();
+                match build_tray_icon(&tray_menu, icon, &last_tooltip) {
+                    Ok(tray) => {
+                        tray.set_title(last_label.as_deref());
+                        tray_icon = Some(tray);
+                        headless = false;
+                        info!("Tray icon re-registered with the tray host.");
+                    }
+                    Err(e) => warn!("Failed to re-register tray icon: {}", e),
+                }
                 request_macos_redraw();
             }
-            Event::UserEvent(UserEvent::UpdateTray) => {
-                println!("Received UpdateTray event. Generating new icon...");
+            Event::UserEvent(UserEvent::Flash(on)) => {
+                let icon = if on { &flash_icon } else { &last_icon };
+                if let (Some(tray), Some(icon)) = (tray_icon.as_mut(), icon) {
+                    if let Err(e) = tray.set_icon(Some(icon.clone())) {
+                        warn!("Failed to set tray icon: {}", e);
+                    }
+                    request_macos_redraw();
+                }
+            }
+            // Nothing visible to refresh; the headless log line is per update only.
+            Event::UserEvent(UserEvent::Tick) if headless => {}
+            Event::UserEvent(UserEvent::UpdateTray | UserEvent::Tick) => {
+                debug!("Received UpdateTray event. Generating new icon...");
+                if metered::is_metered() != metered_shown {
+                    metered_shown = !metered_shown;
+                    let _ = if metered_shown {
+                        tray_menu.insert(&metered_i, 0)
+                    } else {
+                        tray_menu.remove(&metered_i)
+                    };
+                }
+                let until = snooze::until();
+                if snoozed && until.is_none() {
+                    info!("Pause over, resuming updates");
+                    update_in_background(&update_ctx, &proxy, snooze::RESUME_TRIGGER);
+                }
+                snoozed = until.is_some();
+                match until {
+                    Some(until) => pause_m.set_text(i18n::trf(
+                        Msg::PausedFor,
+                        &[&snooze::remaining(until, chrono::Utc::now())],
+                    )),
+                    None => pause_m.set_text(i18n::tr(Msg::PauseUpdates)),
+                }
+                resume_i.set_enabled(snoozed);
+                for (name, symbol, item) in &status_items {
+                    let health = update_ctx.health.get(symbol);
+                    item.set_text(format!("{}: {}", name, health.summary()));
+                }
+                let rates = load_rates(&db_conn_mutex, &config);
+                for (item, line) in quick_items
+                    .iter()
+                    .zip(converter::quick_lines(&rates, &config.converter))
+                {
+                    item.set_text(line);
+                }
+                if let Some(window) = converter.as_mut() {
+                    window.set_rates(rates.clone());
+                }
+                if let Some(window) = widget.as_mut() {
+                    window.set_rates(rates);
+                }
                 if let Some(tray) = tray_icon.as_mut() {
                     let result = {
                         let db_guard = db_conn_mutex.lock().unwrap_or_else(|p| p.into_inner());
-                        generate_tray_icon_image(&font_clone_main_loop, &db_guard)
+                        generate_tray_icon_image(&font_clone_main_loop, &db_guard, &config)
                     };
                     match result {
-                        Ok((new_icon, tooltip_text)) => {
-                            if let Err(e) = tray.set_icon(Some(new_icon)) {
-                                eprintln!("Failed to set tray icon: {}", e);
+                        Ok(render) => {
+                            last_icon = Some(render.icon.clone());
+                            last_tooltip = render.tooltip.clone();
+                            flash_icon = render.flash_icon;
+                            if attention::take_flash() && flash_icon.is_some() {
+                                let proxy_flash = proxy.clone();
+                                let phases = config.attention.flashes * 2;
+                                thread::spawn(move || {
+                                    for phase in 0..phases {
+                                        let on = phase % 2 == 0;
+                                        proxy_flash.send_event(UserEvent::Flash(on)).ok();
+                                        thread::sleep(attention::BLINK_INTERVAL);
+                                    }
+                                    proxy_flash.send_event(UserEvent::Flash(false)).ok();
+                                });
+                            }
+                            if let Err(e) = tray.set_icon(Some(render.icon)) {
+                                warn!("Failed to set tray icon: {}", e);
+                            }
+                            if let Err(e) = tray.set_tooltip(Some(render.tooltip)) {
+                                warn!("Failed to set tooltip: {}", e);
+                            }
+                            if config.tray.show_label {
+                                last_label = Some(render.label);
+                                tray.set_title(last_label.as_deref());
                             }
-                            if let Err(e) = tray.set_tooltip(Some(tooltip_text)) {
-                                eprintln!("Failed to set tooltip: {}", e);
+                            if config.tray.rates_in_menu {
+                                sync_rate_items(
+                                    &tray_menu,
+                                    &mut rate_items,
+                                    &render.rate_lines,
+                                    usize::from(metered_shown),
+                                );
                             }
                         }
                         Err(e) => {
-                            eprintln!("Failed to generate updated icon: {}. Using fallback.", e);
+                            error!("Failed to generate updated icon: {}. Using fallback.", e);
                             let fallback_icon =
                                 create_fallback_icon(&font_clone_main_loop, "Error");
+                            last_icon = Some(fallback_icon.clone());
+                            last_tooltip = i18n::tr(Msg::ErrorUpdatingRates).to_string();
                             if let Err(e) = tray.set_icon(Some(fallback_icon)) {
-                                eprintln!("Failed to set fallback tray icon: {}", e);
+                                warn!("Failed to set fallback tray icon: {}", e);
                             }
-                            if let Err(e) = tray.set_tooltip(Some("Error updating rates")) {
-                                eprintln!("Failed to set error tooltip: {}", e);
+                            if let Err(e) =
+                                tray.set_tooltip(Some(i18n::tr(Msg::ErrorUpdatingRates)))
+                            {
+                                warn!("Failed to set error tooltip: {}", e);
                             }
                         }
                     }
                     request_macos_redraw();
+                } else if headless {
+                    let db_guard = db_conn_mutex.lock().unwrap_or_else(|p| p.into_inner());
+                    match generate_tray_icon_image(&font_clone_main_loop, &db_guard, &config) {
+                        Ok(render) => info!("Headless: {}", render.tooltip),
+                        Err(e) => warn!("Headless: failed to read rates: {}", e),
+                    }
                 } else {
-                    println!("Tray icon not initialized yet, skipping update.");
+                    info!("Tray icon not initialized yet, skipping update.");
                 }
             }
+            // The periodic check keeps finding the same release; say it once.
+            Event::UserEvent(UserEvent::ReleaseAvailable(release))
+                if available_release.as_ref() != Some(&release) =>
+            {
+                info!(
+                    "Release {} is available at {}",
+                    release.version, release.url
+                );
+                notify::notify(
+                    i18n::tr(Msg::UpdateAvailableTitle),
+                    &i18n::trf(
+                        Msg::UpdateAvailableBody,
+                        &[&release.version, &env!("CARGO_PKG_VERSION")],
+                    ),
+                );
+                release_i.set_text(i18n::trf(Msg::DownloadVersion, &[&release.version]));
+                available_release = Some(release);
+            }
+            Event::UserEvent(UserEvent::ReleaseAvailable(_)) => {}
             Event::UserEvent(UserEvent::MenuEvent(menu_event)) => {
                 if menu_event.id == quit_i.id() {
                     tray_icon.take();
+                    // Saves its position.
+                    widget.take();
                     *control_flow = ControlFlow::Exit;
                 } else if menu_event.id == update_now_i.id() {
                     let proxy_manual = proxy.clone();
-                    let db_manual = Arc::clone(&db_conn_mutex);
-                    let http_manual = Arc::clone(&http_client);
-                    let key_manual = Arc::clone(&cmc_api_key);
+                    let update_ctx_manual = Arc::clone(&update_ctx);
                     thread::spawn(move || {
-                        match perform_data_update(&db_manual, &http_manual, &key_manual) {
-                            Ok(_) => println!("Manual Update: Data update process completed."),
-                            Err(e) => eprintln!("Manual Update: Data update process failed: {}", e),
+                        match update::perform_data_update(&update_ctx_manual, "manual") {
+                            Ok(_) => info!("Manual Update: Data update process completed."),
+                            Err(e) => warn!("Manual Update: Data update process failed: {}", e),
                         }
                         proxy_manual.send_event(UserEvent::UpdateTray).ok();
                     });
+                } else if let Some((length, _)) = pause_items
+                    .iter()
+                    .find(|(_, item)| menu_event.id == *item.id())
+                {
+                    snooze::start(*length);
+                    info!("Updates paused until {:?}", snooze::until());
+                    proxy.send_event(UserEvent::UpdateTray).ok();
+                } else if menu_event.id == resume_i.id() {
+                    // The tray refresh sees the pause gone and fetches.
+                    snooze::resume();
+                    proxy.send_event(UserEvent::UpdateTray).ok();
+                } else if let Some((symbol, _)) = refresh_items
+                    .iter()
+                    .find(|(_, item)| menu_event.id == *item.id())
+                {
+                    let symbol = symbol.to_string();
+                    let proxy_manual = proxy.clone();
+                    let update_ctx_manual = Arc::clone(&update_ctx);
+                    thread::spawn(move || {
+                        if let Err(e) =
+                            update::perform_symbol_update(&update_ctx_manual, &symbol, "manual")
+                        {
+                            warn!("Manual {} update failed: {}", symbol, e);
+                        }
+                        proxy_manual.send_event(UserEvent::UpdateTray).ok();
+                    });
+                } else if let Some((code, _)) = fiat_items
+                    .iter()
+                    .find(|(_, item)| menu_event.id == *item.id())
+                {
+                    fiat::set_active(code);
+                    state::update(|state| state.fiat = Some(code.to_string()));
+                    info!("Active fiat set to {}", code);
+                    // Check items toggle themselves; keep exactly one checked.
+                    for (other, item) in &fiat_items {
+                        item.set_checked(other == code);
+                    }
+                    proxy.send_event(UserEvent::UpdateTray).ok();
+                } else if let Some((name, _)) = profile_items
+                    .iter()
+                    .find(|(_, item)| menu_event.id == *item.id())
+                {
+                    for (other, item) in &profile_items {
+                        item.set_checked(*other == cli.profile);
+                    }
+                    if *name != cli.profile {
+                        match restart_with_profile(name.as_deref()) {
+                            Ok(()) => {
+                                tray_icon.take();
+                                *control_flow = ControlFlow::Exit;
+                            }
+                            Err(e) => {
+                                warn!("{}", e);
+                                notify::notify(i18n::tr(Msg::CouldNotSwitchProfile), &e);
+                            }
+                        }
+                    }
+                } else if menu_event.id == converter_i.id() {
+                    open_converter(
+                        &mut converter,
+                        event_loop_target,
+                        &font_clone_main_loop,
+                        &db_conn_mutex,
+                        &config,
+                    );
+                } else if menu_event.id == widget_i.id() {
+                    widget = if widget_i.is_checked() {
+                        open_widget(
+                            event_loop_target,
+                            &font_clone_main_loop,
+                            &db_conn_mutex,
+                            &config,
+                        )
+                    } else {
+                        None
+                    };
+                    widget_i.set_checked(widget.is_some());
+                    let open = widget.is_some();
+                    state::update(|state| state.widget_open = open);
+                } else if menu_event.id == snapshot_i.id() {
+                    if let Some(window) = &snapshot_note {
+                        window.focus();
+                    } else {
+                        match NoteWindow::open(event_loop_target, Arc::clone(&font_clone_main_loop))
+                        {
+                            Ok(window) => snapshot_note = Some(window),
+                            Err(e) => {
+                                warn!("{}", e);
+                                notify::notify(i18n::tr(Msg::SnapshotFailed), &e);
+                            }
+                        }
+                    }
+                } else if menu_event.id == release_i.id() {
+                    if let Some(release) = &available_release {
+                        if let Err(e) = open::that_detached(&release.url) {
+                            warn!("Failed to open {}: {}", release.url, e);
+                        }
+                    } else {
+                        let proxy_release = proxy.clone();
+                        thread::spawn(move || match release_check::check_latest() {
+                            Ok(Some(release)) => {
+                                proxy_release
+                                    .send_event(UserEvent::ReleaseAvailable(release))
+                                    .ok();
+                            }
+                            Ok(None) => notify::notify(
+                                i18n::tr(Msg::UpToDate),
+                                &i18n::trf(Msg::CurrentVersion, &[&env!("CARGO_PKG_VERSION")]),
+                            ),
+                            Err(e) => {
+                                warn!("{}", e);
+                                notify::notify(i18n::tr(Msg::UpdateCheckFailed), &e);
+                            }
+                        });
+                    }
+                } else if menu_event.id == share_i.id() {
+                    let rates = load_rates(&db_conn_mutex, &config);
+                    let text = share::message(&rates, &config, chrono::Local::now());
+                    match clipboard::copy(&text) {
+                        Ok(()) => notify::notify_unlogged(i18n::tr(Msg::RatesCopied), &text),
+                        Err(e) => {
+                            warn!("{}", e);
+                            notify::notify(i18n::tr(Msg::CopyFailed), &e);
+                        }
+                    }
+                } else if menu_event.id == chart_i.id() {
+                    match save_chart(&font_clone_main_loop, &db_conn_mutex, &config) {
+                        Ok(path) => {
+                            info!("Saved chart to {}", path.display());
+                            notify::notify(i18n::tr(Msg::ChartSaved), &path.display().to_string());
+                        }
+                        Err(e) => {
+                            warn!("{}", e);
+                            notify::notify(i18n::tr(Msg::ChartFailed), &e);
+                        }
+                    }
+                } else if menu_event.id == backup_i.id() {
+                    let result = paths::backup_path().and_then(|path| {
+                        let db_guard = db_conn_mutex.lock().unwrap_or_else(|p| p.into_inner());
+                        db::backup_to(&db_guard, &path).map(|()| path)
+                    });
+                    match result {
+                        Ok(path) => {
+                            info!("Backed up database to {}", path.display());
+                            notify::notify(i18n::tr(Msg::BackupSaved), &path.display().to_string());
+                        }
+                        Err(e) => {
+                            warn!("{}", e);
+                            notify::notify(i18n::tr(Msg::BackupFailed), &e);
+                        }
+                    }
+                } else if let Some((name, set, _)) = secret_items.iter().find(|(_, set, clear)| {
+                    menu_event.id == set.id() || menu_event.id == clear.id()
+                }) {
+                    let label = i18n::tr(secret_label(*name));
+                    let result = if menu_event.id == set.id() {
+                        clipboard::paste().and_then(|text| match text.trim() {
+                            "" => Err(i18n::tr(Msg::ClipboardEmpty).to_string()),
+                            value => secrets::set(*name, value).map(|()| Msg::SecretSaved),
+                        })
+                    } else {
+                        secrets::clear(*name).map(|()| Msg::SecretCleared)
+                    };
+                    match result {
+                        Ok(msg) => {
+                            info!("{}", i18n::trf(msg, &[&label]));
+                            notify::notify(
+                                &i18n::trf(msg, &[&label]),
+                                i18n::tr(Msg::SecretNeedsRestart),
+                            );
+                        }
+                        Err(e) => {
+                            warn!("{}", e);
+                            notify::notify(&i18n::trf(Msg::SecretFailed, &[&label]), &e);
+                        }
+                    }
+                } else if menu_event.id == open_log_i.id() {
+                    if let Err(e) = logging::open_current_log() {
+                        warn!("{}", e);
+                        notify::notify(i18n::tr(Msg::CouldNotOpenLog), &e);
+                    }
+                } else if menu_event.id == autostart_i.id() {
+                    // The menu has already flipped the check mark; make reality match it.
+                    let enabled = autostart_i.is_checked();
+                    match autostart::set_enabled(enabled) {
+                        Ok(()) => info!("Start at login set to {}", enabled),
+                        Err(e) => {
+                            autostart_i.set_checked(!enabled);
+                            notify::notify(i18n::tr(Msg::CouldNotChangeStartAtLogin), &e);
+                        }
+                    }
                 }
             }
-            Event::UserEvent(UserEvent::TrayIconEvent(_)) => {}
-            _ => {}
-        }
-    });
-}
-
-fn initialize_database(conn: &Connection) -> DbResult<()> {
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS quotes (
-            symbol TEXT PRIMARY KEY, rate REAL NOT NULL, last_updated TEXT NOT NULL
-        )",
-        [],
-    )?;
-    Ok(())
-}
-
-fn perform_data_update(
-    db_conn_mutex: &Arc<Mutex<Connection>>,
-    http_client: &Client,
-    cmc_api_key: &str,
-) -> Result<(), String> {
-    println!("Performing data update from APIs...");
-    let mut an_update_succeeded = false;
-
-    // --- Fetch BCV rate from bcv.org.ve ---
-    println!("Fetching BCV rate from {}", BCV_URL);
-    match http_client.get(BCV_URL).send() {
-        Ok(response) => {
-            if response.status().is_success() {
-                match response.text() {
-                    Ok(html_content) => {
-                        let document = Html::parse_document(&html_content);
-                        match Selector::parse(BCV_CSS_SELECTOR) {
-                            Ok(selector) => {
-                                if let Some(element) = document.select(&selector).next() {
-                                    let rate_str_raw =
-                                        element.text().collect::<String>().trim().to_string();
-                                    println!("BCV CSS selector raw string: '{}'", rate_str_raw);
-                                    let rate_str_cleaned =
-                                        rate_str_raw.replace(".", "").replace(",", ".");
-                                    match rate_str_cleaned.parse::<f64>() {
-                                        Ok(bcv_rate) => {
-                                            let conn_guard = db_conn_mutex
-                                                .lock()
-                                                .map_err(|e| format!("DB Mutex for BCV: {}", e))?;
-                                            let now_ts = Utc::now().to_rfc3339();
-                                            if conn_guard.execute("INSERT OR REPLACE INTO quotes VALUES(?1,?2,?3)", params!["bcv", bcv_rate, now_ts]).is_ok() {
-                                                println!("Updated BCV from bcv.org.ve: {}", bcv_rate);
-                                                an_update_succeeded = true;
-                                            } else { eprintln!("Failed to update BCV in DB (from bcv.org.ve)"); }
-                                        }
-                                        Err(e) => eprintln!(
-                                            "BCV: Failed to parse rate string '{}' to f64: {}",
-                                            rate_str_cleaned, e
-                                        ),
-                                    }
-                                } else {
-                                    eprintln!(
-                                        "BCV: CSS selector '{}' did not find any node.",
-                                        BCV_CSS_SELECTOR
-                                    );
-                                }
-                            }
+            Event::UserEvent(UserEvent::TrayIconEvent(event)) => {
+                let clicks = &config.clicks;
+                let action = match event {
+                    TrayIconEvent::Click {
+                        button,
+                        button_state: MouseButtonState::Up,
+                        ..
+                    } => match button {
+                        MouseButton::Left => clicks.left,
+                        MouseButton::Middle => clicks.middle,
+                        MouseButton::Right => ClickAction::None,
+                    },
+                    TrayIconEvent::DoubleClick {
+                        button: MouseButton::Left,
+                        ..
+                    } => clicks.double,
+                    _ => ClickAction::None,
+                };
+                match action {
+                    ClickAction::None => {}
+                    ClickAction::Refresh => update_in_background(&update_ctx, &proxy, "manual"),
+                    ClickAction::Converter => open_converter(
+                        &mut converter,
+                        event_loop_target,
+                        &font_clone_main_loop,
+                        &db_conn_mutex,
+                        &config,
+                    ),
+                    ClickAction::Copy => {
+                        let rates = load_rates(&db_conn_mutex, &config);
+                        let Some(rate) = rates.iter().find(|r| r.last_updated.is_some()) else {
+                            return;
+                        };
+                        let value = rate.rounding.format(rate.rate);
+                        match clipboard::copy(&value) {
+                            Ok(()) => notify::notify_unlogged(
+                                i18n::tr(Msg::RateCopied),
+                                &format!("{}: {}", rate.currency, value),
+                            ),
                             Err(e) => {
-                                eprintln!(
-                                    "BCV: Failed to parse CSS selector '{}': {:?}",
-                                    BCV_CSS_SELECTOR, e
-                                );
+                                warn!("{}", e);
+                                notify::notify(i18n::tr(Msg::CopyFailed), &e);
                             }
                         }
                     }
-                    Err(e) => {
-                        eprintln!("BCV: Failed to read response text from {}: {}", BCV_URL, e);
+                    ClickAction::Chart => {
+                        let opened = save_chart(&font_clone_main_loop, &db_conn_mutex, &config)
+                            .and_then(|path| {
+                                open::that_detached(&path).map_err(|e| {
+                                    format!("Could not open {}: {}", path.display(), e)
+                                })
+                            });
+                        if let Err(e) = opened {
+                            warn!("{}", e);
+                            notify::notify(i18n::tr(Msg::ChartFailed), &e);
+                        }
                     }
                 }
-            } else {
-                eprintln!(
-                    "BCV API request to {} failed with status: {}. Body: {:?}",
-                    BCV_URL,
-                    response.status(),
-                    response
-                        .text()
-                        .unwrap_or_else(|_| "Failed to read error body".to_string())
-                );
             }
-        }
-        Err(e) => {
-            eprintln!("BCV fetch error for {}: {}", BCV_URL, e);
-        }
-    }
-
-    // --- Fetch Binance P2P rate ---
-    println!("Fetching Binance P2P rate from {}", BINANCE_P2P_URL);
-    let binance_payload = BinanceP2PRequestPayload {
-        asset: "USDT".to_string(),
-        fiat: "VES".to_string(),
-        merchant_check: false, // Corresponds to Python `False`
-        page: 1,
-        pay_types: vec!["PagoMovil".to_string()],
-        publisher_type: None, // Corresponds to Python `None`, will be JSON `null`
-        rows: 1,
-        trade_type: "SELL".to_string(),
-    };
-
-    let mut binance_headers = HeaderMap::new();
-    binance_headers.insert(ACCEPT, HeaderValue::from_static("*/*"));
-    binance_headers.insert(
-        ACCEPT_ENCODING,
-        HeaderValue::from_static("gzip, deflate, br"),
-    ); // reqwest handles decompression
-    binance_headers.insert(
-        ACCEPT_LANGUAGE,
-        HeaderValue::from_static("en-GB,en-US;q=0.9,en;q=0.8"),
-    );
-    binance_headers.insert(CACHE_CONTROL, HeaderValue::from_static("no-cache"));
-    binance_headers.insert(CONNECTION, HeaderValue::from_static("keep-alive"));
-    binance_headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json")); // Crucial for .json() payload
-    binance_headers.insert(HOST, HeaderValue::from_static("p2p.binance.com"));
-    binance_headers.insert(ORIGIN, HeaderValue::from_static("https://p2p.binance.com"));
-    binance_headers.insert(PRAGMA, HeaderValue::from_static("no-cache"));
-    binance_headers.insert(TE, HeaderValue::from_static("Trailers"));
-    binance_headers.insert(
-        USER_AGENT,
-        HeaderValue::from_static(
-            "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:88.0) Gecko/20100101 Firefox/88.0",
-        ),
-    ); // Specific User-Agent from curl
-
-    match http_client
-        .post(BINANCE_P2P_URL)
-        .headers(binance_headers)
-        .json(&binance_payload)
-        .send()
-    {
-        Ok(response) => {
-            if response.status().is_success() {
-                match response.json::<BinanceResponse>() {
-                    Ok(binance_api_response) => {
-                        if binance_api_response.success && binance_api_response.code == "000000" {
-                            if let Some(ref data_vec) = binance_api_response.data {
-                                if let Some(first_adv_container) = data_vec.get(0) {
-                                    match first_adv_container.adv.price.parse::<f64>() {
-                                        Ok(binance_rate) => {
-                                            let conn_guard = db_conn_mutex.lock().map_err(|e| {
-                                                format!("DB Mutex for Binance P2P: {}", e)
-                                            })?;
-                                            let now_ts = Utc::now().to_rfc3339();
-                                            if conn_guard
-                                                .execute(
-                                                    "INSERT OR REPLACE INTO quotes VALUES(?1,?2,?3)",
-                                                    params!["binance", binance_rate, now_ts],
-                                                )
-                                                .is_ok()
-                                            {
-                                                println!("Updated Binance P2P (USDT/VES): {}", binance_rate);
-                                                an_update_succeeded = true;
-                                            } else {
-                                                eprintln!("Failed to update Binance P2P in DB");
-                                            }
-                                        }
-                                        Err(e) => eprintln!(
-                                            "Binance P2P: Failed to parse price string '{}' to f64: {}",
-                                            first_adv_container.adv.price, e
-                                        ),
-                                    }
-                                } else {
-                                    eprintln!("Binance P2P: 'data' array is empty in API response. Full response: {:?}", binance_api_response);
+            Event::WindowEvent {
+                window_id, event, ..
+            } => {
+                if let Some(window) = converter.as_mut().filter(|w| w.id() == window_id) {
+                    if !window.handle_event(&event) {
+                        converter = None;
+                    }
+                }
+                if let Some(window) = snapshot_note.as_mut().filter(|w| w.id() == window_id) {
+                    match window.handle_event(&event) {
+                        NoteAction::Keep => {}
+                        NoteAction::Cancel => snapshot_note = None,
+                        NoteAction::Save(note) => {
+                            snapshot_note = None;
+                            let result = {
+                                let db_guard =
+                                    db_conn_mutex.lock().unwrap_or_else(|p| p.into_inner());
+                                db::record_snapshot(&db_guard, &note)
+                            };
+                            match result {
+                                Ok(()) => {
+                                    info!("Recorded snapshot: {}", note);
+                                    notify::notify(i18n::tr(Msg::SnapshotSaved), &note);
+                                }
+                                Err(e) => {
+                                    warn!("Failed to record snapshot: {}", e);
+                                    notify::notify(i18n::tr(Msg::SnapshotFailed), &e.to_string());
                                 }
-                            } else {
-                                eprintln!("Binance P2P: 'data' field is null or missing in API response. Full response: {:?}", binance_api_response);
                             }
-                        } else {
-                            eprintln!("Binance P2P API call reported not successful or wrong code. Code: {}, Success: {}. Full response: {:?}", binance_api_response.code, binance_api_response.success, binance_api_response);
                         }
                     }
-                    Err(e) => {
-                        eprintln!("Binance P2P API JSON parse error: {}", e);
+                }
+                if let Some(window) = widget.as_mut().filter(|w| w.id() == window_id) {
+                    if !window.handle_event(&event) {
+                        widget = None;
+                        widget_i.set_checked(false);
+                        state::update(|state| state.widget_open = false);
+                    }
+                }
+            }
+            Event::RedrawRequested(window_id) => {
+                if let Some(window) = converter.as_mut().filter(|w| w.id() == window_id) {
+                    if let Err(e) = window.redraw() {
+                        warn!("{}", e);
+                    }
+                }
+                if let Some(window) = snapshot_note.as_mut().filter(|w| w.id() == window_id) {
+                    if let Err(e) = window.redraw() {
+                        warn!("{}", e);
+                    }
+                }
+                if let Some(window) = widget.as_mut().filter(|w| w.id() == window_id) {
+                    if let Err(e) = window.redraw() {
+                        warn!("{}", e);
                     }
                 }
-            } else {
-                eprintln!(
-                    "Binance P2P API request failed with status: {}. Body: {:?}",
-                    response.status(),
-                    response
-                        .text()
-                        .unwrap_or_else(|_| "Failed to read error body".to_string())
-                );
             }
+            _ => {}
+        }
+    });
+}
+
+fn secret_label(name: SecretName) -> Msg {
+    match name {
+        SecretName::CmcApiKey => Msg::SecretCmcApiKey,
+        SecretName::GoogleServiceAccount => Msg::SecretGoogleServiceAccount,
+        SecretName::ApiToken => Msg::SecretApiToken,
+        SecretName::NoonesApi => Msg::SecretNoonesApi,
+    }
+}
+
+// The keyring is preferred; the env var stays as a fallback for existing setups.
+// Either may hold several comma-separated keys.
+fn load_cmc_keys() -> providers::CmcKeys {
+    match secrets::get(SecretName::CmcApiKey) {
+        Ok(Some(keys)) => return providers::CmcKeys::parse(&keys),
+        Ok(None) => {}
+        Err(e) => warn!("{}", e),
+    }
+    match env::var(CMC_API_KEY_ENV_VAR) {
+        Ok(keys) => providers::CmcKeys::parse(&keys),
+        Err(_) => {
+            warn!(
+                "No CMC API key in the keyring (`bcv-tray secret set cmc-api-key`) \
+                 and env var {} not set. Satoshi updates will be skipped.",
+                CMC_API_KEY_ENV_VAR
+            );
+            providers::CmcKeys::default()
         }
+    }
+}
+
+fn build_tray_icon(
+    menu: &Menu,
+    icon: TrayIconImage,
+    tooltip: &str,
+) -> Result<TrayIcon, tray_icon::Error> {
+    TrayIconBuilder::new()
+        .with_menu(Box::new(menu.clone()))
+        .with_tooltip(tooltip)
+        .with_icon(icon)
+        // Clicks are handled per `[clicks]`; the menu stays on right click.
+        .with_menu_on_left_click(false)
+        .build()
+}
+
+fn load_rates(db: &Mutex<Connection>, config: &config::Config) -> Vec<RateInfo> {
+    let db_guard = db.lock().unwrap_or_else(|p| p.into_inner());
+    fetch_rates(&db_guard, config).unwrap_or_else(|e| {
+        warn!("Failed to read rates: {}", e);
+        Vec::new()
+    })
+}
+
+fn update_in_background(
+    ctx: &Arc<UpdateContext>,
+    proxy: &tao::event_loop::EventLoopProxy<UserEvent>,
+    trigger: &'static str,
+) {
+    let ctx = Arc::clone(ctx);
+    let proxy = proxy.clone();
+    thread::spawn(move || {
+        if let Err(e) = update::perform_data_update(&ctx, trigger) {
+            warn!("{} update failed: {}", trigger, e);
+        }
+        proxy.send_event(UserEvent::UpdateTray).ok();
+    });
+}
+
+// `--once`: one update cycle, then every rate shown as "<symbol>\t<rate>".
+// Exits non-zero when nothing could be updated.
+fn run_once(ctx: &UpdateContext, config: &config::Config) -> i32 {
+    let result = update::perform_data_update(ctx, "manual");
+    for rate in load_rates(&ctx.db, config) {
+        if rate.last_updated.is_some() {
+            println!("{}\t{}", rate.symbol, rate.rounding.format(rate.rate));
+        }
+    }
+    match result {
+        Ok(()) => 0,
         Err(e) => {
-            eprintln!("Binance P2P API fetch error: {}", e);
+            eprintln!("Error: {}", e);
+            1
         }
     }
+}
 
-    // --- CMC Satoshi Fetching Logic (remains unchanged) ---
-    if !cmc_api_key.is_empty() {
-        let cmc_url = format!("{}?id={}", CMC_BASE_URL, CMC_BTC_ID);
-        match http_client
-            .get(&cmc_url)
-            .header("X-CMC_PRO_API_KEY", cmc_api_key)
-            .header("Accept", "application/json")
-            .send()
-        {
-            Ok(response) => {
-                if response.status().is_success() {
-                    match response.json::<CmcResponse>() {
-                        Ok(data) => {
-                            let btc_price_usd = data.data.btc.quote.usd.price;
-                            let usd_price_satoshi = SATS_PER_BTC / btc_price_usd;
-                            let conn_guard = db_conn_mutex
-                                .lock()
-                                .map_err(|e| format!("DB Mutex for CMC: {}", e))?;
-                            let now_ts = Utc::now().to_rfc3339();
-                            if conn_guard
-                                .execute(
-                                    "INSERT OR REPLACE INTO quotes VALUES(?1,?2,?3)",
-                                    params!["satoshi", usd_price_satoshi, now_ts],
-                                )
-                                .is_ok()
-                            {
-                                println!("Updated Satoshi (SAT per USD): {:.2}", usd_price_satoshi);
-                                an_update_succeeded = true;
-                            } else {
-                                eprintln!("Failed to update Satoshi in DB");
-                            }
-                        }
-                        Err(e) => eprintln!("CMC JSON parse error: {}", e),
-                    }
-                } else {
-                    eprintln!(
-                        "CMC API fail: {}. Body: {:?}",
-                        response.status(),
-                        response.text().unwrap_or_default()
-                    );
-                }
+// Charts the first rate in the tray over the last month.
+fn save_chart(
+    font: &FontVec,
+    db: &Mutex<Connection>,
+    config: &config::Config,
+) -> Result<std::path::PathBuf, String> {
+    let rates = load_rates(db, config);
+    let symbol = &rates.first().ok_or("No rates to chart yet")?.symbol;
+    let path = paths::chart_path(symbol)?;
+    let db_guard = db.lock().unwrap_or_else(|p| p.into_inner());
+    chart::save(&db_guard, config, font, symbol, CHART_DAYS, &path)?;
+    Ok(path)
+}
+
+fn open_converter<T>(
+    converter: &mut Option<ConverterWindow>,
+    target: &tao::event_loop::EventLoopWindowTarget<T>,
+    font: &Arc<FontVec>,
+    db: &Mutex<Connection>,
+    config: &config::Config,
+) {
+    if let Some(window) = converter {
+        window.focus();
+        return;
+    }
+    let rates = load_rates(db, config);
+    match ConverterWindow::open(target, Arc::clone(font), rates, config.converter.clone()) {
+        Ok(window) => *converter = Some(window),
+        Err(e) => {
+            warn!("{}", e);
+            notify::notify(i18n::tr(Msg::CouldNotOpenConverter), &e);
+        }
+    }
+}
+
+// One disabled menu line per rate at the top of the menu, below the metered
+// notice when that is shown.
+fn sync_rate_items(menu: &Menu, items: &mut Vec<MenuItem>, lines: &[String], offset: usize) {
+    while items.len() > lines.len() {
+        if let Some(item) = items.pop() {
+            let _ = menu.remove(&item);
+        }
+    }
+    for (i, line) in lines.iter().enumerate() {
+        match items.get(i) {
+            Some(item) => item.set_text(line),
+            None => {
+                let item = MenuItem::new(line, false, None);
+                let _ = menu.insert(&item, offset + i);
+                items.push(item);
             }
-            Err(e) => eprintln!("CMC fetch error: {}", e),
         }
     }
+}
 
-    if an_update_succeeded {
-        Ok(())
-    } else {
-        Err("No rates were successfully updated.".to_string())
-    }
-}
-
-fn fetch_rates(conn: &Connection) -> DbResult<Vec<RateInfo>> {
-    let mut rates_data = Vec::new();
-    for (name, icon_asset_key, symbol) in CURRENCY_MAPPINGS.iter() {
-        match conn.query_row(
-            "SELECT rate FROM quotes WHERE symbol=?1 ORDER BY last_updated DESC LIMIT 1",
-            params![symbol],
-            |row| row.get(0),
-        ) {
-            Ok(rate_value) => {
-                rates_data.push(RateInfo {
-                    currency: name.to_string(),
-                    rate: rate_value,
-                    icon_asset_path: icon_asset_key.to_string(),
-                });
+fn open_widget<T>(
+    target: &tao::event_loop::EventLoopWindowTarget<T>,
+    font: &Arc<FontVec>,
+    db: &Mutex<Connection>,
+    config: &config::Config,
+) -> Option<WidgetWindow> {
+    WidgetWindow::open(
+        target,
+        Arc::clone(font),
+        load_rates(db, config),
+        config.tray.taskbar_badge,
+    )
+    .map_err(|e| warn!("{}", e))
+    .ok()
+}
+
+// Starts a new instance with the same arguments except `--profile`.
+fn restart_with_profile(profile: Option<&str>) -> Result<(), String> {
+    let exe = std::env::current_exe()
+        .map_err(|e| format!("Could not find the running executable: {}", e))?;
+    let mut args = Vec::new();
+    let mut skip_value = false;
+    for arg in std::env::args_os().skip(1) {
+        if std::mem::take(&mut skip_value) {
+            continue;
+        }
+        if arg == "--profile" {
+            skip_value = true;
+        } else if !arg.to_string_lossy().starts_with("--profile=") {
+            args.push(arg);
+        }
+    }
+    if let Some(profile) = profile {
+        args.push("--profile".into());
+        args.push(profile.into());
+    }
+    info!("Restarting with profile {:?}", profile);
+    std::process::Command::new(&exe)
+        .args(&args)
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| format!("Failed to start {}: {}", exe.display(), e))
+}
+
+fn fetch_rates(conn: &Connection, config: &config::Config) -> DbResult<Vec<RateInfo>> {
+    let active = fiat::active();
+    let brecha = (config.brecha_enabled() && active.code == "VES").then_some(&BRECHA_SOURCE);
+    let optional = providers::optional_sources().filter(|_| active.code == "VES");
+    let builtin = active
+        .sources
+        .iter()
+        .chain(optional)
+        .chain(brecha)
+        .map(|source| RateInfo {
+            currency: source.name.to_string(),
+            symbol: source.symbol.to_string(),
+            rate: 0.0, // Default to 0.0 if no data
+            icon_asset_path: source.icon.to_string(),
+            unit: source.unit.to_string(),
+            last_updated: None,
+            provider: source.provider.to_string(),
+            day_change: None,
+            day_range: None,
+            week_ago: None,
+            month_ago: None,
+            trend: None,
+            rounding: config.symbol(source.symbol).rounding(),
+        });
+    // Derived symbols have no icon; their label is drawn as text instead.
+    let derived = config.derived.iter().map(|(symbol, derived)| RateInfo {
+        currency: derived
+            .label
+            .clone()
+            .unwrap_or_else(|| symbol.to_uppercase()),
+        symbol: symbol.clone(),
+        rate: 0.0,
+        icon_asset_path: String::new(),
+        unit: derived.unit.clone(),
+        last_updated: None,
+        provider: derived.formula(),
+        day_change: None,
+        day_range: None,
+        week_ago: None,
+        month_ago: None,
+        trend: None,
+        rounding: config.symbol(symbol).rounding(),
+    });
+    let configured = providers::configured_sources()
+        .iter()
+        .map(|source| RateInfo {
+            currency: source.label.clone(),
+            symbol: source.symbol.clone(),
+            rate: 0.0,
+            icon_asset_path: source.icon.clone(),
+            unit: source.unit.clone(),
+            last_updated: None,
+            provider: source.provider.clone(),
+            day_change: None,
+            day_range: None,
+            week_ago: None,
+            month_ago: None,
+            trend: None,
+            rounding: config.symbol(&source.symbol).rounding(),
+        });
+    let mut rates_data: Vec<RateInfo> = builtin
+        .chain(configured)
+        .chain(derived)
+        .filter(|rate_info| providers::enabled(&rate_info.symbol))
+        .collect();
+    for rate_info in &mut rates_data {
+        if let Some(icon) = &config.symbol(&rate_info.symbol).icon {
+            rate_info.icon_asset_path = icon.clone();
+        }
+        match db::latest_quote(conn, &rate_info.symbol) {
+            Ok(Some((rate_value, last_updated))) => {
+                rate_info.rate = rate_value;
+                rate_info.last_updated = last_updated;
             }
-            Err(rusqlite::Error::QueryReturnedNoRows) => {
-                println!("No rate for {} in DB.", symbol);
-                rates_data.push(RateInfo {
-                    currency: name.to_string(),
-                    rate: 0.0, // Default to 0.0 if no data
-                    icon_asset_path: icon_asset_key.to_string(),
-                });
+            Ok(None) => debug!("No rate for {} in DB.", rate_info.symbol),
+            Err(e) => error!("DB fetch error for {}: {}", rate_info.symbol, e),
+        }
+        let day_ago = chrono::Utc::now() - chrono::Duration::days(1);
+        match db::rate_at(conn, &rate_info.symbol, day_ago) {
+            Ok(Some(previous)) if previous != 0.0 => {
+                rate_info.day_change = Some((rate_info.rate / previous - 1.0) * 100.0);
             }
-            Err(e) => {
-                eprintln!("DB fetch error for {}: {}", symbol, e);
-                rates_data.push(RateInfo {
-                    currency: name.to_string(),
-                    rate: 0.0, // Default to 0.0 on error
-                    icon_asset_path: icon_asset_key.to_string(),
-                });
+            Ok(_) => {}
+            Err(e) => error!("DB history error for {}: {}", rate_info.symbol, e),
+        }
+        match db::range_since(conn, &rate_info.symbol, timefmt::local_midnight()) {
+            Ok(range) => rate_info.day_range = range,
+            Err(e) => error!("DB history error for {}: {}", rate_info.symbol, e),
+        }
+        let rate_days_ago = |days| {
+            let at = chrono::Utc::now() - chrono::Duration::days(days);
+            db::rate_at(conn, &rate_info.symbol, at).unwrap_or_else(|e| {
+                error!("DB history error for {}: {}", rate_info.symbol, e);
+                None
+            })
+        };
+        rate_info.week_ago = rate_days_ago(7);
+        rate_info.month_ago = rate_days_ago(30);
+        if config.trend.show {
+            let since = chrono::Utc::now() - chrono::Duration::hours(config.trend.hours.into());
+            match db::history_since(conn, &rate_info.symbol, since) {
+                Ok(history) => rate_info.trend = trend::fitted_change(&history),
+                Err(e) => error!("DB history error for {}: {}", rate_info.symbol, e),
             }
         }
     }
     Ok(rates_data)
 }
 
+// Keeps only the `[parallel] chain` member to show, in the place of the first
+// one listed, plus a tooltip note naming the sources it stands in for.
+fn parallel_slot(
+    mut rates: Vec<RateInfo>,
+    config: &config::Config,
+) -> (Vec<RateInfo>, Option<String>) {
+    let readings: Vec<_> = rates
+        .iter()
+        .map(|r| (r.symbol.as_str(), r.last_updated))
+        .collect();
+    let Some(winner) = parallel::pick(&config.parallel, &readings, chrono::Utc::now()) else {
+        return (rates, None);
+    };
+    let skipped: Vec<&str> = config
+        .parallel
+        .chain
+        .iter()
+        .take_while(|symbol| **symbol != rates[winner].symbol)
+        .filter_map(|symbol| rates.iter().find(|r| r.symbol == *symbol))
+        .map(|r| r.currency.as_str())
+        .collect();
+    let note = (!skipped.is_empty()).then(|| {
+        i18n::trf(
+            Msg::ParallelFallback,
+            &[&rates[winner].currency, &skipped.join(", ")],
+        )
+    });
+    let slot = rates
+        .iter()
+        .position(|r| parallel::is_member(&config.parallel, &r.symbol))
+        .unwrap_or(winner);
+    rates.swap(slot, winner);
+    let mut index = 0;
+    rates.retain(|r| {
+        let keep = index == slot || !parallel::is_member(&config.parallel, &r.symbol);
+        index += 1;
+        keep
+    });
+    (rates, note)
+}
+
+// Reports `[symbols.*] icon` entries that won't load once at startup; when
+// drawing, such symbols just show their name.
+fn check_icons(config: &config::Config) {
+    for (symbol, symbol_config) in &config.symbols {
+        if let Some(icon) = symbol_config.icon.as_deref().filter(|i| !i.is_empty()) {
+            if let Err(e) = load_and_resize_icon_from_embed(icon, ICON_HEIGHT) {
+                warn!("Icon for {}: {}", symbol, e);
+            }
+        }
+    }
+}
+
 fn load_and_resize_icon_from_embed(
     asset_key: &str,
     target_height: u32,
@@ -613,9 +1587,8 @@ fn load_and_resize_icon_from_embed(
     if target_height == 0 {
         return Err("Target height 0".to_string());
     }
-    let asset_file = Assets::get(asset_key)
-        .ok_or_else(|| format!("Embedded icon not found: '{}'", asset_key))?;
-    let img_data = asset_file.data;
+    let img_data =
+        asset(asset_key).ok_or_else(|| format!("Embedded icon not found: '{}'", asset_key))?;
     let img = load_from_memory(&img_data)
         .map_err(|e| format!("Failed to decode embedded icon '{}': {}", asset_key, e))?
         .into_rgba8();
@@ -634,139 +1607,468 @@ fn load_and_resize_icon_from_embed(
 }
 
 fn generate_tray_icon_image(
-    font: &Arc<Font>,
+    font: &Arc<FontVec>,
     db_conn: &Connection,
-) -> Result<(TrayIconImage, String), Box<dyn std::error::Error>> {
-    let rates = fetch_rates(db_conn)?;
+    config: &config::Config,
+) -> Result<TrayRender, Box<dyn std::error::Error>> {
+    let (mut rates, fallback_note) = parallel_slot(fetch_rates(db_conn, config)?, config);
     if rates.is_empty() {
         let fallback = create_fallback_icon(font, "No Data");
-        return Ok((fallback, "No data".to_string()));
+        return Ok(TrayRender {
+            icon: fallback,
+            tooltip: i18n::tr(Msg::NoData).to_string(),
+            label: String::new(),
+            rate_lines: Vec::new(),
+            flash_icon: None,
+        });
     }
 
     let mut loaded_icons = Vec::new();
-    for rate_info in &rates {
-        loaded_icons
-            .push(load_and_resize_icon_from_embed(&rate_info.icon_asset_path, ICON_HEIGHT).ok());
+    for rate_info in &mut rates {
+        let icon = if rate_info.icon_asset_path.is_empty() {
+            None
+        } else {
+            load_and_resize_icon_from_embed(&rate_info.icon_asset_path, ICON_HEIGHT)
+                .map_err(|e| debug!("{}", e))
+                .ok()
+        };
+        // An icon that won't load is treated as none, so the name is drawn.
+        if icon.is_none() {
+            rate_info.icon_asset_path.clear();
+        }
+        loaded_icons.push(icon);
     }
 
-    #[cfg(target_os = "macos")]
-    let tc = Rgba([255u8, 255u8, 255u8, 255u8]);
+    // High contrast: larger digits ringed in the opposite color.
+    let outline = config.tray.high_contrast.then(appearance::contrast_color);
+    // Slightly larger than the icon height for better fit.
+    let text_scale = if outline.is_some() {
+        HIGH_CONTRAST_TEXT_SCALE
+    } else {
+        1.2
+    };
 
-    #[cfg(not(target_os = "macos"))]
-    let tc = Rgba([255u8, 255u8, 255u8, 255u8]); // Text color
+    let base_scale = PxScale::from(ICON_HEIGHT as f32 * text_scale);
 
-    let scale = Scale::uniform(ICON_HEIGHT as f32 * 1.2); // Slightly larger for better fit
-    let vm = font.v_metrics(scale);
-    let ty = ((ICON_HEIGHT as f32 - (vm.ascent - vm.descent)) / 2.0 + vm.ascent).round() as i32;
+    // Between two rates: `padding`, then optionally the separator and a
+    // divider line, each followed by `padding` again.
+    let layout = &config.layout;
+    let separator = (!layout.separator.is_empty())
+        .then(|| draw_tray_text(font, &layout.separator, base_scale, outline, config));
+    let mut between_w = layout.padding;
+    if let Some(separator) = &separator {
+        between_w += separator.width() + layout.padding;
+    }
+    if layout.divider {
+        between_w += 1 + layout.padding;
+    }
 
-    let mut total_w = 0u32;
     let mut elements = Vec::new();
     let mut tooltips = Vec::new();
+    let mut rate_lines = Vec::new();
+    let mut labels = Vec::new();
 
     for (i, rate_info) in rates.iter().enumerate() {
         let icon_img_opt = loaded_icons.get(i).and_then(|o| o.as_ref());
-        let icon_w = icon_img_opt.map_or(ICON_HEIGHT / 2, |img| img.width().max(1)); // Placeholder width if icon fails
-        let text_str = format!("{:.2}  ", rate_info.rate); // Add padding to text
-        tooltips.push(format!("{}: {}", rate_info.currency, text_str.trim()));
-        let glyphs: Vec<_> = font
-            .layout(&text_str, scale, rusttype::point(0.0, 0.0))
-            .collect();
-        let text_w = glyphs
-            .iter()
-            .rev()
-            .filter_map(|g| g.pixel_bounding_box().map(|bb| bb.max.x))
-            .max()
-            .unwrap_or(0) as u32;
-        let text_w_eff = text_w.max(10); // Min text width
-        let mut text_img = RgbaImage::from_pixel(text_w_eff, ICON_HEIGHT, Rgba([0, 0, 0, 0]));
-        draw_text_mut(
-            &mut text_img,
-            tc,
-            0,                                   // x position for text within its own image
-            ty - vm.ascent.abs().round() as i32, // y position for text (adjust based on font metrics)
-            scale,
-            font,
-            &text_str,
+        let icon_w = icon_img_opt.map_or(0, |img| img.width().max(1) + layout.icon_gap);
+        let symbol_config = config.symbol(&rate_info.symbol);
+        let mut value_str = rate_info
+            .rounding
+            .format(symbol_config.display_value(rate_info.rate));
+        if rate_info.unit == "%" {
+            value_str.push('%');
+        }
+        // The trend follows the displayed value, which inverting flips.
+        let trend = rate_info
+            .trend
+            .map(|change| {
+                if symbol_config.invert {
+                    -change
+                } else {
+                    change
+                }
+            })
+            .map(|change| trend::classify(change, config.trend.flat_pct));
+        // Rates that just made a large move keep a caret until the next update.
+        let caret = attention::caret(&rate_info.symbol);
+        let spoken = spoken_line(
+            rate_info,
+            &value_str,
+            &symbol_config,
+            trend,
+            caret.is_some(),
         );
-        if i > 0 {
-            total_w = total_w.saturating_add(PADDING);
+        if config.tray.verbose_tooltip {
+            tooltips.push(spoken.clone());
+        } else {
+            tooltips.push(tooltip_line(rate_info, &value_str, &symbol_config));
         }
-        total_w = total_w.saturating_add(icon_w);
-        total_w = total_w.saturating_add(PADDING); // Padding between icon and text
-        total_w = total_w.saturating_add(text_w_eff);
-        elements.push((icon_img_opt.cloned(), Some(text_img)));
+        rate_lines.push(spoken);
+        labels.push(format!("{} {}", rate_info.currency, value_str));
+        let marks: String = trend.map(trend::glyph).into_iter().chain(caret).collect();
+        // Without an icon the name goes in front so the number isn't anonymous.
+        let text_str = if rate_info.icon_asset_path.is_empty() {
+            format!("{} {}{}  ", rate_info.currency, value_str, marks)
+        } else {
+            format!("{}{}  ", value_str, marks) // Add padding to text
+        };
+        let text_img = draw_tray_text(font, &text_str, base_scale, outline, config);
+        // Icon plus the gap before its text, then the text.
+        let entry_w = icon_w.saturating_add(text_img.width());
+        elements.push((icon_img_opt.cloned(), text_img, entry_w));
     }
 
+    // With `rows = 2` rates fill columns top to bottom (BCV over BIN, ...);
+    // each column is as wide as its widest entry.
+    let rows = layout.rows.clamp(1, 2);
+    let height = ICON_HEIGHT * rows;
+    let columns: Vec<_> = elements.chunks(rows as usize).collect();
+    let column_w = |column: &[(Option<RgbaImage>, RgbaImage, u32)]| {
+        column.iter().map(|(_, _, w)| *w).max().unwrap_or(0)
+    };
+    let mut total_w = columns.iter().map(|c| column_w(c)).sum::<u32>();
+    total_w = total_w.saturating_add(between_w * columns.len().saturating_sub(1) as u32);
+
     if total_w == 0 {
-        println!("Calculated canvas width is zero, using fallback.");
+        info!("Calculated canvas width is zero, using fallback.");
         let fallback_icon = create_fallback_icon(font, "...");
-        return Ok((fallback_icon, "Error generating icon".to_string()));
+        return Ok(TrayRender {
+            icon: fallback_icon,
+            tooltip: "Error generating icon".to_string(),
+            label: String::new(),
+            rate_lines: Vec::new(),
+            flash_icon: None,
+        });
     }
     total_w = total_w.max(1); // Ensure width is at least 1
-    let mut canvas = RgbaImage::from_pixel(total_w, ICON_HEIGHT, Rgba([0, 0, 0, 0])); // Transparent background
+
+    // Transparent unless a solid background is asked for (busy wallpapers).
+    let background = if config.tray.solid_background {
+        appearance::contrast_color()
+    } else {
+        Rgba([0, 0, 0, 0])
+    };
+    let mut canvas = RgbaImage::from_pixel(total_w, height, background);
     let mut current_x: i64 = 0;
-    for (i, (icon_opt, text_opt)) in elements.iter().enumerate() {
+    for (i, column) in columns.iter().enumerate() {
         if i > 0 {
-            current_x += PADDING as i64; // Padding between currency groups
+            current_x += layout.padding as i64; // Padding between currency groups
+            if let Some(separator) = &separator {
+                for row in 0..rows {
+                    let y = (row * ICON_HEIGHT) as i64;
+                    image::imageops::overlay(&mut canvas, separator, current_x, y);
+                }
+                current_x += (separator.width() + layout.padding) as i64;
+            }
+            if layout.divider {
+                draw_divider(&mut canvas, current_x as u32);
+                current_x += 1 + layout.padding as i64;
+            }
         }
-        if let Some(icon) = icon_opt {
-            image::imageops::overlay(&mut canvas, icon, current_x, 0);
-            current_x += icon.width() as i64;
-        } else {
-            // If icon failed to load, still advance X to keep spacing somewhat consistent
-            current_x += (ICON_HEIGHT / 2) as i64;
+        for (row, (icon_opt, text, _)) in column.iter().enumerate() {
+            let y = (row as u32 * ICON_HEIGHT) as i64;
+            let mut x = current_x;
+            // Without an icon the text (which then starts with the name) goes first.
+            if let Some(icon) = icon_opt {
+                image::imageops::overlay(&mut canvas, icon, x, y);
+                x += icon.width() as i64;
+                x += layout.icon_gap as i64; // Padding between icon and text
+            }
+            image::imageops::overlay(&mut canvas, text, x, y);
         }
-        current_x += PADDING as i64; // Padding between icon and text
-        if let Some(text) = text_opt {
-            image::imageops::overlay(&mut canvas, text, current_x, 0);
-            current_x += text.width() as i64;
+        current_x += column_w(column) as i64;
+    }
+    tooltips.extend(fallback_note);
+    if config.tray.equivalences {
+        tooltips.extend(rates.first().and_then(equivalence_line));
+    }
+    if !update::updated_this_session() {
+        fade(&mut canvas);
+        tooltips.push(i18n::tr(Msg::PreviousSession).to_string());
+    }
+    if let Some(newest) = rates.iter().filter_map(|r| r.last_updated).max() {
+        tooltips.push(i18n::trf(Msg::UpdatedAgo, &[&timefmt::relative(newest)]));
+    }
+    let flash_icon =
+        TrayIconImage::from_rgba(attention::tint(&canvas).into_raw(), total_w, height)?;
+    Ok(TrayRender {
+        icon: TrayIconImage::from_rgba(canvas.into_raw(), total_w, height)?,
+        tooltip: tooltips.join("\n"),
+        label: labels.join(" · "),
+        rate_lines,
+        flash_icon: Some(flash_icon),
+    })
+}
+
+// `text` fitted to the icon height in the tray text color, outlined in high
+// contrast mode, on a transparent image just wide enough for it.
+fn draw_tray_text(
+    font: &FontVec,
+    text: &str,
+    base_scale: PxScale,
+    outline: Option<Rgba<u8>>,
+    config: &config::Config,
+) -> RgbaImage {
+    let border = u32::from(outline.is_some());
+    let (scale, text_y) = fit_text(font, text, base_scale, ICON_HEIGHT);
+    let text_w = text::width(font, text, scale).max(0) as u32;
+    let text_w_eff = text_w.max(1) + 2 * border;
+    let mut text_img = RgbaImage::from_pixel(text_w_eff, ICON_HEIGHT, Rgba([0, 0, 0, 0]));
+    let text_x = border as i32; // x position for text within its own image
+    if let Some(outline) = outline {
+        for (dx, dy) in OUTLINE_OFFSETS {
+            text::draw_text(
+                &mut text_img,
+                outline,
+                text_x + dx,
+                text_y + dy,
+                scale,
+                font,
+                text,
+                config.tray.supersample,
+            );
         }
     }
-    Ok((
-        TrayIconImage::from_rgba(canvas.into_raw(), total_w, ICON_HEIGHT)?,
-        tooltips.join(" | "),
+    text::draw_text(
+        &mut text_img,
+        appearance::text_color(),
+        text_x,
+        text_y,
+        scale,
+        font,
+        text,
+        config.tray.supersample,
+    );
+    text_img
+}
+
+// A one-pixel line in the text color, faded, down the middle of the icon.
+fn draw_divider(canvas: &mut RgbaImage, x: u32) {
+    let Rgba([r, g, b, a]) = appearance::text_color();
+    let color = Rgba([r, g, b, a / 2]);
+    let inset = ICON_HEIGHT / 8;
+    if x < canvas.width() {
+        for y in inset..canvas.height() - inset {
+            canvas.put_pixel(x, y, color);
+        }
+    }
+}
+
+// Half transparency for rates carried over from the previous session.
+fn fade(canvas: &mut RgbaImage) {
+    for px in canvas.pixels_mut() {
+        px.0[3] /= 2;
+    }
+}
+
+// "1 · 10 · 100 USD = 178.35 · 1783.50 · 17835.00 Bs", for rates quoted per USD.
+fn equivalence_line(rate_info: &RateInfo) -> Option<String> {
+    if rate_info.last_updated.is_none() || rate_info.unit == "%" {
+        return None;
+    }
+    let amounts: Vec<String> = EQUIVALENCE_AMOUNTS.iter().map(|a| a.to_string()).collect();
+    let values: Vec<String> = EQUIVALENCE_AMOUNTS
+        .iter()
+        .map(|amount| rate_info.rounding.format(rate_info.rate * *amount as f64))
+        .collect();
+    Some(format!(
+        "{} USD = {} {}",
+        amounts.join(" · "),
+        values.join(" · "),
+        rate_info.unit
     ))
 }
 
-fn create_fallback_icon(font: &Arc<Font>, text: &str) -> TrayIconImage {
-    let h = ICON_HEIGHT;
-    let scale = Scale::uniform(h as f32 * 0.7); // Smaller text for fallback
+// "BCV: 178.35 Bs  +0.42% 24h · 5 min ago · Banco Central de Venezuela".
+// Windows cuts tooltips at 127 characters, so the provider is left out there.
+fn tooltip_line(
+    rate_info: &RateInfo,
+    value_str: &str,
+    symbol_config: &config::SymbolConfig,
+) -> String {
+    let value = if symbol_config.invert {
+        i18n::trf(
+            Msg::UsdPer,
+            &[&value_str, &symbol_config.per, &rate_info.unit],
+        )
+    } else if rate_info.unit == "%" {
+        value_str.to_string()
+    } else {
+        format!("{} {}", value_str, rate_info.unit)
+    };
+    let mut line = format!("{}: {}", rate_info.currency, value);
+    if let Some(change) = rate_info.day_change {
+        line.push_str(&format!("  {:+.2}% 24h", change));
+    }
+    // A single reading isn't a range; Windows has no room for it either.
+    if let Some((low, high)) = rate_info.day_range.filter(|(low, high)| low < high) {
+        if cfg!(not(target_os = "windows")) {
+            let (low, high) = display_range(low, high, symbol_config);
+            line.push_str(&format!(
+                "  {}",
+                i18n::trf(
+                    Msg::TodayRange,
+                    &[
+                        &rate_info.rounding.format(low),
+                        &rate_info.rounding.format(high)
+                    ]
+                )
+            ));
+        }
+    }
+    if let Some(updated) = rate_info.last_updated {
+        line.push_str(&format!(" · {}", timefmt::relative(updated)));
+    }
+    if cfg!(not(target_os = "windows")) && !rate_info.provider.is_empty() {
+        line.push_str(&format!(" · {}", rate_info.provider));
+    }
+    line
+}
+
+// The same information as a sentence, with what the icon only shows as
+// glyphs spelled out: "BCV: 178.35 Bs, up 0.42% in 24 hours, trending up,
+// today between 177.90 and 178.35, updated 5 min ago, source BCV."
+fn spoken_line(
+    rate_info: &RateInfo,
+    value_str: &str,
+    symbol_config: &config::SymbolConfig,
+    trend: Option<taskbar::Trend>,
+    sharp_move: bool,
+) -> String {
+    let value = if symbol_config.invert {
+        i18n::trf(
+            Msg::UsdPer,
+            &[&value_str, &symbol_config.per, &rate_info.unit],
+        )
+    } else if rate_info.unit == "%" {
+        value_str.to_string()
+    } else {
+        format!("{} {}", value_str, rate_info.unit)
+    };
+    if rate_info.last_updated.is_none() {
+        return format!("{}: {}", rate_info.currency, i18n::tr(Msg::NoRatesYet));
+    }
+    let mut parts = vec![format!("{}: {}", rate_info.currency, value)];
+    if let Some(change) = rate_info.day_change {
+        let pct = format!("{:.2}", change.abs());
+        parts.push(match change {
+            c if c > 0.0 => i18n::trf(Msg::SpokenUp, &[&pct]),
+            c if c < 0.0 => i18n::trf(Msg::SpokenDown, &[&pct]),
+            _ => i18n::tr(Msg::SpokenUnchanged).to_string(),
+        });
+    }
+    if let Some(trend) = trend {
+        parts.push(
+            i18n::tr(match trend {
+                taskbar::Trend::Up => Msg::SpokenTrendUp,
+                taskbar::Trend::Down => Msg::SpokenTrendDown,
+                taskbar::Trend::Flat => Msg::SpokenTrendFlat,
+            })
+            .to_string(),
+        );
+    }
+    if sharp_move {
+        parts.push(i18n::tr(Msg::SpokenSharpMove).to_string());
+    }
+    if let Some((low, high)) = rate_info.day_range.filter(|(low, high)| low < high) {
+        let (low, high) = display_range(low, high, symbol_config);
+        parts.push(i18n::trf(
+            Msg::SpokenRange,
+            &[
+                &rate_info.rounding.format(low),
+                &rate_info.rounding.format(high),
+            ],
+        ));
+    }
+    if let Some(updated) = rate_info.last_updated {
+        parts.push(i18n::trf(
+            Msg::SpokenUpdated,
+            &[&timefmt::relative(updated)],
+        ));
+    }
+    if !rate_info.provider.is_empty() {
+        parts.push(i18n::trf(Msg::SpokenSource, &[&rate_info.provider]));
+    }
+    format!("{}.", parts.join(", "))
+}
+
+// Inverting swaps which end of the range is lower.
+fn display_range(low: f64, high: f64, symbol_config: &config::SymbolConfig) -> (f64, f64) {
+    let (a, b) = (
+        symbol_config.display_value(low),
+        symbol_config.display_value(high),
+    );
+    (a.min(b), a.max(b))
+}
+
+// Inverted rates are often well below 1, where two decimals would show 0.00.
+fn format_rate(value: f64) -> String {
+    if value != 0.0 && value.abs() < 1.0 {
+        format!("{:.4}", value)
+    } else {
+        format!("{:.2}", value)
+    }
+}
 
-    #[cfg(target_os = "macos")]
-    let tc = Rgba([255u8, 255, 255, 255]); // White text
+fn create_fallback_icon(font: &Arc<FontVec>, text: &str) -> TrayIconImage {
+    let h = ICON_HEIGHT;
+    let scale = PxScale::from(h as f32 * 0.7); // Smaller text for fallback
 
-    #[cfg(not(target_os = "macos"))]
-    let tc = Rgba([255u8, 255, 255, 255]); // White text
+    let tc = appearance::text_color();
     let bg = Rgba([0u8, 0, 0, 0]); // Transparent background
 
     // Calculate text width
-    let glyphs: Vec<_> = font
-        .layout(text, scale, rusttype::point(0.0, 0.0))
-        .collect();
-    let tw = glyphs
-        .last()
-        .map(|g| g.position().x + g.unpositioned().h_metrics().advance_width)
-        .unwrap_or(30.0); // Default width if no glyphs
+    let scaled = font.as_scaled(scale);
+    let tw: f32 = text
+        .chars()
+        .map(|c| scaled.h_advance(scaled.glyph_id(c)))
+        .sum();
     let w = (tw.ceil() as u32).max(10) + PADDING * 2; // Add padding
 
     let mut canvas = RgbaImage::from_pixel(w, h, bg);
 
-    // Calculate text y position for vertical centering
-    let vm = font.v_metrics(scale);
-    let ty = ((h as f32 - (vm.ascent - vm.descent)) / 2.0 + vm.ascent).round() as i32;
-
-    draw_text_mut(
+    let (scale, y) = fit_text(font, text, scale, h);
+    text::draw_text(
         &mut canvas,
         tc,
-        PADDING as i32,                       // X position with padding
-        ty - vm.descent.abs().round() as i32, // Y position, adjust for font metrics
+        PADDING as i32, // X position with padding
+        y,
         scale,
         font,
         text,
+        false,
     );
     TrayIconImage::from_rgba(canvas.into_raw(), w, h).expect("Fallback icon create failed")
 }
 
+// Scale and draw_text y offset that center the pixels `text` actually
+// covers in `height`, shrinking it only if they don't fit. The font's
+// ascent-descent span is taller than the 16 px icon, so centering on that
+// left descenders (",", "g", "y") cut off at the bottom.
+fn fit_text(font: &FontVec, text: &str, scale: PxScale, height: u32) -> (PxScale, i32) {
+    // Rows covered, relative to the y draw_text is given.
+    let rows = |scale: PxScale| {
+        let ascent = font.as_scaled(scale).ascent();
+        text::layout(font, text, scale, point(0.0, ascent))
+            .into_iter()
+            .map(|g| g.px_bounds())
+            .map(|bb| (bb.min.y as i32, bb.max.y as i32))
+            .reduce(|(top, bottom), (t, b)| (top.min(t), bottom.max(b)))
+    };
+    let height = height as i32;
+    let Some((mut top, mut bottom)) = rows(scale) else {
+        return (scale, 0);
+    };
+    let mut scale = scale;
+    if bottom - top > height {
+        let shrunk = PxScale::from(scale.y * height as f32 / (bottom - top) as f32);
+        if let Some((t, b)) = rows(shrunk) {
+            (scale, top, bottom) = (shrunk, t, b);
+        }
+    }
+    (scale, (height - (bottom - top)) / 2 - top)
+}
+
 #[cfg(target_os = "macos")]
 fn request_macos_redraw() {
     extern "C" {
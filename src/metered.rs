@@ -0,0 +1,78 @@
+// --- Metered Connections ---
+// Mobile hotspots and capped plans are flagged as metered by NetworkManager
+// and Windows. On those we either update less often or only on "Update Now",
+// depending on `[metered] mode`. Other platforms are never considered metered.
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+use tracing::info;
+
+use crate::config::{MeteredConfig, MeteredMode};
+
+static METERED: AtomicBool = AtomicBool::new(false);
+
+#[cfg(target_os = "linux")]
+fn query_metered() -> bool {
+    // NMMetered: 1 = yes, 3 = guessed yes (e.g. a phone hotspot).
+    let metered = || -> zbus::Result<u32> {
+        let conn = zbus::blocking::Connection::system()?;
+        let proxy = zbus::blocking::Proxy::new(
+            &conn,
+            "org.freedesktop.NetworkManager",
+            "/org/freedesktop/NetworkManager",
+            "org.freedesktop.NetworkManager",
+        )?;
+        proxy.get_property("Metered")
+    };
+    matches!(metered(), Ok(1 | 3))
+}
+
+#[cfg(target_os = "windows")]
+fn query_metered() -> bool {
+    use windows::Networking::Connectivity::{NetworkCostType, NetworkInformation};
+
+    let Ok(cost) =
+        NetworkInformation::GetInternetConnectionProfile().and_then(|p| p.GetConnectionCost())
+    else {
+        return false;
+    };
+    matches!(
+        cost.NetworkCostType(),
+        Ok(NetworkCostType::Fixed | NetworkCostType::Variable)
+    ) || cost.Roaming().unwrap_or(false)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+fn query_metered() -> bool {
+    false
+}
+
+// Re-reads the connection state and logs when it changes. Called once per cycle.
+pub fn refresh(config: &MeteredConfig) -> bool {
+    let metered = config.mode != MeteredMode::Ignore && query_metered();
+    if METERED.swap(metered, Ordering::Relaxed) != metered {
+        info!(
+            "Connection is {}metered",
+            if metered { "" } else { "no longer " }
+        );
+    }
+    metered
+}
+
+pub fn is_metered() -> bool {
+    METERED.load(Ordering::Relaxed)
+}
+
+// Scheduled updates are skipped entirely in manual-only mode.
+pub fn blocks(trigger: &str, config: &MeteredConfig) -> bool {
+    trigger != "manual" && is_metered() && config.mode == MeteredMode::Manual
+}
+
+pub fn stretch(interval: Duration, config: &MeteredConfig) -> Duration {
+    if is_metered() && config.mode == MeteredMode::Reduced {
+        interval.saturating_mul(config.interval_multiplier.max(1))
+    } else {
+        interval
+    }
+}
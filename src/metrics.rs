@@ -0,0 +1,266 @@
+// --- Prometheus Metrics ---
+// The latest rates in the Prometheus text format: served on the API's
+// `/metrics` for scraping, and, with `[pushgateway] url` set, pushed to a
+// Pushgateway after every update cycle for installs nothing can reach.
+// Provider health (success rate, latency, failure streak) comes along, per
+// fetched symbol.
+use chrono::{DateTime, Utc};
+use reqwest::{blocking::Client, Url};
+use rusqlite::Connection;
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tracing::{debug, warn};
+
+use crate::{
+    api,
+    config::Config,
+    db,
+    health::{HealthRegistry, ProviderHealth},
+    redact,
+};
+
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+struct Sample {
+    symbol: String,
+    name: String,
+    unit: String,
+    rate: f64,
+    updated: Option<DateTime<Utc>>,
+}
+
+// Every rate the API lists that has a stored value.
+pub fn render(conn: &Connection, config: &Config, health: &HealthRegistry) -> String {
+    let samples: Vec<Sample> = api::symbols(config)
+        .into_iter()
+        .filter_map(|(symbol, name, unit)| {
+            let (rate, updated) = db::latest_quote(conn, &symbol).ok().flatten()?;
+            Some(Sample {
+                symbol,
+                name,
+                unit,
+                rate,
+                updated,
+            })
+        })
+        .collect();
+    let mut out = format_samples(&samples);
+    out.push_str(&format_health(&health.all()));
+    out
+}
+
+fn format_samples(samples: &[Sample]) -> String {
+    let mut out = String::new();
+    out.push_str("# HELP bcv_tray_rate Latest stored rate.\n");
+    out.push_str("# TYPE bcv_tray_rate gauge\n");
+    for sample in samples {
+        let _ = writeln!(
+            out,
+            "bcv_tray_rate{{symbol=\"{}\",name=\"{}\",unit=\"{}\"}} {}",
+            escape(&sample.symbol),
+            escape(&sample.name),
+            escape(&sample.unit),
+            sample.rate
+        );
+    }
+    out.push_str(
+        "# HELP bcv_tray_rate_updated_seconds When the rate was last stored, as a Unix time.\n",
+    );
+    out.push_str("# TYPE bcv_tray_rate_updated_seconds gauge\n");
+    for sample in samples {
+        let Some(updated) = sample.updated else {
+            continue;
+        };
+        let _ = writeln!(
+            out,
+            "bcv_tray_rate_updated_seconds{{symbol=\"{}\"}} {}",
+            escape(&sample.symbol),
+            updated.timestamp()
+        );
+    }
+    out
+}
+
+// Name, help text and value of each per-provider health gauge.
+type HealthGauge = (&'static str, &'static str, fn(&ProviderHealth) -> f64);
+
+const HEALTH_GAUGES: [HealthGauge; 3] = [
+    (
+        "bcv_tray_provider_success_ratio",
+        "Share of fetches that succeeded since startup.",
+        |provider| provider.success_rate().unwrap_or_default(),
+    ),
+    (
+        "bcv_tray_provider_latency_seconds",
+        "Average fetch time since startup.",
+        |provider| provider.average_latency().unwrap_or_default().as_secs_f64(),
+    ),
+    (
+        "bcv_tray_provider_consecutive_failures",
+        "Fetches that failed in a row.",
+        |provider| provider.consecutive_failures as f64,
+    ),
+];
+
+// Only providers that have been tried; a ratio over no attempts means nothing.
+fn format_health(health: &BTreeMap<String, ProviderHealth>) -> String {
+    let tried: Vec<(&String, &ProviderHealth)> = health
+        .iter()
+        .filter(|(_, provider)| provider.attempts > 0)
+        .collect();
+    let mut out = String::new();
+    for (name, help, value) in HEALTH_GAUGES {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} gauge", name);
+        for (symbol, provider) in &tried {
+            let _ = writeln!(
+                out,
+                "{}{{symbol=\"{}\"}} {}",
+                name,
+                escape(symbol),
+                value(provider)
+            );
+        }
+    }
+    out
+}
+
+// Label values may not hold raw backslashes, quotes or newlines.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+pub struct Pushgateway {
+    config: Arc<Config>,
+    // Not the fetchers' client: the URL's basic-auth credentials must only
+    // reach a verified gateway. None when pushing is off.
+    client: Option<Client>,
+}
+
+impl Pushgateway {
+    pub fn new(config: Arc<Config>) -> Self {
+        let client = if config.pushgateway.url.is_empty() {
+            None
+        } else {
+            Client::builder()
+                .user_agent(concat!("bcv-tray/", env!("CARGO_PKG_VERSION")))
+                .timeout(Duration::from_secs(15))
+                .build()
+                .map_err(|e| warn!("Pushgateway disabled, no HTTP client: {}", e))
+                .ok()
+        };
+        Pushgateway { config, client }
+    }
+
+    // Called after each successful update; replaces the job's metrics on the
+    // gateway so rates that went away don't linger.
+    pub fn after_update(&self, db: &Mutex<Connection>, health: &HealthRegistry) {
+        let Some(client) = &self.client else {
+            return;
+        };
+        let settings = &self.config.pushgateway;
+        let body = {
+            let conn = db.lock().unwrap_or_else(|p| p.into_inner());
+            render(&conn, &self.config, health)
+        };
+        match push(
+            client,
+            &settings.url,
+            &settings.job,
+            &settings.instance,
+            body,
+        ) {
+            Ok(()) => debug!("Pushed metrics to the Pushgateway"),
+            Err(e) => warn!("{}", e),
+        }
+    }
+}
+
+fn push(client: &Client, url: &str, job: &str, instance: &str, body: String) -> Result<(), String> {
+    let mut url = Url::parse(url).map_err(|e| format!("Invalid Pushgateway URL: {}", e))?;
+    // Credentials in the URL become basic auth rather than part of the path.
+    let username = url.username().to_string();
+    let password = url.password().map(str::to_string);
+    let _ = url.set_username("");
+    let _ = url.set_password(None);
+    url.path_segments_mut()
+        .map_err(|_| "Invalid Pushgateway URL".to_string())?
+        .pop_if_empty()
+        .extend(["metrics", "job", job]);
+    if !instance.is_empty() {
+        url.path_segments_mut()
+            .map_err(|_| "Invalid Pushgateway URL".to_string())?
+            .extend(["instance", instance]);
+    }
+    let mut request = client
+        .put(url)
+        .header("Content-Type", CONTENT_TYPE)
+        .body(body);
+    if !username.is_empty() {
+        request = request.basic_auth(username, password);
+    }
+    request
+        .send()
+        .and_then(|response| response.error_for_status())
+        .map(|_| ())
+        .map_err(|e| {
+            format!(
+                "Pushgateway push failed: {}",
+                redact::redact(&e.to_string())
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_gauges_with_escaped_labels() {
+        let samples = [
+            Sample {
+                symbol: "bcv".to_string(),
+                name: "BCV \"oficial\"".to_string(),
+                unit: "Bs".to_string(),
+                rate: 36.5,
+                updated: DateTime::from_timestamp(1_700_000_000, 0),
+            },
+            Sample {
+                symbol: "brecha".to_string(),
+                name: "BRECHA".to_string(),
+                unit: "%".to_string(),
+                rate: 12.25,
+                updated: None,
+            },
+        ];
+        let text = format_samples(&samples);
+        assert!(text.contains(
+            "bcv_tray_rate{symbol=\"bcv\",name=\"BCV \\\"oficial\\\"\",unit=\"Bs\"} 36.5\n"
+        ));
+        assert!(text.contains("bcv_tray_rate_updated_seconds{symbol=\"bcv\"} 1700000000\n"));
+        assert!(!text.contains("bcv_tray_rate_updated_seconds{symbol=\"brecha\"}"));
+    }
+
+    #[test]
+    fn formats_health_of_tried_providers() {
+        let registry = HealthRegistry::default();
+        registry.record("bcv", Duration::from_millis(400), Ok(()));
+        registry.record("bcv", Duration::from_millis(200), Err("timeout"));
+        registry.record("binance", Duration::ZERO, Ok(()));
+        let mut health = registry.all();
+        health.insert("satoshi".to_string(), ProviderHealth::default());
+        let text = format_health(&health);
+        assert!(text.contains("bcv_tray_provider_success_ratio{symbol=\"bcv\"} 0.5\n"));
+        assert!(text.contains("bcv_tray_provider_latency_seconds{symbol=\"bcv\"} 0.3\n"));
+        assert!(text.contains("bcv_tray_provider_consecutive_failures{symbol=\"bcv\"} 1\n"));
+        assert!(text.contains("bcv_tray_provider_success_ratio{symbol=\"binance\"} 1\n"));
+        assert!(!text.contains("satoshi"));
+    }
+}
@@ -0,0 +1,107 @@
+// --- Desktop Notifications ---
+// Thin wrapper so callers don't care which backend the platform uses.
+
+const APP_NAME: &str = "BCV Tray";
+#[cfg(target_os = "windows")]
+const APP_USER_MODEL_ID: &str = "ruvasqm.BcvTray";
+
+// Registers the app as a notification source where the platform needs it.
+#[cfg(target_os = "windows")]
+pub fn init() {
+    if let Err(e) = register_app_user_model_id() {
+        tracing::warn!("Failed to register notification source: {}", e);
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn init() {}
+
+// Toasts are attributed to an AppUserModelID; without a registered one Windows
+// either drops them or shows them under PowerShell's name.
+#[cfg(target_os = "windows")]
+fn register_app_user_model_id() -> Result<(), String> {
+    use windows_sys::Win32::UI::Shell::SetCurrentProcessExplicitAppUserModelID;
+    use winreg::{enums::HKEY_CURRENT_USER, RegKey};
+
+    let key_path = format!(r"Software\Classes\AppUserModelId\{}", APP_USER_MODEL_ID);
+    let (key, _) = RegKey::predef(HKEY_CURRENT_USER)
+        .create_subkey(&key_path)
+        .map_err(|e| format!("Failed to create {}: {}", key_path, e))?;
+    key.set_value("DisplayName", &APP_NAME)
+        .map_err(|e| format!("Failed to set DisplayName: {}", e))?;
+
+    let id: Vec<u16> = APP_USER_MODEL_ID.encode_utf16().chain(Some(0)).collect();
+    let hr = unsafe { SetCurrentProcessExplicitAppUserModelID(id.as_ptr()) };
+    if hr < 0 {
+        return Err(format!(
+            "SetCurrentProcessExplicitAppUserModelID failed: {:#x}",
+            hr
+        ));
+    }
+    Ok(())
+}
+
+pub fn notify(summary: &str, body: &str) {
+    tracing::info!("Notification: {} - {}", summary, body);
+    show(summary, body);
+}
+
+// Same as `notify` but keeps the content out of the log, for things like
+// clipboard amounts the user never asked to have recorded.
+pub fn notify_unlogged(summary: &str, body: &str) {
+    show(summary, body);
+}
+
+#[cfg(not(target_os = "windows"))]
+fn show(summary: &str, body: &str) {
+    if let Err(e) = notify_rust::Notification::new()
+        .appname(APP_NAME)
+        .summary(summary)
+        .body(body)
+        .show()
+    {
+        tracing::warn!("Failed to show notification '{}': {}", summary, e);
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn show(summary: &str, body: &str) {
+    use tauri_winrt_notification::Toast;
+
+    if let Err(e) = Toast::new(APP_USER_MODEL_ID)
+        .title(summary)
+        .text1(body)
+        .show()
+    {
+        tracing::warn!("Failed to show toast '{}': {:?}", summary, e);
+    }
+}
+
+// Blocking error dialog for failures the user must see before the app exits.
+// Notifications can be missed or unavailable, so Windows gets a real message box.
+pub fn error_dialog(summary: &str, body: &str) {
+    tracing::error!("{}: {}", summary, body);
+    show_error_dialog(summary, body);
+}
+
+#[cfg(not(target_os = "windows"))]
+fn show_error_dialog(summary: &str, body: &str) {
+    show(summary, body);
+}
+
+#[cfg(target_os = "windows")]
+fn show_error_dialog(summary: &str, body: &str) {
+    use windows_sys::Win32::UI::WindowsAndMessaging::{MessageBoxW, MB_ICONERROR, MB_OK};
+
+    let wide = |s: &str| s.encode_utf16().chain(Some(0)).collect::<Vec<u16>>();
+    let text = wide(body);
+    let caption = wide(&format!("{} - {}", APP_NAME, summary));
+    unsafe {
+        MessageBoxW(
+            std::ptr::null_mut(),
+            text.as_ptr(),
+            caption.as_ptr(),
+            MB_OK | MB_ICONERROR,
+        );
+    }
+}
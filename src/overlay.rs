@@ -0,0 +1,44 @@
+// --- Stream Overlay ---
+// For streamers who keep the dollar rate on screen. OBS can show a text file
+// ("Text (GDI+)" with "Read from file") or a browser source; the file is
+// rewritten after every update cycle from `[overlay] template`, and the API
+// serves a self-updating page on `/overlay`.
+use rusqlite::Connection;
+use std::{fs, path::Path};
+use tracing::warn;
+
+use crate::{config::OverlayConfig, db, format_rate};
+
+// Replaces each `{symbol}` in the template with that symbol's latest rate,
+// or "--" when there is none yet.
+pub fn render(conn: &Connection, template: &str) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        let symbol = &rest[start + 1..start + len];
+        match db::latest_rate(conn, symbol) {
+            Ok(Some(rate)) => out.push_str(&format_rate(rate)),
+            _ => out.push_str("--"),
+        }
+        rest = &rest[start + len + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+pub fn write_text_file(conn: &Connection, config: &OverlayConfig) {
+    if config.text_file.is_empty() {
+        return;
+    }
+    let text = render(conn, &config.template);
+    // Write then rename so OBS never picks up a half-written file.
+    let path = Path::new(&config.text_file);
+    let tmp = path.with_extension("tmp");
+    if let Err(e) = fs::write(&tmp, text).and_then(|()| fs::rename(&tmp, path)) {
+        warn!("Failed to write overlay file {}: {}", path.display(), e);
+    }
+}
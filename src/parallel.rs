@@ -0,0 +1,68 @@
+// --- Parallel Slot ---
+// With `[parallel] chain = ["binance", "zelle", ...]` the tray shows a single
+// "parallel USD" value instead of one per source: the first symbol in the
+// chain updated within `max_age_minutes`, or, when every one is stale, the
+// first that has a reading at all. The menu, converter and API still list
+// every source.
+use chrono::{DateTime, Duration, Utc};
+
+use crate::config::ParallelConfig;
+
+// Index into `readings` (symbol, last update) of the source to show, or None
+// when no chain member is among them.
+pub fn pick(
+    config: &ParallelConfig,
+    readings: &[(&str, Option<DateTime<Utc>>)],
+    now: DateTime<Utc>,
+) -> Option<usize> {
+    let members: Vec<usize> = config
+        .chain
+        .iter()
+        .filter_map(|symbol| readings.iter().position(|(s, _)| s == symbol))
+        .collect();
+    let cutoff = now - Duration::minutes(config.max_age_minutes.into());
+    let updated = |i: &usize| readings[*i].1;
+    members
+        .iter()
+        .copied()
+        .find(|i| updated(i).is_some_and(|at| at >= cutoff))
+        .or_else(|| members.iter().copied().find(|i| updated(i).is_some()))
+        .or_else(|| members.first().copied())
+}
+
+pub fn is_member(config: &ParallelConfig, symbol: &str) -> bool {
+    config.chain.iter().any(|s| s == symbol)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chain(symbols: &[&str]) -> ParallelConfig {
+        ParallelConfig {
+            chain: symbols.iter().map(|s| s.to_string()).collect(),
+            max_age_minutes: 60,
+        }
+    }
+
+    #[test]
+    fn first_fresh_member_wins() {
+        let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let readings = [
+            ("bcv", Some(now)),
+            ("binance", Some(now - Duration::hours(3))),
+            ("zelle", Some(now - Duration::minutes(10))),
+        ];
+        let config = chain(&["binance", "zelle"]);
+        assert_eq!(pick(&config, &readings, now), Some(2));
+    }
+
+    #[test]
+    fn stale_data_beats_none() {
+        let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let readings = [("binance", None), ("zelle", Some(now - Duration::hours(5)))];
+        assert_eq!(pick(&chain(&["binance", "zelle"]), &readings, now), Some(1));
+        assert_eq!(pick(&chain(&["binance"]), &readings, now), Some(0));
+        assert_eq!(pick(&chain(&["eldorado"]), &readings, now), None);
+    }
+}
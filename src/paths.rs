@@ -0,0 +1,126 @@
+// --- Filesystem Locations ---
+use directories::{BaseDirs, ProjectDirs};
+use std::{
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+use tracing::info;
+
+const DATABASE_FILE: &str = "bin.db";
+const DEMO_DATABASE_FILE: &str = "demo.db";
+const BACKUPS_DIR: &str = "backups";
+const CHARTS_DIR: &str = "charts";
+const LEGACY_DATABASE_PATH: &str = ".local/share/money/bin.db";
+
+// Set from `--db-path`; replaces bin.db for the app and every subcommand.
+static DATABASE_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+pub fn set_database_path(path: PathBuf) {
+    let _ = DATABASE_OVERRIDE.set(path);
+}
+
+pub fn project_dirs() -> Result<ProjectDirs, String> {
+    ProjectDirs::from("com", "ruvasqm", "bcv-tray")
+        .ok_or_else(|| "Could not determine a home directory for application data".to_string())
+}
+
+// Linux: ~/.local/share/bcv-tray, macOS: ~/Library/Application Support/com.ruvasqm.bcv-tray,
+// Windows: %APPDATA%\ruvasqm\bcv-tray\data
+pub fn data_dir() -> Result<PathBuf, String> {
+    Ok(project_dirs()?.data_dir().to_path_buf())
+}
+
+pub fn config_dir() -> Result<PathBuf, String> {
+    Ok(project_dirs()?.config_dir().to_path_buf())
+}
+
+// Files here override the embedded icons, font and overlay page.
+pub fn assets_dir() -> Result<PathBuf, String> {
+    Ok(config_dir()?.join("assets"))
+}
+
+pub fn database_path() -> Result<PathBuf, String> {
+    if let Some(path) = DATABASE_OVERRIDE.get() {
+        return Ok(path.clone());
+    }
+    let path = ensure_data_dir()?.join(DATABASE_FILE);
+    if !path.exists() {
+        if let Some(legacy) = legacy_database_path().filter(|p| p.exists()) {
+            migrate_legacy_database(&legacy, &path)?;
+        }
+    }
+    Ok(path)
+}
+
+// Kept apart from the real database so demo rates never leak into it.
+pub fn demo_database_path() -> Result<PathBuf, String> {
+    Ok(ensure_data_dir()?.join(DEMO_DATABASE_FILE))
+}
+
+// Where the tray menu's "Back Up Database" puts timestamped copies.
+pub fn backup_path() -> Result<PathBuf, String> {
+    let dir = ensure_data_dir()?.join(BACKUPS_DIR);
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Could not create backup directory {}: {}", dir.display(), e))?;
+    let name = format!("bin-{}.db", chrono::Local::now().format("%Y%m%d-%H%M%S"));
+    Ok(dir.join(name))
+}
+
+// Where the tray menu's "Save Chart as PNG" puts its images.
+pub fn chart_path(symbol: &str) -> Result<PathBuf, String> {
+    let dir = ensure_data_dir()?.join(CHARTS_DIR);
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Could not create chart directory {}: {}", dir.display(), e))?;
+    let name = format!(
+        "{}-{}.png",
+        symbol,
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    );
+    Ok(dir.join(name))
+}
+
+fn ensure_data_dir() -> Result<PathBuf, String> {
+    let dir = data_dir()?;
+    std::fs::create_dir_all(&dir).map_err(|e| {
+        format!(
+            "Could not create data directory {}: {}. Check that it is writable.",
+            dir.display(),
+            e
+        )
+    })?;
+    Ok(dir)
+}
+
+fn legacy_database_path() -> Option<PathBuf> {
+    BaseDirs::new().map(|dirs| dirs.home_dir().join(LEGACY_DATABASE_PATH))
+}
+
+// Copies rather than moves so an older build pointed at the legacy path keeps working.
+fn migrate_legacy_database(legacy: &Path, target: &Path) -> Result<(), String> {
+    info!(
+        "Migrating database from legacy location {} to {}",
+        legacy.display(),
+        target.display()
+    );
+    for sidecar in ["", "-wal", "-shm"] {
+        let src = append_to_path(legacy, sidecar);
+        if src.exists() {
+            let dst = append_to_path(target, sidecar);
+            std::fs::copy(&src, &dst).map_err(|e| {
+                format!(
+                    "Failed to copy {} to {}: {}",
+                    src.display(),
+                    dst.display(),
+                    e
+                )
+            })?;
+        }
+    }
+    Ok(())
+}
+
+pub fn append_to_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
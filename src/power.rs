@@ -0,0 +1,114 @@
+// --- Power State ---
+// On battery (or with the OS battery saver on) scheduled updates are spread
+// out and optional sources paused; plugging back in restores the normal
+// cadence within a minute. Anything we can't read counts as AC power.
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+use tracing::info;
+
+use crate::config::PowerConfig;
+
+const RECHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+static ON_BATTERY: AtomicBool = AtomicBool::new(false);
+
+#[cfg(target_os = "linux")]
+fn query_on_battery() -> bool {
+    use std::fs;
+
+    // Any online AC adapter means we're plugged in; otherwise a discharging
+    // battery means we're not. Desktops have neither and stay on "AC".
+    let Ok(entries) = fs::read_dir("/sys/class/power_supply") else {
+        return false;
+    };
+    let mut discharging = false;
+    for entry in entries.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        let read = |name: &str| {
+            fs::read_to_string(path.join(name))
+                .map(|s| s.trim().to_string())
+                .unwrap_or_default()
+        };
+        match read("type").as_str() {
+            "Mains" if read("online") == "1" => return false,
+            "Battery" if read("status") == "Discharging" => discharging = true,
+            _ => {}
+        }
+    }
+    discharging
+        || fs::read_to_string("/sys/firmware/acpi/platform_profile")
+            .is_ok_and(|profile| profile.trim() == "low-power")
+}
+
+#[cfg(target_os = "windows")]
+fn query_on_battery() -> bool {
+    use windows_sys::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+
+    let mut status: SYSTEM_POWER_STATUS = unsafe { std::mem::zeroed() };
+    if unsafe { GetSystemPowerStatus(&mut status) } == 0 {
+        return false;
+    }
+    // ACLineStatus 0 is offline; SystemStatusFlag 1 is battery saver.
+    status.ACLineStatus == 0 || status.SystemStatusFlag == 1
+}
+
+#[cfg(target_os = "macos")]
+fn query_on_battery() -> bool {
+    std::process::Command::new("pmset")
+        .args(["-g", "batt"])
+        .output()
+        .map(|out| String::from_utf8_lossy(&out.stdout).contains("'Battery Power'"))
+        .unwrap_or(false)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+fn query_on_battery() -> bool {
+    false
+}
+
+// Re-reads the power state and logs when it changes. Called once per cycle.
+pub fn refresh(config: &PowerConfig) -> bool {
+    let on_battery = config.throttle_on_battery && query_on_battery();
+    if ON_BATTERY.swap(on_battery, Ordering::Relaxed) != on_battery {
+        info!(
+            "Running on {}, {} update cadence",
+            if on_battery { "battery" } else { "AC power" },
+            if on_battery { "reduced" } else { "normal" }
+        );
+    }
+    on_battery
+}
+
+pub fn on_battery() -> bool {
+    ON_BATTERY.load(Ordering::Relaxed)
+}
+
+fn stretch(interval: Duration, config: &PowerConfig) -> Duration {
+    if on_battery() {
+        interval.saturating_mul(config.battery_interval_multiplier.max(1))
+    } else {
+        interval
+    }
+}
+
+// Sleeps until the next scheduled update is due. The power state is checked
+// every minute, so plugging in cuts a stretched wait short.
+pub fn wait(interval: Duration, config: &PowerConfig) {
+    let started = Instant::now();
+    loop {
+        let due = stretch(interval, config);
+        let Some(remaining) = due.checked_sub(started.elapsed()) else {
+            return;
+        };
+        thread::sleep(remaining.min(RECHECK_INTERVAL));
+        refresh(config);
+    }
+}
+
+// Sources listed in `pause_on_battery` wait for AC power, except on "Update Now".
+pub fn is_paused(symbol: &str, trigger: &str, config: &PowerConfig) -> bool {
+    trigger != "manual" && on_battery() && config.pause_on_battery.iter().any(|s| s == symbol)
+}
@@ -0,0 +1,1766 @@
+// --- Rate Providers ---
+// Each fetcher returns the rate to store or a human-readable reason it couldn't.
+use regex_automata::meta::Regex;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{blocking::Client, StatusCode};
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    net::{IpAddr, SocketAddr},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex, OnceLock,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+use tracing::{debug, info, warn};
+
+use crate::{
+    aggregate::{self, Weighting},
+    audit, browser,
+    config::{
+        BcvConfig, BinanceConfig, Config, DnsConfig, HttpConfig, NoonesConfig, ProvidersConfig,
+        ProxyConfig, ReserveConfig, ZelleConfig,
+    },
+    depeg, doh, fiat, fixtures, import, redact,
+    secrets::{self, SecretName},
+};
+
+const BCV_URL: &str = "https://www.bcv.org.ve/";
+// The full path to the USD box; `[bcv] selector` replaces it.
+const BCV_CSS_SELECTOR: &str = "html > body > div:nth-of-type(4) > div:nth-of-type(1) > div:nth-of-type(2) > div:nth-of-type(1) > div:nth-of-type(1) > div:nth-of-type(1) > section:nth-of-type(1) > div:nth-of-type(1) > div:nth-of-type(2) > div:nth-of-type(1) > div:nth-of-type(7) > div:nth-of-type(1) > div:nth-of-type(1) > div:nth-of-type(2) > strong";
+
+const BINANCE_P2P_URL: &str = "https://p2p.binance.com/bapi/c2c/v2/friendly/c2c/adv/search";
+// What a browser sends to the search endpoint; requests without them get
+// rejected. reqwest handles the decompression.
+const BINANCE_HEADERS: &[(&str, &str)] = &[
+    ("Accept", "*/*"),
+    ("Accept-Encoding", "gzip, deflate, br"),
+    ("Accept-Language", "en-GB,en-US;q=0.9,en;q=0.8"),
+    ("Cache-Control", "no-cache"),
+    ("Connection", "keep-alive"),
+    ("Content-Type", "application/json"),
+    ("Host", "p2p.binance.com"),
+    ("Origin", "https://p2p.binance.com"),
+    ("Pragma", "no-cache"),
+    ("TE", "Trailers"),
+    (
+        "User-Agent",
+        "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:88.0) Gecko/20100101 Firefox/88.0",
+    ),
+];
+// The most ads the search endpoint returns per page.
+const BINANCE_MAX_ADS: u32 = 20;
+
+// Colombian TRM published by the Superintendencia Financiera on datos.gov.co.
+const TRM_URL: &str =
+    "https://www.datos.gov.co/resource/32sa-8pi3.json?$order=vigenciadesde%20DESC&$limit=1";
+
+// USDT/USD spot, for the de-peg monitor.
+const KRAKEN_USDT_URL: &str = "https://api.kraken.com/0/public/Ticker?pair=USDTZUSD";
+
+// Paxful-style API: client-credentials token, then form-encoded offer search.
+const NOONES_TOKEN_URL: &str = "https://auth.noones.com/oauth2/token";
+const NOONES_OFFERS_URL: &str = "https://api.noones.com/noones/v1/offer/all";
+const NOONES_MAX_ADS: u32 = 50;
+
+// Public web preview of a channel, with its latest posts.
+const TELEGRAM_PREVIEW_URL: &str = "https://t.me/s/";
+
+const DOLARAPI_BASE_URL: &str = "https://dolarapi.com/v1/dolares";
+
+const CMC_BASE_URL: &str = "https://pro-api.coinmarketcap.com/v2/cryptocurrency/quotes/latest";
+const CMC_BTC_QUERY: &str = "id=1";
+const SATS_PER_BTC: f64 = 100_000_000.0;
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct BinanceP2PRequestPayload {
+    asset: String,
+    fiat: String,
+    merchant_check: bool,
+    page: u32,
+    pay_types: Vec<String>,
+    publisher_type: Option<String>, // Will be serialized as null if None
+    rows: u32,
+    trade_type: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct BinanceResponse {
+    code: String,
+    // message: Option<String>, // Not strictly needed for price extraction
+    // messageDetail: Option<String>, // Not strictly needed
+    data: Option<Vec<BinanceAdvContainer>>,
+    success: bool,
+}
+
+#[derive(Deserialize, Debug)]
+struct BinanceAdvContainer {
+    adv: BinanceAdv,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct BinanceAdv {
+    price: String, // Price is a string in the JSON
+    // USDT still available in the ad; weights the "volume" aggregation.
+    surplus_amount: Option<String>,
+}
+
+// Set once at startup from `[binance]`.
+static BINANCE: OnceLock<BinanceConfig> = OnceLock::new();
+
+pub fn configure_binance(config: &BinanceConfig) {
+    let _ = BINANCE.set(config.clone());
+}
+
+// The Binance P2P rate for `fiat`, with the pay types `[binance]` sets for it.
+fn fetch_binance_for(http_client: &Client, fiat: &str) -> Result<f64, String> {
+    let settings = BINANCE.get().cloned().unwrap_or_default();
+    let pay_types = settings.pay_types.get(fiat).cloned().unwrap_or_default();
+    let pay_types: Vec<&str> = pay_types.iter().map(String::as_str).collect();
+    fetch_binance(http_client, fiat, &pay_types)
+}
+
+static BCV: OnceLock<BcvConfig> = OnceLock::new();
+
+// Set once at startup from `[providers]`.
+static PROVIDERS: OnceLock<ProvidersConfig> = OnceLock::new();
+
+pub fn configure_providers(config: &ProvidersConfig) {
+    for provider in &config.disabled {
+        info!("Provider '{}' disabled in config", provider);
+    }
+    let _ = PROVIDERS.set(config.clone());
+}
+
+// False when `symbol` comes from a provider in `[providers] disabled`; such
+// symbols are skipped by every update and left out of the tray.
+pub fn enabled(symbol: &str) -> bool {
+    PROVIDERS
+        .get()
+        .is_none_or(|config| config.enabled(provider_key(symbol)))
+}
+
+// The key `symbol`'s provider goes by in `[http.headers]` and
+// `[providers] disabled`; "" for derived symbols, which fetch nothing.
+pub fn provider_key(symbol: &str) -> &'static str {
+    match symbol {
+        "bcv" => "bcv",
+        "binance" | "binance_cop" | "binance_ars" => "binance",
+        "trm" => "trm",
+        "ars_oficial" => "dolarapi",
+        depeg::SYMBOL => "kraken",
+        ZELLE_SYMBOL => "zelle",
+        NOONES_SYMBOL => "noones",
+        RESERVE_SYMBOL => "reserve",
+        _ if uses_cmc(symbol) => "cmc",
+        _ => match configured(symbol).map(|source| &source.fetch) {
+            Some(ConfiguredFetch::Telegram { .. }) => "telegram",
+            Some(ConfiguredFetch::Scrape { .. }) => "scrape",
+            _ => "",
+        },
+    }
+}
+
+pub fn configure_bcv(config: &BcvConfig) {
+    for mirror in config
+        .mirrors
+        .iter()
+        .filter(|url| url.starts_with("http://"))
+    {
+        warn!(
+            "[bcv] mirror {} is plain HTTP; anyone on the network path can change its rate",
+            mirror
+        );
+    }
+    let _ = BCV.set(config.clone());
+}
+
+pub const ZELLE_SYMBOL: &str = "zelle";
+pub const NOONES_SYMBOL: &str = "noones";
+pub const RESERVE_SYMBOL: &str = "reserve";
+
+// Extra VES sources, each off unless its config section enables it.
+const OPTIONAL_SOURCES: [fiat::Source; 3] = [
+    fiat::Source {
+        name: "ZEL",
+        icon: "",
+        symbol: ZELLE_SYMBOL,
+        provider: "Zelle (aggregator)",
+        unit: "Bs",
+    },
+    fiat::Source {
+        name: "NOO",
+        icon: "",
+        symbol: NOONES_SYMBOL,
+        provider: "Noones P2P (via BTC)",
+        unit: "Bs",
+    },
+    fiat::Source {
+        name: "RSV",
+        icon: "",
+        symbol: RESERVE_SYMBOL,
+        provider: "Reserve",
+        unit: "Bs",
+    },
+];
+
+// Set once at startup from `[zelle]`, `[noones]` and `[reserve]`.
+static ZELLE: OnceLock<ZelleConfig> = OnceLock::new();
+static NOONES: OnceLock<(NoonesConfig, Option<(String, String)>)> = OnceLock::new();
+static RESERVE: OnceLock<ReserveConfig> = OnceLock::new();
+
+pub fn configure_optional(zelle: &ZelleConfig, noones: &NoonesConfig, reserve: &ReserveConfig) {
+    let _ = ZELLE.set(zelle.clone());
+    if reserve.enabled && reserve.url.is_empty() {
+        warn!("[reserve] is enabled without a url; it will be skipped.");
+    }
+    let _ = RESERVE.set(reserve.clone());
+    let credentials = if noones.enabled {
+        noones_credentials()
+    } else {
+        None
+    };
+    let _ = NOONES.set((noones.clone(), credentials));
+}
+
+// The keyring entry holds "<client id>:<client secret>".
+fn noones_credentials() -> Option<(String, String)> {
+    let stored = match secrets::get(SecretName::NoonesApi) {
+        Ok(stored) => stored,
+        Err(e) => {
+            warn!("{}", e);
+            None
+        }
+    };
+    let Some((id, secret)) = stored.as_deref().and_then(|s| s.trim().split_once(':')) else {
+        warn!(
+            "Noones is enabled but no credentials are stored \
+             (`bcv-tray secret set noones-api`, as <id>:<secret>); it will be skipped."
+        );
+        return None;
+    };
+    redact::register_secret(secret);
+    Some((id.to_string(), secret.to_string()))
+}
+
+pub fn optional_sources() -> impl Iterator<Item = &'static fiat::Source> {
+    OPTIONAL_SOURCES
+        .iter()
+        .filter(|source| match source.symbol {
+            ZELLE_SYMBOL => ZELLE.get().is_some_and(|zelle| zelle.enabled),
+            NOONES_SYMBOL => NOONES
+                .get()
+                .is_some_and(|(noones, credentials)| noones.enabled && credentials.is_some()),
+            RESERVE_SYMBOL => RESERVE
+                .get()
+                .is_some_and(|reserve| reserve.enabled && !reserve.url.is_empty()),
+            _ => false,
+        })
+}
+
+// Sources declared in the config (`[cryptos]`, `[telegram]`, `[scrape]`), in
+// the order the tray shows them.
+pub struct ConfiguredSource {
+    pub symbol: String,
+    pub label: String,
+    pub icon: String,
+    pub unit: String,
+    pub provider: String,
+    fetch: ConfiguredFetch,
+}
+
+enum ConfiguredFetch {
+    // CoinMarketCap ticker, priced in USD.
+    Cmc(String),
+    Telegram {
+        channel: String,
+        pattern: Regex,
+    },
+    Scrape {
+        url: String,
+        selector: Selector,
+        pattern: Option<Regex>,
+        browser: bool,
+    },
+}
+
+static CONFIGURED: OnceLock<Vec<ConfiguredSource>> = OnceLock::new();
+
+// Symbols that clash with a built-in one and invalid patterns are reported
+// here and skipped.
+pub fn configure_sources(config: &Config) {
+    let builtin = |table: &str, symbol: &str| {
+        let clash = fiat::all_sources().any(|source| source.symbol == symbol);
+        if clash {
+            warn!(
+                "[{}.{}] clashes with a built-in symbol, ignoring it",
+                table, symbol
+            );
+        }
+        clash
+    };
+    let cryptos = config
+        .cryptos
+        .iter()
+        .filter(|(symbol, _)| !builtin("cryptos", symbol))
+        .map(|(symbol, crypto)| ConfiguredSource {
+            symbol: symbol.clone(),
+            label: crypto.label(symbol),
+            icon: crypto.icon.clone(),
+            unit: "USD".to_string(),
+            provider: "CoinMarketCap".to_string(),
+            fetch: ConfiguredFetch::Cmc(crypto.coin(symbol)),
+        });
+    let telegram = config
+        .telegram
+        .iter()
+        .filter(|(symbol, _)| !builtin("telegram", symbol))
+        .filter_map(|(symbol, channel)| {
+            let pattern = Regex::new(&channel.pattern)
+                .map_err(|e| {
+                    warn!(
+                        "[telegram.{}] pattern is invalid, ignoring it: {}",
+                        symbol, e
+                    )
+                })
+                .ok()?;
+            Some(ConfiguredSource {
+                symbol: symbol.clone(),
+                label: channel
+                    .label
+                    .clone()
+                    .unwrap_or_else(|| symbol.to_uppercase()),
+                icon: channel.icon.clone(),
+                unit: channel.unit.clone(),
+                provider: format!("t.me/{}", channel.channel),
+                fetch: ConfiguredFetch::Telegram {
+                    channel: channel.channel.clone(),
+                    pattern,
+                },
+            })
+        });
+    let scrape = config
+        .scrape
+        .iter()
+        .filter(|(symbol, _)| !builtin("scrape", symbol))
+        .filter_map(|(symbol, page)| {
+            let invalid = |what: &str, e: String| {
+                warn!(
+                    "[scrape.{}] {} is invalid, ignoring it: {}",
+                    symbol, what, e
+                )
+            };
+            let selector = Selector::parse(&page.selector)
+                .map_err(|e| invalid("selector", format!("{:?}", e)))
+                .ok()?;
+            let pattern = match page.pattern.as_str() {
+                "" => None,
+                pattern => Some(
+                    Regex::new(pattern)
+                        .map_err(|e| invalid("pattern", e.to_string()))
+                        .ok()?,
+                ),
+            };
+            if page.browser && cfg!(not(feature = "browser")) {
+                warn!(
+                    "[scrape.{}] needs a build with the `browser` feature; it will fail",
+                    symbol
+                );
+            }
+            Some(ConfiguredSource {
+                symbol: symbol.clone(),
+                label: page.label.clone().unwrap_or_else(|| symbol.to_uppercase()),
+                icon: page.icon.clone(),
+                unit: page.unit.clone(),
+                provider: page.url.clone(),
+                fetch: ConfiguredFetch::Scrape {
+                    url: page.url.clone(),
+                    selector,
+                    pattern,
+                    browser: page.browser,
+                },
+            })
+        });
+    let _ = CONFIGURED.set(cryptos.chain(telegram).chain(scrape).collect());
+}
+
+pub fn configured_sources() -> &'static [ConfiguredSource] {
+    CONFIGURED.get().map(Vec::as_slice).unwrap_or_default()
+}
+
+fn configured(symbol: &str) -> Option<&'static ConfiguredSource> {
+    configured_sources()
+        .iter()
+        .find(|source| source.symbol == symbol)
+}
+
+// Whether `symbol` is priced by CoinMarketCap and so needs a key.
+pub fn uses_cmc(symbol: &str) -> bool {
+    symbol == "satoshi"
+        || configured(symbol).is_some_and(|source| matches!(source.fetch, ConfiguredFetch::Cmc(_)))
+}
+
+#[derive(Deserialize, Debug)]
+struct TrmEntry {
+    valor: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct DolarApiQuote {
+    venta: f64,
+}
+
+// One or more CoinMarketCap keys, e.g. a team's free-tier keys. The current
+// one is used until CMC answers that it is rate limited or out of credits;
+// then the next is tried, and stays current from then on.
+#[derive(Default)]
+pub struct CmcKeys {
+    keys: Vec<String>,
+    current: AtomicUsize,
+}
+
+impl CmcKeys {
+    // Keys separated by commas, spaces or newlines.
+    pub fn parse(text: &str) -> Self {
+        CmcKeys {
+            keys: text
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|key| !key.is_empty())
+                .map(str::to_string)
+                .collect(),
+            current: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.keys.iter().map(String::as_str)
+    }
+}
+
+fn build_http_client() -> reqwest::Result<Client> {
+    http_client_builder().build()
+}
+
+static BASE_CLIENT: OnceLock<Client> = OnceLock::new();
+static BCV_CLIENT: OnceLock<Client> = OnceLock::new();
+
+// The client every fetch starts from; `fetch` swaps in the proxied or DoH one
+// per symbol. Built once, after `configure_http`.
+pub fn http_client() -> Result<Client, String> {
+    if let Some(client) = BASE_CLIENT.get() {
+        return Ok(client.clone());
+    }
+    let client = build_http_client().map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+    Ok(BASE_CLIENT.get_or_init(|| client).clone())
+}
+
+// The direct client for BCV pages; see `Clients`.
+fn bcv_client() -> Result<Client, String> {
+    if let Some(client) = BCV_CLIENT.get() {
+        return Ok(client.clone());
+    }
+    let client = bcv_builder(http_client_builder())
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+    Ok(BCV_CLIENT.get_or_init(|| client).clone())
+}
+
+static HTTP: OnceLock<HttpConfig> = OnceLock::new();
+
+pub fn configure_http(config: &HttpConfig) {
+    let _ = HTTP.set(config.clone());
+}
+
+fn http_client_builder() -> reqwest::blocking::ClientBuilder {
+    let user_agent = HTTP
+        .get()
+        .map(|http| http.user_agent.clone())
+        .unwrap_or_else(|| HttpConfig::default().user_agent);
+    Client::builder()
+        .user_agent(user_agent)
+        .timeout(Duration::from_secs(15))
+}
+
+// bcv.org.ve serves a certificate chain that doesn't verify, so its pages are
+// the only requests sent without checking the certificate. Everything else,
+// credentials included, goes over verified TLS.
+fn bcv_builder(builder: reqwest::blocking::ClientBuilder) -> reqwest::blocking::ClientBuilder {
+    builder.danger_accept_invalid_certs(true)
+}
+
+// A fetch client (proxied or DoH) and its BCV twin, built with the same settings.
+#[derive(Clone)]
+struct Clients {
+    verified: Client,
+    bcv: Client,
+}
+
+impl Clients {
+    fn build(
+        configure: impl Fn(reqwest::blocking::ClientBuilder) -> reqwest::blocking::ClientBuilder,
+    ) -> reqwest::Result<Self> {
+        Ok(Clients {
+            verified: configure(http_client_builder()).build()?,
+            bcv: configure(bcv_builder(http_client_builder())).build()?,
+        })
+    }
+
+    fn for_symbol(&self, symbol: &str) -> &Client {
+        if symbol == "bcv" {
+            &self.bcv
+        } else {
+            &self.verified
+        }
+    }
+}
+
+// `[proxy]`: clients going through the proxy, and the symbols that use them
+// (all when empty).
+static PROXIED: OnceLock<(Clients, Vec<String>)> = OnceLock::new();
+
+pub fn configure_proxy(config: &ProxyConfig) {
+    if config.url.is_empty() {
+        return;
+    }
+    // user:password@host URLs carry a password.
+    if config.url.contains('@') {
+        redact::register_secret(&config.url);
+    }
+    let clients = reqwest::Proxy::all(&config.url)
+        .and_then(|proxy| Clients::build(|builder| builder.proxy(proxy.clone())));
+    match clients {
+        Ok(clients) => {
+            let _ = PROXIED.set((clients, config.symbols.clone()));
+        }
+        Err(e) => warn!(
+            "[proxy] url is unusable, fetching directly: {}",
+            redact::redact(&e.to_string())
+        ),
+    }
+}
+
+// Headers for a request to `provider`: its built-in ones with the
+// `[http.headers.<provider>]` entries applied on top. An empty value drops a
+// built-in header.
+fn provider_headers(provider: &str, defaults: &[(&str, &str)]) -> HeaderMap {
+    let overrides = HTTP.get().and_then(|http| http.headers.get(provider));
+    let mut headers = HeaderMap::new();
+    let entries = defaults.iter().map(|(name, value)| (*name, *value)).chain(
+        overrides
+            .into_iter()
+            .flatten()
+            .map(|(n, v)| (n.as_str(), v.as_str())),
+    );
+    for (name, value) in entries {
+        let Ok(name) = HeaderName::from_bytes(name.as_bytes()) else {
+            warn!(
+                "[http.headers.{}] has an invalid header name '{}'",
+                provider, name
+            );
+            continue;
+        };
+        if value.is_empty() {
+            headers.remove(&name);
+            continue;
+        }
+        match HeaderValue::from_str(value) {
+            Ok(value) => {
+                headers.insert(name, value);
+            }
+            Err(_) => warn!(
+                "[http.headers.{}] has an invalid value for {}",
+                provider, name
+            ),
+        }
+    }
+    headers
+}
+
+fn proxied_clients(symbol: &str) -> Option<&'static Clients> {
+    let (clients, symbols) = PROXIED.get()?;
+    (symbols.is_empty() || symbols.iter().any(|s| s == symbol)).then_some(clients)
+}
+
+// `[dns]`: with DoH on, fetches use clients that connect to addresses looked
+// up over DoH. Each host is looked up again after DOH_REFRESH since CDN
+// addresses move, or after DOH_RETRY if the DoH server couldn't resolve it.
+// Lookups run without holding the lock: meanwhile fetches keep the previous
+// clients, or the system resolver before there are any.
+const DOH_REFRESH: Duration = Duration::from_secs(60 * 60);
+const DOH_RETRY: Duration = Duration::from_secs(5 * 60);
+const DOH_TIMEOUT: Duration = Duration::from_secs(5);
+static DNS: OnceLock<DnsConfig> = OnceLock::new();
+static DOH: Mutex<DohState> = Mutex::new(DohState {
+    clients: None,
+    hosts: BTreeMap::new(),
+    looking_up: false,
+});
+
+struct DohState {
+    // The clients and when one of their hosts is due for another lookup.
+    clients: Option<(Clients, Instant)>,
+    // Addresses per host (None when the lookup failed) and when they expire.
+    hosts: BTreeMap<String, (Option<Vec<IpAddr>>, Instant)>,
+    looking_up: bool,
+}
+
+pub fn configure_dns(config: &DnsConfig) {
+    let _ = DNS.set(config.clone());
+}
+
+fn doh_clients() -> Option<Clients> {
+    let config = DNS.get().filter(|dns| dns.doh)?;
+    let due: Vec<String> = {
+        let mut state = DOH.lock().unwrap_or_else(|p| p.into_inner());
+        match &state.clients {
+            Some((clients, stale_at)) if Instant::now() < *stale_at || state.looking_up => {
+                return Some(clients.clone());
+            }
+            None if state.looking_up => return None,
+            _ => {}
+        }
+        state.looking_up = true;
+        let now = Instant::now();
+        provider_hosts()
+            .into_iter()
+            .filter(|host| state.hosts.get(host).is_none_or(|(_, until)| now >= *until))
+            .collect()
+    };
+    let looked_up = lookup_hosts(&config.server, &due);
+
+    let mut state = DOH.lock().unwrap_or_else(|p| p.into_inner());
+    state.looking_up = false;
+    let now = Instant::now();
+    for (host, addresses) in looked_up {
+        let keep = if addresses.is_some() {
+            DOH_REFRESH
+        } else {
+            DOH_RETRY
+        };
+        state.hosts.insert(host, (addresses, now + keep));
+    }
+    let stale_at = state
+        .hosts
+        .values()
+        .map(|(_, until)| *until)
+        .min()
+        .unwrap_or(now + DOH_REFRESH);
+    // reqwest takes the port from the URL, so any will do here.
+    let clients = Clients::build(|builder| {
+        state
+            .hosts
+            .iter()
+            .filter_map(|(host, (addresses, _))| Some((host, addresses.as_ref()?)))
+            .fold(builder, |builder, (host, addresses)| {
+                let addresses: Vec<SocketAddr> =
+                    addresses.iter().map(|ip| SocketAddr::new(*ip, 0)).collect();
+                builder.resolve_to_addrs(host, &addresses)
+            })
+    });
+    match clients {
+        Ok(clients) => {
+            state.clients = Some((clients.clone(), stale_at));
+            Some(clients)
+        }
+        Err(e) => {
+            warn!("Failed to build the DoH client: {}", e);
+            None
+        }
+    }
+}
+
+// All of `hosts` at once, so an unreachable DoH server costs one timeout
+// rather than one per host. None for a host that failed; it falls back to the
+// system resolver until it's retried.
+fn lookup_hosts(server: &str, hosts: &[String]) -> Vec<(String, Option<Vec<IpAddr>>)> {
+    if hosts.is_empty() {
+        return Vec::new();
+    }
+    // Verified TLS: a resolver that can hijack DNS could otherwise answer for
+    // the DoH server too.
+    let client = match http_client_builder().timeout(DOH_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(e) => {
+            warn!("Failed to build the DoH lookup client: {}", e);
+            return hosts.iter().map(|host| (host.clone(), None)).collect();
+        }
+    };
+    thread::scope(|scope| {
+        let lookups: Vec<_> = hosts
+            .iter()
+            .map(|host| scope.spawn(|| doh::lookup(&client, server, host)))
+            .collect();
+        hosts
+            .iter()
+            .zip(lookups)
+            .map(|(host, lookup)| {
+                let addresses = lookup
+                    .join()
+                    .unwrap_or_else(|_| Err(format!("DoH lookup of {} panicked", host)))
+                    .map_err(|e| warn!("{}", e))
+                    .ok();
+                (host.clone(), addresses)
+            })
+            .collect()
+    })
+}
+
+fn provider_hosts() -> Vec<String> {
+    let mut hosts: Vec<String> = provider_urls()
+        .iter()
+        .filter_map(|url| {
+            reqwest::Url::parse(url)
+                .ok()?
+                .host_str()
+                .map(str::to_string)
+        })
+        .collect();
+    hosts.sort();
+    hosts.dedup();
+    hosts
+}
+
+// Every URL a fetcher may request, for looking up their hosts ahead of time.
+fn provider_urls() -> Vec<String> {
+    let mut urls: Vec<String> = [
+        BCV_URL,
+        BINANCE_P2P_URL,
+        TRM_URL,
+        KRAKEN_USDT_URL,
+        NOONES_TOKEN_URL,
+        NOONES_OFFERS_URL,
+        TELEGRAM_PREVIEW_URL,
+        DOLARAPI_BASE_URL,
+        CMC_BASE_URL,
+    ]
+    .iter()
+    .map(|url| url.to_string())
+    .collect();
+    urls.extend(BCV.get().into_iter().flat_map(|bcv| bcv.mirrors.clone()));
+    urls.extend(ZELLE.get().map(|zelle| zelle.url.clone()));
+    urls.extend(RESERVE.get().map(|reserve| reserve.url.clone()));
+    urls.extend(
+        configured_sources()
+            .iter()
+            .filter_map(|source| match &source.fetch {
+                ConfiguredFetch::Scrape { url, .. } => Some(url.clone()),
+                _ => None,
+            }),
+    );
+    urls.sort();
+    urls.dedup();
+    urls
+}
+
+// Fetches the rate stored under `symbol`; see fiat::FIATS for which symbols exist.
+pub fn fetch(symbol: &str, http_client: &Client, cmc_keys: &CmcKeys) -> Result<f64, String> {
+    let proxied = proxied_clients(symbol);
+    let doh = proxied.is_none().then(doh_clients).flatten();
+    let bcv;
+    let http_client = match proxied.or(doh.as_ref()) {
+        Some(clients) => clients.for_symbol(symbol),
+        None if symbol == "bcv" => {
+            bcv = bcv_client()?;
+            &bcv
+        }
+        None => http_client,
+    };
+    match symbol {
+        "bcv" => fetch_bcv(http_client),
+        "binance" => fetch_binance_for(http_client, "VES"),
+        "satoshi" => fetch_cmc_satoshi(http_client, cmc_keys),
+        "trm" => fetch_trm(http_client),
+        "binance_cop" => fetch_binance_for(http_client, "COP"),
+        "ars_oficial" => fetch_dolarapi(http_client, "oficial"),
+        "binance_ars" => fetch_binance_for(http_client, "ARS"),
+        depeg::SYMBOL => fetch_kraken_usdt(http_client),
+        ZELLE_SYMBOL => {
+            let settings = ZELLE.get().cloned().unwrap_or_default();
+            fetch_json_feed(http_client, "zelle", &settings.url, &settings.field)
+        }
+        NOONES_SYMBOL => fetch_noones(http_client),
+        RESERVE_SYMBOL => {
+            let settings = RESERVE.get().cloned().unwrap_or_default();
+            fetch_json_feed(http_client, "reserve", &settings.url, &settings.field)
+        }
+        _ => match configured(symbol).map(|source| &source.fetch) {
+            Some(ConfiguredFetch::Cmc(coin)) => {
+                fetch_cmc_usd(http_client, cmc_keys, &format!("symbol={}", coin))
+            }
+            Some(ConfiguredFetch::Telegram { channel, pattern }) => {
+                fetch_telegram(http_client, channel, pattern)
+            }
+            Some(ConfiguredFetch::Scrape {
+                url,
+                selector,
+                pattern,
+                browser,
+            }) => fetch_scraped(http_client, url, selector, pattern.as_ref(), *browser),
+            None => Err(format!("no provider for symbol '{}'", symbol)),
+        },
+    }
+}
+
+fn error_body(response: reqwest::blocking::Response) -> String {
+    redact::body(
+        &response
+            .text()
+            .unwrap_or_else(|_| "Failed to read error body".to_string()),
+    )
+}
+
+// --- BCV (bcv.org.ve, scraped) ---
+// The home page first, then each `[bcv] mirrors` page in turn when it times
+// out, errors or serves something without the rate (e.g. an anti-bot
+// challenge).
+fn fetch_bcv(http_client: &Client) -> Result<f64, String> {
+    let settings = BCV.get().cloned().unwrap_or_default();
+    let selectors = bcv_selectors(&settings);
+    let mut errors = Vec::new();
+    for url in std::iter::once(BCV_URL).chain(settings.mirrors.iter().map(String::as_str)) {
+        match fetch_bcv_page(http_client, url, &selectors) {
+            Ok(rate) => return Ok(rate),
+            Err(e) => {
+                if !settings.mirrors.is_empty() {
+                    warn!("BCV: {}", e);
+                }
+                errors.push(e);
+            }
+        }
+    }
+    match errors.len() {
+        1 => Err(errors.remove(0)),
+        _ => Err(format!("every BCV page failed: {}", errors.join("; "))),
+    }
+}
+
+// `[bcv] selector` (the built-in full path when empty), then the fallbacks.
+fn bcv_selectors(settings: &BcvConfig) -> Vec<&str> {
+    let primary = if settings.selector.is_empty() {
+        BCV_CSS_SELECTOR
+    } else {
+        &settings.selector
+    };
+    std::iter::once(primary)
+        .chain(settings.fallback_selectors.iter().map(String::as_str))
+        .collect()
+}
+
+fn fetch_bcv_page(http_client: &Client, url: &str, selectors: &[&str]) -> Result<f64, String> {
+    debug!("Fetching BCV rate from {}", url);
+    audit::endpoint("bcv", url);
+    let response = http_client
+        .get(url)
+        .headers(provider_headers("bcv", &[]))
+        .send()
+        .map_err(|e| {
+            format!(
+                "fetch error for {}: {}",
+                url,
+                redact::redact(&e.to_string())
+            )
+        })?;
+    if !response.status().is_success() {
+        let status = response.status();
+        return Err(format!(
+            "request to {} failed with status: {}. Body: {}",
+            url,
+            status,
+            error_body(response)
+        ));
+    }
+    let html_content = response
+        .text()
+        .map_err(|e| format!("failed to read response text from {}: {}", url, e))?;
+    fixtures::record("bcv", "html", &html_content);
+    parse_bcv_html(&html_content, selectors).map_err(|e| format!("{}: {}", url, e))
+}
+
+// The first of `selectors` that finds a node gives the rate. One that doesn't
+// parse is skipped like one that finds nothing, so a typo in the config still
+// leaves the others to try.
+fn parse_bcv_html(html_content: &str, selectors: &[&str]) -> Result<f64, String> {
+    let document = Html::parse_document(html_content);
+    let mut tried = Vec::new();
+    let element = selectors
+        .iter()
+        .find_map(|css| match Selector::parse(css) {
+            Ok(selector) => {
+                let found = document.select(&selector).next();
+                if found.is_none() {
+                    tried.push(format!("'{}'", css));
+                }
+                found
+            }
+            Err(e) => {
+                tried.push(format!("'{}' (invalid: {:?})", css, e));
+                None
+            }
+        })
+        .ok_or_else(|| {
+            format!(
+                "CSS selector {} did not find any node",
+                tried.join(", then ")
+            )
+        })?;
+    let rate_str_raw = element.text().collect::<String>().trim().to_string();
+    debug!("BCV CSS selector raw string: '{}'", rate_str_raw);
+    audit::raw(&rate_str_raw);
+    let rate_str_cleaned = rate_str_raw.replace(".", "").replace(",", ".");
+    rate_str_cleaned.parse::<f64>().map_err(|e| {
+        format!(
+            "failed to parse rate string '{}' to f64: {}",
+            rate_str_cleaned, e
+        )
+    })
+}
+
+// --- Binance P2P (USDT/<fiat>, first SELL ad; no pay types means any) ---
+fn fetch_binance(http_client: &Client, fiat: &str, pay_types: &[&str]) -> Result<f64, String> {
+    debug!(
+        "Fetching Binance P2P {} rate from {}",
+        fiat, BINANCE_P2P_URL
+    );
+    audit::endpoint(
+        "binance",
+        &format!(
+            "{} USDT/{} SELL {}",
+            BINANCE_P2P_URL,
+            fiat,
+            pay_types.join(",")
+        ),
+    );
+    let settings = BINANCE.get().cloned().unwrap_or_default();
+    let binance_payload = BinanceP2PRequestPayload {
+        asset: "USDT".to_string(),
+        fiat: fiat.to_string(),
+        merchant_check: false,
+        page: 1,
+        pay_types: pay_types.iter().map(|p| p.to_string()).collect(),
+        publisher_type: None,
+        rows: settings.ads.clamp(1, BINANCE_MAX_ADS),
+        trade_type: "SELL".to_string(),
+    };
+
+    let response = http_client
+        .post(BINANCE_P2P_URL)
+        .headers(provider_headers("binance", BINANCE_HEADERS))
+        .json(&binance_payload)
+        .send()
+        .map_err(|e| format!("API fetch error: {}", redact::redact(&e.to_string())))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        return Err(format!(
+            "API request failed with status: {}. Body: {}",
+            status,
+            error_body(response)
+        ));
+    }
+    let body = response
+        .text()
+        .map_err(|e| format!("failed to read API response: {}", e))?;
+    fixtures::record("binance", "json", &body);
+    parse_binance_response(&body, settings.weighting)
+}
+
+fn parse_binance_response(body: &str, weighting: Weighting) -> Result<f64, String> {
+    let binance_api_response = serde_json::from_str::<BinanceResponse>(body)
+        .map_err(|e| format!("API JSON parse error: {}", e))?;
+    if !(binance_api_response.success && binance_api_response.code == "000000") {
+        return Err(format!(
+            "API call reported not successful or wrong code. Code: {}, Success: {}. Full response: {}",
+            binance_api_response.code,
+            binance_api_response.success,
+            redact::body(&format!("{:?}", binance_api_response))
+        ));
+    }
+    let first_adv_container = match binance_api_response.data.as_ref() {
+        None => {
+            return Err(format!(
+                "'data' field is null or missing in API response. Full response: {}",
+                redact::body(&format!("{:?}", binance_api_response))
+            ))
+        }
+        Some(data_vec) => data_vec.first().ok_or_else(|| {
+            format!(
+                "'data' array is empty in API response. Full response: {}",
+                redact::body(&format!("{:?}", binance_api_response))
+            )
+        })?,
+    };
+    let first_price = first_adv_container.adv.price.parse::<f64>().map_err(|e| {
+        format!(
+            "failed to parse price string '{}' to f64: {}",
+            first_adv_container.adv.price, e
+        )
+    })?;
+    // The first ad is the one that has to parse; odd ones further down are skipped.
+    let ads: Vec<(f64, f64)> = binance_api_response
+        .data
+        .iter()
+        .flatten()
+        .filter_map(|container| {
+            let price = container.adv.price.parse::<f64>().ok()?;
+            let volume = container
+                .adv
+                .surplus_amount
+                .as_deref()
+                .and_then(|amount| amount.parse::<f64>().ok())
+                .unwrap_or(0.0);
+            Some((price, volume))
+        })
+        .collect();
+    let prices: Vec<String> = ads.iter().map(|(price, _)| price.to_string()).collect();
+    audit::raw(&prices.join(", "));
+    Ok(aggregate::ads_price(&ads, weighting).unwrap_or(first_price))
+}
+
+// --- TRM (official COP/USD, datos.gov.co) ---
+pub fn fetch_trm(http_client: &Client) -> Result<f64, String> {
+    debug!("Fetching TRM from {}", TRM_URL);
+    audit::endpoint("trm", TRM_URL);
+    let response = http_client
+        .get(TRM_URL)
+        .headers(provider_headers("trm", &[]))
+        .send()
+        .map_err(|e| format!("fetch error: {}", redact::redact(&e.to_string())))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        return Err(format!(
+            "API fail: {}. Body: {}",
+            status,
+            error_body(response)
+        ));
+    }
+    let body = response
+        .text()
+        .map_err(|e| format!("failed to read API response: {}", e))?;
+    fixtures::record("trm", "json", &body);
+    parse_trm(&body)
+}
+
+fn parse_trm(body: &str) -> Result<f64, String> {
+    let entries = serde_json::from_str::<Vec<TrmEntry>>(body)
+        .map_err(|e| format!("JSON parse error: {}", e))?;
+    let entry = entries
+        .first()
+        .ok_or_else(|| "TRM response has no entries".to_string())?;
+    audit::raw(&entry.valor);
+    entry
+        .valor
+        .parse::<f64>()
+        .map_err(|e| format!("failed to parse TRM '{}': {}", entry.valor, e))
+}
+
+// --- DolarApi (ARS/USD quotes; `casa` is e.g. "oficial" or "blue") ---
+pub fn fetch_dolarapi(http_client: &Client, casa: &str) -> Result<f64, String> {
+    let url = format!("{}/{}", DOLARAPI_BASE_URL, casa);
+    debug!("Fetching ARS quote from {}", url);
+    audit::endpoint("dolarapi", &url);
+    let response = http_client
+        .get(&url)
+        .headers(provider_headers("dolarapi", &[]))
+        .send()
+        .map_err(|e| format!("fetch error: {}", redact::redact(&e.to_string())))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        return Err(format!(
+            "API fail: {}. Body: {}",
+            status,
+            error_body(response)
+        ));
+    }
+    let body = response
+        .text()
+        .map_err(|e| format!("failed to read API response: {}", e))?;
+    fixtures::record("dolarapi", "json", &body);
+    let quote = serde_json::from_str::<DolarApiQuote>(&body)
+        .map_err(|e| format!("JSON parse error: {}", e))?;
+    audit::raw(&quote.venta.to_string());
+    Ok(quote.venta)
+}
+
+// --- JSON feeds (Zelle from an aggregator, Reserve): one number at `field` ---
+pub fn fetch_json_feed(
+    http_client: &Client,
+    name: &str,
+    url: &str,
+    field: &str,
+) -> Result<f64, String> {
+    debug!("Fetching {} rate from {}", name, url);
+    audit::endpoint(name, url);
+    let response = http_client
+        .get(url)
+        .headers(provider_headers(name, &[]))
+        .send()
+        .map_err(|e| format!("fetch error: {}", redact::redact(&e.to_string())))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        return Err(format!(
+            "API fail: {}. Body: {}",
+            status,
+            error_body(response)
+        ));
+    }
+    let body = response
+        .text()
+        .map_err(|e| format!("failed to read API response: {}", e))?;
+    fixtures::record(name, "json", &body);
+    parse_json_field(&body, field)
+}
+
+// --- Noones P2P (VES per USD implied by BTC offers priced in each) ---
+pub fn fetch_noones(http_client: &Client) -> Result<f64, String> {
+    let Some((settings, Some((client_id, client_secret)))) = NOONES.get() else {
+        return Err("no Noones credentials configured".to_string());
+    };
+    audit::endpoint("noones", NOONES_OFFERS_URL);
+    let token = noones_token(http_client, client_id, client_secret)?;
+    let ves = noones_price(
+        http_client,
+        &token,
+        settings,
+        "VES",
+        &settings.payment_method,
+    )?;
+    let usd = noones_price(http_client, &token, settings, "USD", "")?;
+    audit::raw(&format!("{} VES/BTC, {} USD/BTC", ves, usd));
+    Ok(ves / usd)
+}
+
+fn noones_token(
+    http_client: &Client,
+    client_id: &str,
+    client_secret: &str,
+) -> Result<String, String> {
+    let response = http_client
+        .post(NOONES_TOKEN_URL)
+        .headers(provider_headers("noones", &[]))
+        .form(&[
+            ("grant_type", "client_credentials"),
+            ("client_id", client_id),
+            ("client_secret", client_secret),
+        ])
+        .send()
+        .map_err(|e| format!("token request failed: {}", redact::redact(&e.to_string())))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        return Err(format!(
+            "token request failed with status {}: {}",
+            status,
+            error_body(response)
+        ));
+    }
+    let body = response
+        .text()
+        .map_err(|e| format!("failed to read token response: {}", e))?;
+    let token = serde_json::from_str::<serde_json::Value>(&body)
+        .ok()
+        .and_then(|value| value["access_token"].as_str().map(str::to_string))
+        .ok_or_else(|| "invalid token response".to_string())?;
+    redact::register_secret(&token);
+    Ok(token)
+}
+
+// Price of one BTC in `currency` from the best offers to buy it.
+fn noones_price(
+    http_client: &Client,
+    token: &str,
+    settings: &NoonesConfig,
+    currency: &str,
+    payment_method: &str,
+) -> Result<f64, String> {
+    debug!(
+        "Fetching Noones {} offers from {}",
+        currency, NOONES_OFFERS_URL
+    );
+    let limit = settings.ads.clamp(1, NOONES_MAX_ADS).to_string();
+    let mut form = vec![
+        ("offer_type", "buy"),
+        ("crypto_currency_code", "btc"),
+        ("currency_code", currency),
+        ("limit", limit.as_str()),
+    ];
+    if !payment_method.is_empty() {
+        form.push(("payment_method", payment_method));
+    }
+    let response = http_client
+        .post(NOONES_OFFERS_URL)
+        .headers(provider_headers("noones", &[]))
+        .bearer_auth(token)
+        .form(&form)
+        .send()
+        .map_err(|e| format!("API fetch error: {}", redact::redact(&e.to_string())))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        return Err(format!(
+            "API request failed with status: {}. Body: {}",
+            status,
+            error_body(response)
+        ));
+    }
+    let body = response
+        .text()
+        .map_err(|e| format!("failed to read API response: {}", e))?;
+    fixtures::record("noones", "json", &body);
+    let offers = parse_noones_offers(&body)?;
+    aggregate::ads_price(&offers, settings.weighting)
+        .ok_or_else(|| format!("no {} offers", currency))
+}
+
+// (price per BTC, BTC available) per offer, cheapest first.
+fn parse_noones_offers(body: &str) -> Result<Vec<(f64, f64)>, String> {
+    let response = serde_json::from_str::<serde_json::Value>(body)
+        .map_err(|e| format!("JSON parse error: {}", e))?;
+    if response["status"] != "success" {
+        return Err(format!("API reported an error: {}", redact::body(body)));
+    }
+    let number = |value: &serde_json::Value| match value {
+        serde_json::Value::String(text) => text.parse::<f64>().ok(),
+        other => other.as_f64(),
+    };
+    let mut offers: Vec<(f64, f64)> = response["data"]["offers"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|offer| {
+            let price = number(&offer["fiat_price_per_crypto"])?;
+            let max = number(&offer["fiat_amount_range_max"]).unwrap_or(0.0);
+            (price.is_finite() && price > 0.0).then_some((price, max / price))
+        })
+        .collect();
+    offers.sort_by(|a, b| a.0.total_cmp(&b.0));
+    if offers.is_empty() {
+        return Err("no offers in response".to_string());
+    }
+    Ok(offers)
+}
+
+// The number at a dot-separated `path`; numeric strings count too, and
+// numeric segments index into arrays.
+fn parse_json_field(body: &str, path: &str) -> Result<f64, String> {
+    let response = serde_json::from_str::<serde_json::Value>(body)
+        .map_err(|e| format!("JSON parse error: {}", e))?;
+    let value = path
+        .split('.')
+        .filter(|segment| !segment.is_empty())
+        .try_fold(&response, |value, segment| match segment.parse::<usize>() {
+            Ok(index) if value.is_array() => value.get(index),
+            _ => value.get(segment),
+        })
+        .ok_or_else(|| format!("no '{}' in response: {}", path, redact::body(body)))?;
+    audit::raw(&value.to_string());
+    let rate = match value {
+        serde_json::Value::String(text) => text.trim().parse::<f64>().ok(),
+        other => other.as_f64(),
+    };
+    rate.filter(|rate| rate.is_finite() && *rate > 0.0)
+        .ok_or_else(|| format!("'{}' is not a usable rate: {}", path, value))
+}
+
+// --- Telegram (public channel preview, newest matching post) ---
+pub fn fetch_telegram(http_client: &Client, channel: &str, pattern: &Regex) -> Result<f64, String> {
+    let url = format!("{}{}", TELEGRAM_PREVIEW_URL, channel);
+    debug!("Fetching Telegram posts from {}", url);
+    audit::endpoint("telegram", &url);
+    let response = http_client
+        .get(&url)
+        .headers(provider_headers("telegram", &[]))
+        .send()
+        .map_err(|e| format!("fetch error: {}", redact::redact(&e.to_string())))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        return Err(format!(
+            "request to {} failed with status: {}. Body: {}",
+            url,
+            status,
+            error_body(response)
+        ));
+    }
+    let html_content = response
+        .text()
+        .map_err(|e| format!("failed to read response text from {}: {}", url, e))?;
+    fixtures::record("telegram", "html", &html_content);
+    parse_telegram_posts(&html_content, pattern)
+}
+
+// The first capture group of `pattern` in `text`, or the whole match.
+fn first_match<'a>(pattern: &Regex, text: &'a str) -> Option<&'a str> {
+    let mut captures = pattern.create_captures();
+    pattern.captures(text, &mut captures);
+    let span = captures
+        .get_group(1)
+        .or_else(|| captures.get_match().map(|m| m.span()))?;
+    Some(&text[span.range()])
+}
+
+fn parse_telegram_posts(html_content: &str, pattern: &Regex) -> Result<f64, String> {
+    let document = Html::parse_document(html_content);
+    let selector = Selector::parse(".tgme_widget_message_text")
+        .map_err(|e| format!("failed to parse Telegram selector: {:?}", e))?;
+    let posts: Vec<String> = document
+        .select(&selector)
+        .map(|post| post.text().collect::<Vec<_>>().join(" "))
+        .collect();
+    if posts.is_empty() {
+        return Err("no posts found; is the channel public?".to_string());
+    }
+    // The preview lists posts oldest first.
+    posts
+        .iter()
+        .rev()
+        .find_map(|post| {
+            let found = first_match(pattern, post)?;
+            debug!("Telegram pattern matched '{}'", found);
+            import::parse_rate(found).inspect(|_| audit::raw(found))
+        })
+        .ok_or_else(|| format!("none of the last {} posts matched the pattern", posts.len()))
+}
+
+// --- Scraped pages (`[scrape.*]`) ---
+fn fetch_scraped(
+    http_client: &Client,
+    url: &str,
+    selector: &Selector,
+    pattern: Option<&Regex>,
+    browser: bool,
+) -> Result<f64, String> {
+    audit::endpoint("scrape", url);
+    let html_content = if browser {
+        browser::render(url)?
+    } else {
+        debug!("Fetching {}", url);
+        let response = http_client
+            .get(url)
+            .headers(provider_headers("scrape", &[]))
+            .send()
+            .map_err(|e| format!("fetch error: {}", redact::redact(&e.to_string())))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            return Err(format!(
+                "request to {} failed with status: {}. Body: {}",
+                url,
+                status,
+                error_body(response)
+            ));
+        }
+        response
+            .text()
+            .map_err(|e| format!("failed to read response text from {}: {}", url, e))?
+    };
+    fixtures::record("scrape", "html", &html_content);
+    parse_scraped(&html_content, selector, pattern)
+}
+
+fn parse_scraped(
+    html_content: &str,
+    selector: &Selector,
+    pattern: Option<&Regex>,
+) -> Result<f64, String> {
+    let document = Html::parse_document(html_content);
+    let text = document
+        .select(selector)
+        .next()
+        .map(|element| element.text().collect::<Vec<_>>().join(" "))
+        .ok_or("the selector did not find any node; does the page need `browser = true`?")?;
+    let found = match pattern {
+        Some(pattern) => first_match(pattern, &text)
+            .ok_or_else(|| format!("the pattern did not match '{}'", text.trim()))?,
+        None => text.trim(),
+    };
+    audit::raw(found);
+    import::parse_rate(found).ok_or_else(|| format!("no rate in '{}'", found))
+}
+
+// --- Kraken (USDT/USD last trade) ---
+pub fn fetch_kraken_usdt(http_client: &Client) -> Result<f64, String> {
+    debug!("Fetching USDT/USD from {}", KRAKEN_USDT_URL);
+    audit::endpoint("kraken", KRAKEN_USDT_URL);
+    let response = http_client
+        .get(KRAKEN_USDT_URL)
+        .headers(provider_headers("kraken", &[]))
+        .send()
+        .map_err(|e| format!("fetch error: {}", redact::redact(&e.to_string())))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        return Err(format!(
+            "API fail: {}. Body: {}",
+            status,
+            error_body(response)
+        ));
+    }
+    let body = response
+        .text()
+        .map_err(|e| format!("failed to read API response: {}", e))?;
+    fixtures::record("kraken", "json", &body);
+    parse_kraken_last(&body)
+}
+
+// `result` holds one pair; `c` is [last trade price, lot volume].
+fn parse_kraken_last(body: &str) -> Result<f64, String> {
+    let response = serde_json::from_str::<serde_json::Value>(body)
+        .map_err(|e| format!("JSON parse error: {}", e))?;
+    if let Some(error) = response["error"].as_array().and_then(|e| e.first()) {
+        return Err(format!("Kraken error: {}", error));
+    }
+    let last = response["result"]
+        .as_object()
+        .and_then(|result| result.values().next())
+        .and_then(|pair| pair["c"][0].as_str())
+        .ok_or_else(|| format!("no last price in response: {}", redact::body(body)))?;
+    audit::raw(last);
+    last.parse::<f64>()
+        .ok()
+        .filter(|price| price.is_finite() && *price > 0.0)
+        .ok_or_else(|| format!("implausible USDT price '{}'", last))
+}
+
+// --- CoinMarketCap (BTC/USD stored as satoshis per USD; other coins in USD) ---
+pub fn fetch_cmc_satoshi(http_client: &Client, cmc_keys: &CmcKeys) -> Result<f64, String> {
+    fetch_cmc_usd(http_client, cmc_keys, CMC_BTC_QUERY).map(|price| SATS_PER_BTC / price)
+}
+
+// USD price of the coin picked by `query` ("id=1", "symbol=ETH").
+fn fetch_cmc_usd(http_client: &Client, cmc_keys: &CmcKeys, query: &str) -> Result<f64, String> {
+    if cmc_keys.is_empty() {
+        return Err("no CMC API key configured".to_string());
+    }
+    let first = cmc_keys.current.load(Ordering::Relaxed);
+    let mut reasons = Vec::new();
+    for offset in 0..cmc_keys.len() {
+        let index = (first + offset) % cmc_keys.len();
+        match fetch_cmc_with_key(http_client, &cmc_keys.keys[index], query) {
+            Ok(body) => {
+                cmc_keys.current.store(index, Ordering::Relaxed);
+                return parse_cmc_usd(&body);
+            }
+            Err((status, reason)) if key_exhausted(status) && offset + 1 < cmc_keys.len() => {
+                warn!(
+                    "CMC key {} of {} is rate limited or out of credits; trying the next",
+                    index + 1,
+                    cmc_keys.len()
+                );
+                reasons.push(reason);
+            }
+            Err((_, reason)) => {
+                reasons.push(reason);
+                break;
+            }
+        }
+    }
+    Err(reasons.pop().unwrap_or_default())
+}
+
+// 429 covers the per-minute, daily and monthly credit limits; 402 an
+// expired plan. Anything else would fail the same way with another key.
+fn key_exhausted(status: Option<StatusCode>) -> bool {
+    matches!(
+        status,
+        Some(StatusCode::TOO_MANY_REQUESTS | StatusCode::PAYMENT_REQUIRED)
+    )
+}
+
+// The response body, or the status (if there was one) and why it failed.
+fn fetch_cmc_with_key(
+    http_client: &Client,
+    cmc_api_key: &str,
+    query: &str,
+) -> Result<String, (Option<StatusCode>, String)> {
+    let cmc_url = format!("{}?{}", CMC_BASE_URL, query);
+    debug!("Fetching CMC quote from {}", cmc_url);
+    audit::endpoint("cmc", &cmc_url);
+    let response = http_client
+        .get(&cmc_url)
+        .headers(provider_headers("cmc", &[]))
+        .header("X-CMC_PRO_API_KEY", cmc_api_key)
+        .header("Accept", "application/json")
+        .send()
+        .map_err(|e| {
+            (
+                None,
+                format!("fetch error: {}", redact::redact(&e.to_string())),
+            )
+        })?;
+    if !response.status().is_success() {
+        let status = response.status();
+        return Err((
+            Some(status),
+            format!("API fail: {}. Body: {}", status, error_body(response)),
+        ));
+    }
+    let body = response
+        .text()
+        .map_err(|e| (None, format!("failed to read API response: {}", e)))?;
+    fixtures::record("cmc", "json", &body);
+    Ok(body)
+}
+
+// `data` is keyed by whatever was asked for; by ticker each entry is a list,
+// since several coins can share one, and the first is the largest.
+fn parse_cmc_usd(body: &str) -> Result<f64, String> {
+    let response = serde_json::from_str::<serde_json::Value>(body)
+        .map_err(|e| format!("JSON parse error: {}", e))?;
+    let coin = response["data"]
+        .as_object()
+        .and_then(|data| data.values().next())
+        .map(|entry| entry.get(0).unwrap_or(entry))
+        .ok_or_else(|| format!("no quote in response: {}", redact::body(body)))?;
+    let price = coin["quote"]["USD"]["price"]
+        .as_f64()
+        .ok_or_else(|| format!("no USD price in response: {}", redact::body(body)))?;
+    audit::raw(&price.to_string());
+    if !(price.is_finite() && price > 0.0) {
+        return Err(format!("implausible {} price: {}", coin["symbol"], price));
+    }
+    Ok(price)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! fixture {
+        ($name:literal) => {
+            include_str!(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/tests/fixtures/",
+                $name
+            ))
+        };
+    }
+
+    fn parse_bcv(html: &str) -> Result<f64, String> {
+        parse_bcv_html(html, &bcv_selectors(&BcvConfig::default()))
+    }
+
+    #[test]
+    fn unreachable_doh_server_fails_every_host() {
+        let hosts = vec!["www.bcv.org.ve".to_string(), "p2p.binance.com".to_string()];
+        let looked_up = lookup_hosts("https://127.0.0.1:9/dns-query", &hosts);
+        assert_eq!(
+            looked_up,
+            vec![
+                ("www.bcv.org.ve".to_string(), None),
+                ("p2p.binance.com".to_string(), None)
+            ]
+        );
+    }
+
+    #[test]
+    fn bcv_parses_captured_page() {
+        let rate = parse_bcv(fixture!("bcv_home.html")).unwrap();
+        assert!((rate - 178.3507).abs() < 1e-9, "got {}", rate);
+    }
+
+    #[test]
+    fn bcv_notes_the_text_it_read() {
+        audit::endpoint("bcv", BCV_URL);
+        parse_bcv(fixture!("bcv_home.html")).unwrap();
+        let source = audit::take();
+        assert_eq!(
+            (source.endpoint.as_str(), source.raw.as_str()),
+            (BCV_URL, "178,35070000")
+        );
+        assert_eq!(audit::take(), audit::Source::default());
+    }
+
+    #[test]
+    fn bcv_handles_thousands_separator() {
+        let html = fixture!("bcv_home.html").replace("178,35070000", "1.234,56000000");
+        assert_eq!(parse_bcv(&html).unwrap(), 1234.56);
+    }
+
+    #[test]
+    fn bcv_survives_layout_change() {
+        // The full path no longer matches, but the USD box is still there.
+        let rate = parse_bcv(fixture!("bcv_layout_changed.html")).unwrap();
+        assert!((rate - 178.3507).abs() < 1e-9, "got {}", rate);
+    }
+
+    #[test]
+    fn bcv_reports_missing_rate() {
+        let html = fixture!("bcv_layout_changed.html").replace("id=\"dolar\"", "id=\"usd\"");
+        let err = parse_bcv(&html).unwrap_err();
+        assert!(err.contains("did not find any node"), "{}", err);
+    }
+
+    #[test]
+    fn bcv_tries_configured_selectors_in_order() {
+        let html = fixture!("bcv_layout_changed.html").replace("id=\"dolar\"", "id=\"usd\"");
+        let settings = BcvConfig {
+            selector: "#dolar >".to_string(),
+            fallback_selectors: vec!["#rate-box strong".to_string(), "#usd strong".to_string()],
+            ..BcvConfig::default()
+        };
+        let rate = parse_bcv_html(&html, &bcv_selectors(&settings)).unwrap();
+        assert!((rate - 178.3507).abs() < 1e-9, "got {}", rate);
+    }
+
+    #[test]
+    fn bcv_falls_back_to_the_usd_box() {
+        let html = r#"<html><body><div id="dolar"><span> USD</span>
+            <strong> 36,52000000 </strong></div></body></html>"#;
+        assert_eq!(parse_bcv(html).unwrap(), 36.52);
+    }
+
+    #[test]
+    fn scraped_element_with_and_without_pattern() {
+        let html = r#"<div class="rate"><b>Promedio</b> Bs. 41,25 por USD</div>"#;
+        let selector = Selector::parse(".rate").unwrap();
+        assert_eq!(parse_scraped(html, &selector, None).unwrap(), 41.25);
+        let pattern = Regex::new(r"Bs\. ([\d.,]+)").unwrap();
+        assert_eq!(
+            parse_scraped(html, &selector, Some(&pattern)).unwrap(),
+            41.25
+        );
+        let missing = Selector::parse("#rate").unwrap();
+        assert!(parse_scraped(html, &missing, None).is_err());
+    }
+
+    #[test]
+    fn bcv_reports_unparseable_rate() {
+        let html = fixture!("bcv_home.html").replace("178,35070000", "N/D");
+        assert!(parse_bcv(&html).is_err());
+    }
+
+    #[test]
+    fn binance_takes_first_ad_price() {
+        let rate = parse_binance_response(fixture!("binance_p2p.json"), Weighting::First).unwrap();
+        assert_eq!(rate, 203.5);
+    }
+
+    #[test]
+    fn binance_rejects_empty_data() {
+        let err = parse_binance_response(fixture!("binance_p2p_empty.json"), Weighting::First)
+            .unwrap_err();
+        assert!(err.contains("empty"), "{}", err);
+    }
+
+    #[test]
+    fn binance_rejects_error_code() {
+        let err = parse_binance_response(fixture!("binance_p2p_error.json"), Weighting::First)
+            .unwrap_err();
+        assert!(err.contains("not successful"), "{}", err);
+    }
+
+    #[test]
+    fn binance_rejects_non_json() {
+        assert!(parse_binance_response("<html>captcha</html>", Weighting::First).is_err());
+    }
+
+    #[test]
+    fn trm_takes_latest_value() {
+        assert_eq!(parse_trm(fixture!("trm.json")).unwrap(), 4123.45);
+    }
+
+    #[test]
+    fn json_field_follows_dotted_path() {
+        let body = r#"{"USD": {"VES": 212.5}, "rates": [{"zelle": "215,0"}, {"zelle": "214.1"}]}"#;
+        assert_eq!(parse_json_field(body, "USD.VES"), Ok(212.5));
+        assert_eq!(parse_json_field(body, "rates.1.zelle"), Ok(214.1));
+        assert!(parse_json_field(body, "rates.0.zelle").is_err());
+        assert!(parse_json_field(body, "USD.COP").is_err());
+    }
+
+    #[test]
+    fn telegram_takes_newest_matching_post() {
+        let html = r#"<div class="tgme_widget_message_text">Dólar hoy: Bs. 210,50</div>
+            <div class="tgme_widget_message_text">Dólar hoy: Bs. 212,75</div>
+            <div class="tgme_widget_message_text">Buenas noches</div>"#;
+        let pattern = Regex::new(r"Bs\.? ?([\d.,]+)").unwrap();
+        assert_eq!(parse_telegram_posts(html, &pattern), Ok(212.75));
+        let unmatched = Regex::new(r"EUR ([\d.,]+)").unwrap();
+        assert!(parse_telegram_posts(html, &unmatched).is_err());
+    }
+
+    #[test]
+    fn noones_offers_sorted_cheapest_first() {
+        let body = r#"{"status": "success", "data": {"offers": [
+            {"fiat_price_per_crypto": "21000000", "fiat_amount_range_max": 2100000},
+            {"fiat_price_per_crypto": 20500000.5, "fiat_amount_range_max": "410000"},
+            {"fiat_price_per_crypto": "n/a"}
+        ]}}"#;
+        let offers = parse_noones_offers(body).unwrap();
+        assert_eq!(offers.len(), 2);
+        assert_eq!(offers[0].0, 20500000.5);
+        assert!((offers[1].1 - 0.1).abs() < 1e-12);
+    }
+
+    #[test]
+    fn noones_rejects_errors() {
+        let body = r#"{"status": "error", "error": {"message": "Unauthorized"}}"#;
+        assert!(parse_noones_offers(body).is_err());
+    }
+
+    #[test]
+    fn kraken_takes_last_trade() {
+        assert_eq!(parse_kraken_last(fixture!("kraken_usdt.json")), Ok(0.99985));
+    }
+
+    #[test]
+    fn kraken_reports_errors() {
+        let body = r#"{"error":["EQuery:Unknown asset pair"]}"#;
+        assert!(parse_kraken_last(body)
+            .unwrap_err()
+            .contains("Unknown asset pair"));
+    }
+
+    #[test]
+    fn trm_rejects_empty_list() {
+        assert!(parse_trm("[]").is_err());
+    }
+
+    #[test]
+    fn cmc_converts_to_sats_per_usd() {
+        let sats = SATS_PER_BTC / parse_cmc_usd(fixture!("cmc_quotes_latest.json")).unwrap();
+        assert!((sats - 1000.0).abs() < 1e-9, "got {}", sats);
+    }
+
+    #[test]
+    fn cmc_keys_split_on_commas_and_whitespace() {
+        let keys = CmcKeys::parse("aaa, bbb\nccc ");
+        assert_eq!(keys.iter().collect::<Vec<_>>(), ["aaa", "bbb", "ccc"]);
+        assert!(CmcKeys::parse(" \n").is_empty());
+    }
+
+    #[test]
+    fn cmc_rotates_only_on_limits() {
+        assert!(key_exhausted(Some(StatusCode::TOO_MANY_REQUESTS)));
+        assert!(key_exhausted(Some(StatusCode::PAYMENT_REQUIRED)));
+        assert!(!key_exhausted(Some(StatusCode::UNAUTHORIZED)));
+        assert!(!key_exhausted(None));
+    }
+
+    #[test]
+    fn cmc_rejects_error_payload() {
+        assert!(parse_cmc_usd(fixture!("cmc_error.json")).is_err());
+    }
+
+    #[test]
+    fn cmc_rejects_zero_price() {
+        let body = fixture!("cmc_quotes_latest.json").replace("100000.0", "0.0");
+        assert!(parse_cmc_usd(&body).is_err());
+    }
+
+    #[test]
+    fn cmc_quote_by_ticker_takes_the_first_coin() {
+        let body = r#"{"data": {"ETH": [
+            {"symbol": "ETH", "quote": {"USD": {"price": 2500.5}}},
+            {"symbol": "ETH", "quote": {"USD": {"price": 0.01}}}
+        ]}}"#;
+        assert_eq!(parse_cmc_usd(body), Ok(2500.5));
+    }
+}
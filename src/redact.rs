@@ -0,0 +1,156 @@
+// --- Log Redaction ---
+// Everything that may carry credentials or large payloads goes through here
+// before it is printed, so individual call sites don't have to remember.
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Mutex,
+};
+
+const MASK: &str = "***";
+// Query parameters whose values are credentials in the URLs we deal with.
+const SENSITIVE_PARAMS: [&str; 6] = [
+    "key",
+    "api_key",
+    "apikey",
+    "token",
+    "access_token",
+    "secret",
+];
+
+static KNOWN_SECRETS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+static LOG_BODIES: AtomicBool = AtomicBool::new(false);
+
+// Any value registered here is masked wherever it appears in log output.
+pub fn register_secret(secret: &str) {
+    if secret.len() < 4 {
+        return;
+    }
+    let mut secrets = KNOWN_SECRETS.lock().unwrap_or_else(|p| p.into_inner());
+    if !secrets.iter().any(|s| s == secret) {
+        secrets.push(secret.to_string());
+    }
+}
+
+pub fn set_log_bodies(enabled: bool) {
+    LOG_BODIES.store(enabled, Ordering::Relaxed);
+}
+
+pub fn redact(text: &str) -> String {
+    let mut out = text.to_string();
+    for secret in KNOWN_SECRETS
+        .lock()
+        .unwrap_or_else(|p| p.into_inner())
+        .iter()
+    {
+        out = out.replace(secret.as_str(), MASK);
+    }
+    redact_url_tokens(&out)
+}
+
+// Response bodies are only logged in full when explicitly asked for.
+pub fn body(text: &str) -> String {
+    if LOG_BODIES.load(Ordering::Relaxed) {
+        redact(text)
+    } else {
+        format!("<{} bytes omitted, use --log-bodies to show>", text.len())
+    }
+}
+
+fn redact_url_tokens(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    // Telegram-style bot tokens live in the path: /bot<id>:<token>/
+    while let Some(pos) = rest.find("/bot") {
+        out.push_str(&rest[..pos + 4]);
+        rest = &rest[pos + 4..];
+        let end = rest.find(['/', '?', ' ', '"']).unwrap_or(rest.len());
+        if rest[..end].contains(':') {
+            out.push_str(MASK);
+            rest = &rest[end..];
+        }
+    }
+    out.push_str(rest);
+
+    let mut result = String::with_capacity(out.len());
+    let mut rest = out.as_str();
+    while let Some(pos) = rest.find(['?', '&']) {
+        result.push_str(&rest[..=pos]);
+        rest = &rest[pos + 1..];
+        let Some(eq) = rest.find('=') else { break };
+        let name = &rest[..eq];
+        if SENSITIVE_PARAMS
+            .iter()
+            .any(|p| name.eq_ignore_ascii_case(p))
+        {
+            result.push_str(name);
+            result.push('=');
+            result.push_str(MASK);
+            let end = rest[eq + 1..]
+                .find(['&', ' ', '"', '#'])
+                .map_or(rest.len(), |i| eq + 1 + i);
+            rest = &rest[end..];
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn masks_credential_query_parameters() {
+        assert_eq!(
+            redact("GET https://pro-api.coinmarketcap.com/v1/x?key=abc123&convert=VES"),
+            "GET https://pro-api.coinmarketcap.com/v1/x?key=***&convert=VES"
+        );
+        assert_eq!(
+            redact("https://example.com/hook?id=7&token=s3cr3t#frag"),
+            "https://example.com/hook?id=7&token=***#frag"
+        );
+        assert_eq!(
+            redact("error for \"https://example.com/?APIKEY=zzz\": timeout"),
+            "error for \"https://example.com/?APIKEY=***\": timeout"
+        );
+    }
+
+    #[test]
+    fn masks_telegram_bot_tokens() {
+        assert_eq!(
+            redact("POST https://api.telegram.org/bot123456:AAF-xyz/sendMessage failed"),
+            "POST https://api.telegram.org/bot***/sendMessage failed"
+        );
+        assert_eq!(
+            redact("https://api.telegram.org/bot42:abc?chat_id=1"),
+            "https://api.telegram.org/bot***?chat_id=1"
+        );
+    }
+
+    #[test]
+    fn masks_registered_secrets_in_bodies() {
+        register_secret("registered-secret-value");
+        // Too short to mask without mangling ordinary text.
+        register_secret("abc");
+        set_log_bodies(true);
+        let logged = body(r#"{"error":"bad key registered-secret-value","code":"abc"}"#);
+        set_log_bodies(false);
+        assert_eq!(logged, r#"{"error":"bad key ***","code":"abc"}"#);
+        assert_eq!(
+            body("registered-secret-value"),
+            "<23 bytes omitted, use --log-bodies to show>"
+        );
+    }
+
+    #[test]
+    fn leaves_other_text_alone() {
+        for text in [
+            "Fetching https://p2p.binance.com/bapi/c2c?fiat=VES&page=1",
+            "https://example.com/botany/plants?q=1&keyword=x",
+            "no URL here, just a rate of 36,52",
+            "trailing?",
+        ] {
+            assert_eq!(redact(text), text);
+        }
+    }
+}
@@ -0,0 +1,70 @@
+// --- Release Check ---
+// Tray apps get installed once and forgotten, so look for a newer GitHub
+// release now and then and let the user know. Nothing is downloaded.
+use reqwest::blocking::Client;
+use serde::Deserialize;
+use std::time::Duration;
+use tracing::debug;
+
+const LATEST_RELEASE_URL: &str = "https://api.github.com/repos/ruvasqm/bcv-tray/releases/latest";
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Deserialize, Debug)]
+struct GithubRelease {
+    tag_name: String,
+    html_url: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Release {
+    pub version: String,
+    pub url: String,
+}
+
+// Some(release) when GitHub has something newer than this build.
+pub fn check_latest() -> Result<Option<Release>, String> {
+    // GitHub rejects API requests without a User-Agent.
+    let client = Client::builder()
+        .user_agent(concat!("bcv-tray/", env!("CARGO_PKG_VERSION")))
+        .timeout(Duration::from_secs(15))
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+    let response = client
+        .get(LATEST_RELEASE_URL)
+        .header("Accept", "application/vnd.github+json")
+        .send()
+        .map_err(|e| format!("Release check failed: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!(
+            "Release check failed with status {}",
+            response.status()
+        ));
+    }
+    let release: GithubRelease = response
+        .json()
+        .map_err(|e| format!("Release check returned unexpected JSON: {}", e))?;
+    debug!("Latest release is {}", release.tag_name);
+    let version = release.tag_name.trim_start_matches('v').to_string();
+    if is_newer(&version, env!("CARGO_PKG_VERSION")) {
+        Ok(Some(Release {
+            version,
+            url: release.html_url,
+        }))
+    } else {
+        Ok(None)
+    }
+}
+
+// Numeric dot-separated comparison; anything after a '-' (pre-release) is ignored.
+fn is_newer(candidate: &str, current: &str) -> bool {
+    let parse = |version: &str| -> Vec<u64> {
+        version
+            .split('-')
+            .next()
+            .unwrap_or_default()
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect()
+    };
+    parse(candidate) > parse(current)
+}
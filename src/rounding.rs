@@ -0,0 +1,101 @@
+// --- Rounding ---
+// Per-symbol rounding for everything shown or converted (`[symbols.<name>]
+// rounding` and `round_to`): shops often price in steps of 0.05 or 0.10, or
+// truncate instead of rounding. Stored rates are never rounded.
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RoundingMode {
+    // Halves go up (away from zero).
+    #[default]
+    HalfUp,
+    // Extra digits are dropped.
+    Truncate,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Rounding {
+    pub mode: RoundingMode,
+    // Round to a multiple of this (0.01, 0.05, 0.1, 1...). None keeps the
+    // usual precision: 2 decimals, or 4 below 1.
+    pub step: Option<f64>,
+}
+
+impl Rounding {
+    pub fn apply(&self, value: f64) -> f64 {
+        let step = self.step.filter(|s| *s > 0.0).unwrap_or_else(|| {
+            if value != 0.0 && value.abs() < 1.0 {
+                0.0001
+            } else {
+                0.01
+            }
+        });
+        // 36.525 / 0.01 is 3652.4999...; clear the float noise first so
+        // halves really round up.
+        let steps = ((value / step) * 1e9).round() / 1e9;
+        let steps = match self.mode {
+            RoundingMode::HalfUp => steps.round(),
+            RoundingMode::Truncate => steps.trunc(),
+        };
+        steps * step
+    }
+
+    pub fn format(&self, value: f64) -> String {
+        let rounded = self.apply(value);
+        match self.step.filter(|s| *s > 0.0) {
+            Some(step) => format!("{:.*}", decimals(step), rounded),
+            None => crate::format_rate(rounded),
+        }
+    }
+}
+
+// Digits after the decimal point needed to show multiples of `step`.
+fn decimals(step: f64) -> usize {
+    (0..8)
+        .find(|&d| {
+            let scaled = step * 10f64.powi(d as i32);
+            (scaled - scaled.round()).abs() < 1e-9
+        })
+        .unwrap_or(8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rounding(mode: RoundingMode, step: Option<f64>) -> Rounding {
+        Rounding { mode, step }
+    }
+
+    #[test]
+    fn default_keeps_usual_precision() {
+        let r = Rounding::default();
+        assert_eq!(r.format(36.525), "36.53");
+        assert_eq!(r.format(0.027384), "0.0274");
+    }
+
+    #[test]
+    fn truncate_drops_digits() {
+        let r = rounding(RoundingMode::Truncate, None);
+        assert_eq!(r.format(36.529), "36.52");
+    }
+
+    #[test]
+    fn step_rounds_to_nearest_multiple() {
+        let r = rounding(RoundingMode::HalfUp, Some(0.05));
+        assert_eq!(r.format(36.52), "36.50");
+        assert_eq!(r.format(36.525), "36.55");
+        assert_eq!(r.format(36.58), "36.60");
+        let r = rounding(RoundingMode::HalfUp, Some(0.1));
+        assert_eq!(r.format(36.55), "36.6");
+    }
+
+    #[test]
+    fn step_truncates_down_to_multiple() {
+        let r = rounding(RoundingMode::Truncate, Some(0.1));
+        assert_eq!(r.format(36.59), "36.5");
+        let r = rounding(RoundingMode::Truncate, Some(1.0));
+        assert_eq!(r.format(3567.99), "3567");
+    }
+}
@@ -0,0 +1,239 @@
+// --- Fetch Schedule ---
+// BCV only publishes on business days, so scraping it on weekends and bank
+// holidays just fetches Friday's rate again. Other sources trade every day
+// and keep the normal cadence.
+//
+// In smart mode BCV is also left out of the regular cycle once it has been
+// checked that day. Instead it is polled every few minutes during the
+// afternoon publication window until the rate changes, which is how the new
+// "fecha valor" shows up on the site.
+//
+// `intervals` give providers their own cadence (BCV every few hours, Binance
+// every few minutes). The scheduled cycle then runs at the shortest interval
+// and each source sits out the cycles until its own interval has passed.
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDate, Timelike, Utc, Weekday};
+use std::{
+    collections::{hash_map::RandomState, BTreeMap, HashMap},
+    hash::{BuildHasher, Hasher},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use tracing::{info, warn};
+
+use crate::{config::ScheduleConfig, providers};
+
+pub const BCV_SYMBOL: &str = "bcv";
+// Trigger name for the regular update cycle.
+pub const BACKGROUND_TRIGGER: &str = "background";
+// Shorter per-provider intervals are raised to this.
+const MIN_INTERVAL: Duration = Duration::from_secs(60);
+// Trigger name for the publication-window poller.
+pub const WINDOW_TRIGGER: &str = "bcv_window";
+pub const WINDOW_POLL_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+// A random duration below `max`. RandomState is seeded randomly per process
+// and per call, which is plenty for spreading out fetch times.
+pub fn jitter(max: Duration) -> Duration {
+    let millis = max.as_millis() as u64;
+    if millis == 0 {
+        return Duration::ZERO;
+    }
+    Duration::from_millis(RandomState::new().build_hasher().finish() % millis)
+}
+
+// Venezuela has no daylight saving time.
+fn vet() -> FixedOffset {
+    FixedOffset::west_opt(4 * 3600).expect("valid VET offset")
+}
+
+fn vet_today(now: DateTime<Utc>) -> NaiveDate {
+    now.with_timezone(&vet()).date_naive()
+}
+
+#[derive(Default)]
+struct BcvState {
+    // VET day of the last successful fetch.
+    checked_on: Option<NaiveDate>,
+    // VET day a changed rate was last seen.
+    published_on: Option<NaiveDate>,
+}
+
+pub struct Schedule {
+    business_days_only: bool,
+    holidays: Vec<NaiveDate>,
+    smart_window: bool,
+    window_hours: (u32, u32),
+    bcv: Mutex<BcvState>,
+    default_interval: Duration,
+    // By provider key.
+    intervals: BTreeMap<String, Duration>,
+    // When each symbol was last fetched.
+    attempts: Mutex<HashMap<String, Instant>>,
+}
+
+impl Schedule {
+    // `default_interval` applies to providers without an entry in `intervals`.
+    pub fn from_config(config: &ScheduleConfig, default_interval: Duration) -> Self {
+        let holidays = config
+            .bcv_holidays
+            .iter()
+            .filter_map(|day| match NaiveDate::parse_from_str(day, "%Y-%m-%d") {
+                Ok(date) => Some(date),
+                Err(e) => {
+                    warn!("Ignoring holiday '{}' (expected YYYY-MM-DD): {}", day, e);
+                    None
+                }
+            })
+            .collect();
+        let window_hours = (config.bcv_window_start_hour, config.bcv_window_end_hour);
+        if config.bcv_smart_window && (window_hours.0 >= window_hours.1 || window_hours.1 > 24) {
+            warn!(
+                "Invalid BCV window {}-{}h, smart scheduling disabled",
+                window_hours.0, window_hours.1
+            );
+        }
+        Schedule {
+            business_days_only: config.bcv_business_days_only,
+            holidays,
+            smart_window: config.bcv_smart_window
+                && window_hours.0 < window_hours.1
+                && window_hours.1 <= 24,
+            window_hours,
+            bcv: Mutex::new(BcvState::default()),
+            default_interval,
+            intervals: config
+                .intervals
+                .iter()
+                .map(|(provider, secs)| {
+                    let interval = Duration::from_secs(*secs).max(MIN_INTERVAL);
+                    (provider.to_lowercase(), interval)
+                })
+                .collect(),
+            attempts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // How often the regular cycle runs: the shortest interval of any provider.
+    pub fn cycle_interval(&self) -> Duration {
+        self.intervals
+            .values()
+            .copied()
+            .fold(self.default_interval, Duration::min)
+    }
+
+    // Whether `symbol`'s own interval has passed since it was last fetched.
+    // Only the regular cycle waits; nothing waits until there is a rate.
+    pub fn due(&self, symbol: &str, trigger: &str, have_rate: bool) -> bool {
+        if trigger != BACKGROUND_TRIGGER || !have_rate {
+            return true;
+        }
+        let interval = self
+            .intervals
+            .get(providers::provider_key(symbol))
+            .copied()
+            .unwrap_or(self.default_interval);
+        let attempts = self.attempts.lock().unwrap_or_else(|p| p.into_inner());
+        is_due(
+            attempts.get(symbol).map(Instant::elapsed),
+            interval,
+            self.cycle_interval(),
+        )
+    }
+
+    pub fn record_attempt(&self, symbol: &str) {
+        let mut attempts = self.attempts.lock().unwrap_or_else(|p| p.into_inner());
+        attempts.insert(symbol.to_string(), Instant::now());
+    }
+
+    pub fn smart_window(&self) -> bool {
+        self.smart_window
+    }
+
+    pub fn is_bcv_business_day(&self, now: DateTime<Utc>) -> bool {
+        let today = vet_today(now);
+        !matches!(today.weekday(), Weekday::Sat | Weekday::Sun) && !self.holidays.contains(&today)
+    }
+
+    // Why a fetch of `symbol` should be skipped, if it should. Manual updates
+    // always go through, and nothing is skipped until there is a rate to show.
+    pub fn skip_reason(
+        &self,
+        symbol: &str,
+        trigger: &str,
+        have_rate: bool,
+    ) -> Option<&'static str> {
+        if symbol != BCV_SYMBOL || trigger == "manual" || !have_rate {
+            return None;
+        }
+        let now = Utc::now();
+        if self.business_days_only && !self.is_bcv_business_day(now) {
+            return Some("not a publication day");
+        }
+        let state = self.bcv.lock().unwrap_or_else(|p| p.into_inner());
+        if self.smart_window
+            && trigger != WINDOW_TRIGGER
+            && state.checked_on == Some(vet_today(now))
+        {
+            return Some("already checked today, waiting for the publication window");
+        }
+        None
+    }
+
+    // Called after every stored rate with the one it replaced.
+    pub fn record_fetch(&self, symbol: &str, previous: Option<f64>, rate: f64) {
+        if symbol != BCV_SYMBOL {
+            return;
+        }
+        let now = Utc::now();
+        let today = vet_today(now);
+        let mut state = self.bcv.lock().unwrap_or_else(|p| p.into_inner());
+        state.checked_on = Some(today);
+        // A change seen in the morning is yesterday's publication catching up.
+        let after_window_start = now.with_timezone(&vet()).hour() >= self.window_hours.0;
+        if after_window_start && previous.is_some_and(|previous| previous != rate) {
+            if self.smart_window && state.published_on != Some(today) {
+                info!("New BCV rate published, polling stops until tomorrow's window");
+            }
+            state.published_on = Some(today);
+        }
+    }
+
+    // True while the publication-window poller should keep checking BCV.
+    pub fn in_bcv_window(&self, now: DateTime<Utc>) -> bool {
+        if !self.smart_window || !self.is_bcv_business_day(now) {
+            return false;
+        }
+        let hour = now.with_timezone(&vet()).hour();
+        let state = self.bcv.lock().unwrap_or_else(|p| p.into_inner());
+        (self.window_hours.0..self.window_hours.1).contains(&hour)
+            && state.published_on != Some(vet_today(now))
+    }
+}
+
+// Cycles start a little later each time (jitter, the fetches themselves), so
+// a source counts as due half a cycle early rather than waiting a whole extra
+// cycle.
+fn is_due(elapsed: Option<Duration>, interval: Duration, cycle: Duration) -> bool {
+    elapsed.is_none_or(|elapsed| elapsed + cycle / 2 >= interval)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slow_sources_sit_out_cycles_until_due() {
+        let (hour, cycle) = (Duration::from_secs(3600), Duration::from_secs(300));
+        let interval = 6 * hour;
+        assert!(is_due(None, interval, cycle));
+        assert!(!is_due(Some(5 * hour), interval, cycle));
+        // The cycle that starts a few seconds short of six hours still fetches.
+        assert!(is_due(
+            Some(interval - Duration::from_secs(20)),
+            interval,
+            cycle
+        ));
+        // Sources on the default interval run every cycle.
+        assert!(is_due(Some(cycle), cycle, cycle));
+    }
+}
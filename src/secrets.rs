@@ -0,0 +1,66 @@
+// --- Secrets ---
+// API keys and tokens live in the OS keyring (Secret Service, Keychain,
+// Credential Manager) rather than in env files or the config.
+use clap::ValueEnum;
+use keyring::Entry;
+
+const SERVICE: &str = "bcv-tray";
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecretName {
+    /// CoinMarketCap Pro API key (used for the SAT rate)
+    CmcApiKey,
+    /// Google service account key (the whole JSON file), for the Sheets export
+    GoogleServiceAccount,
+    /// Token required by the local HTTP API
+    ApiToken,
+    /// Noones API client ID and secret, as <id>:<secret>
+    NoonesApi,
+}
+
+impl SecretName {
+    pub const ALL: [SecretName; 4] = [
+        SecretName::CmcApiKey,
+        SecretName::GoogleServiceAccount,
+        SecretName::ApiToken,
+        SecretName::NoonesApi,
+    ];
+
+    fn key(self) -> &'static str {
+        match self {
+            SecretName::CmcApiKey => "cmc_api_key",
+            SecretName::GoogleServiceAccount => "google_service_account",
+            SecretName::ApiToken => "api_token",
+            SecretName::NoonesApi => "noones_api",
+        }
+    }
+}
+
+fn entry(name: SecretName) -> Result<Entry, String> {
+    Entry::new(SERVICE, name.key()).map_err(|e| format!("Keyring unavailable: {}", e))
+}
+
+pub fn get(name: SecretName) -> Result<Option<String>, String> {
+    match entry(name)?.get_password() {
+        Ok(value) => Ok(Some(value)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(format!("Failed to read {} from keyring: {}", name.key(), e)),
+    }
+}
+
+pub fn set(name: SecretName, value: &str) -> Result<(), String> {
+    entry(name)?
+        .set_password(value)
+        .map_err(|e| format!("Failed to store {} in keyring: {}", name.key(), e))
+}
+
+pub fn clear(name: SecretName) -> Result<(), String> {
+    match entry(name)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(format!(
+            "Failed to remove {} from keyring: {}",
+            name.key(),
+            e
+        )),
+    }
+}
@@ -0,0 +1,132 @@
+// --- Share Message ---
+// "Share Rates" in the tray menu copies the rates as the kind of morning post
+// people paste into WhatsApp and Telegram groups: Spanish whatever the app's
+// language, decimal commas, the 24 h change and where each rate comes from.
+// `*bold*` renders in both apps.
+use chrono::{DateTime, Datelike, Local, NaiveDate};
+
+use crate::{config::Config, derived, RateInfo};
+
+const WEEKDAYS: [&str; 7] = [
+    "Lunes",
+    "Martes",
+    "Miércoles",
+    "Jueves",
+    "Viernes",
+    "Sábado",
+    "Domingo",
+];
+const MONTHS: [&str; 12] = [
+    "enero",
+    "febrero",
+    "marzo",
+    "abril",
+    "mayo",
+    "junio",
+    "julio",
+    "agosto",
+    "septiembre",
+    "octubre",
+    "noviembre",
+    "diciembre",
+];
+
+// Rates without a stored value yet are left out.
+pub fn message(rates: &[RateInfo], config: &Config, now: DateTime<Local>) -> String {
+    let shown: Vec<&RateInfo> = rates.iter().filter(|r| r.last_updated.is_some()).collect();
+    let mut lines = vec![
+        "*Tasas del día*".to_string(),
+        spanish_date(now.date_naive()),
+        String::new(),
+    ];
+    for rate in &shown {
+        let value = es_number(&rate.rounding.format(rate.rate));
+        let value = if rate.unit == "%" {
+            format!("{}%", value)
+        } else {
+            format!("{} {}", value, rate.unit)
+        };
+        let change = rate
+            .day_change
+            .map(|pct| {
+                let arrow = match pct {
+                    p if p >= 0.005 => "▲",
+                    p if p <= -0.005 => "▼",
+                    _ => "=",
+                };
+                format!(" ({} {}%)", arrow, es_number(&format!("{:.2}", pct.abs())))
+            })
+            .unwrap_or_default();
+        lines.push(format!("• {}: *{}*{}", rate.currency, value, change));
+    }
+    // Computed rates name a formula, not a source.
+    let mut sources: Vec<&str> = Vec::new();
+    for rate in &shown {
+        let computed =
+            rate.symbol == derived::BRECHA_SYMBOL || config.derived.contains_key(&rate.symbol);
+        if !computed && !sources.contains(&rate.provider.as_str()) {
+            sources.push(&rate.provider);
+        }
+    }
+    if !sources.is_empty() {
+        lines.push(String::new());
+        lines.push(format!("Fuentes: {}.", sources.join(", ")));
+    }
+    if let Some(updated) = shown.iter().filter_map(|r| r.last_updated).max() {
+        lines.push(format!(
+            "Actualizado: {}",
+            updated.with_timezone(&Local).format("%d/%m %H:%M")
+        ));
+    }
+    lines.join("\n")
+}
+
+fn spanish_date(date: NaiveDate) -> String {
+    format!(
+        "{} {} de {} de {}",
+        WEEKDAYS[date.weekday().num_days_from_monday() as usize],
+        date.day(),
+        MONTHS[date.month0() as usize],
+        date.year()
+    )
+}
+
+// "1234.56" as written in Venezuela: "1.234,56".
+fn es_number(text: &str) -> String {
+    let (sign, digits) = match text.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", text),
+    };
+    let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+    let mut grouped = String::new();
+    for (i, c) in whole.chars().enumerate() {
+        if i > 0 && (whole.len() - i) % 3 == 0 {
+            grouped.push('.');
+        }
+        grouped.push(c);
+    }
+    if fraction.is_empty() {
+        format!("{}{}", sign, grouped)
+    } else {
+        format!("{}{},{}", sign, grouped, fraction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_use_decimal_commas_and_thousands_dots() {
+        assert_eq!(es_number("36.52"), "36,52");
+        assert_eq!(es_number("1234567.8"), "1.234.567,8");
+        assert_eq!(es_number("-0.0271"), "-0,0271");
+        assert_eq!(es_number("100"), "100");
+    }
+
+    #[test]
+    fn dates_are_spelled_out() {
+        let date = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+        assert_eq!(spanish_date(date), "Viernes 16 de octubre de 2026");
+    }
+}
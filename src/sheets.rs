@@ -0,0 +1,206 @@
+// --- Google Sheets Export ---
+// Appends one row per day (date, then each configured symbol's rate) to a
+// Google Sheet, authenticating as a service account. Built only with the
+// `sheets` feature, since signing the service-account token needs OpenSSL.
+// The key file's JSON comes from the keyring (`secret set
+// google-service-account`) or from `[sheets] credentials_file`.
+use chrono::{Local, NaiveDate, Timelike};
+#[cfg(feature = "sheets")]
+use reqwest::blocking::Client;
+use rusqlite::Connection;
+use std::sync::Mutex;
+use tracing::{info, warn};
+
+use crate::{config::SheetsConfig, db, paths};
+
+// Remembers the last day written so restarts don't append it twice.
+const STATE_FILE: &str = "sheets_last_day";
+
+pub struct SheetsExporter {
+    config: SheetsConfig,
+    last_day: Mutex<Option<NaiveDate>>,
+}
+
+impl SheetsExporter {
+    pub fn new(config: SheetsConfig) -> Self {
+        if config.enabled && !cfg!(feature = "sheets") {
+            warn!("[sheets] is enabled but this build has no Google Sheets support");
+        }
+        let last_day = paths::data_dir()
+            .ok()
+            .and_then(|dir| std::fs::read_to_string(dir.join(STATE_FILE)).ok())
+            .and_then(|day| NaiveDate::parse_from_str(day.trim(), "%Y-%m-%d").ok());
+        SheetsExporter {
+            config,
+            last_day: Mutex::new(last_day),
+        }
+    }
+
+    // Called after each successful update; appends today's row once, at the
+    // first update after `append_hour` (local time).
+    pub fn after_update(&self, db: &Mutex<Connection>) {
+        if !self.config.enabled || !cfg!(feature = "sheets") {
+            return;
+        }
+        let now = Local::now();
+        let today = now.date_naive();
+        let mut last_day = self.last_day.lock().unwrap_or_else(|p| p.into_inner());
+        if now.hour() < self.config.append_hour || *last_day == Some(today) {
+            return;
+        }
+        let mut row = vec![serde_json::Value::from(
+            today.format("%Y-%m-%d").to_string(),
+        )];
+        {
+            let conn = db.lock().unwrap_or_else(|p| p.into_inner());
+            for symbol in &self.config.symbols {
+                row.push(match db::latest_rate(&conn, symbol) {
+                    Ok(Some(rate)) => rate.into(),
+                    _ => "".into(),
+                });
+            }
+        }
+        match append_row(&self.config, row) {
+            Ok(()) => {
+                info!("Appended {} rates to Google Sheets", today);
+                *last_day = Some(today);
+                if let Ok(dir) = paths::data_dir() {
+                    let day = today.format("%Y-%m-%d").to_string();
+                    if let Err(e) = std::fs::write(dir.join(STATE_FILE), day) {
+                        warn!("Failed to save Google Sheets state: {}", e);
+                    }
+                }
+            }
+            Err(e) => warn!("Google Sheets export failed: {}", e),
+        }
+    }
+}
+
+#[cfg(feature = "sheets")]
+fn append_row(config: &SheetsConfig, row: Vec<serde_json::Value>) -> Result<(), String> {
+    const SCOPE: &str = "https://www.googleapis.com/auth/spreadsheets";
+    const SHEETS_API: &str = "https://sheets.googleapis.com/v4/spreadsheets";
+
+    // Not the fetchers' client: the signed assertion and the token it buys
+    // must only ever reach Google, so certificates are always verified.
+    let client = Client::builder()
+        .user_agent(concat!("bcv-tray/", env!("CARGO_PKG_VERSION")))
+        .timeout(std::time::Duration::from_secs(15))
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+    let key = service_account_key(config)?;
+    let token = access_token(&client, &key, SCOPE)?;
+    let url = format!(
+        "{}/{}/values/{}:append?valueInputOption=USER_ENTERED",
+        SHEETS_API,
+        config.spreadsheet_id,
+        percent_encode(&config.sheet)
+    );
+    let response = client
+        .post(&url)
+        .bearer_auth(token)
+        .json(&serde_json::json!({ "values": [row] }))
+        .send()
+        .map_err(|e| format!("append request failed: {}", e))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = crate::redact::body(&response.text().unwrap_or_default());
+        return Err(format!("append failed with status {}: {}", status, body));
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "sheets"))]
+fn append_row(_config: &SheetsConfig, _row: Vec<serde_json::Value>) -> Result<(), String> {
+    Err("built without the `sheets` feature".to_string())
+}
+
+#[cfg(feature = "sheets")]
+#[derive(serde::Deserialize)]
+struct ServiceAccountKey {
+    client_email: String,
+    private_key: String,
+    token_uri: String,
+}
+
+#[cfg(feature = "sheets")]
+fn service_account_key(config: &SheetsConfig) -> Result<ServiceAccountKey, String> {
+    use crate::secrets::{self, SecretName};
+
+    let json = match secrets::get(SecretName::GoogleServiceAccount)? {
+        Some(json) => json,
+        None if !config.credentials_file.is_empty() => {
+            std::fs::read_to_string(&config.credentials_file)
+                .map_err(|e| format!("Failed to read {}: {}", config.credentials_file, e))?
+        }
+        None => return Err("no service account key configured".to_string()),
+    };
+    serde_json::from_str(&json).map_err(|e| format!("invalid service account key: {}", e))
+}
+
+// OAuth 2.0 JWT bearer flow: a self-signed RS256 assertion is exchanged for
+// a short-lived access token. One token per append is plenty at one row a day.
+#[cfg(feature = "sheets")]
+fn access_token(client: &Client, key: &ServiceAccountKey, scope: &str) -> Result<String, String> {
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+    use openssl::{hash::MessageDigest, pkey::PKey, sign::Signer};
+
+    #[derive(serde::Deserialize)]
+    struct TokenResponse {
+        access_token: String,
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    let header = URL_SAFE_NO_PAD.encode(r#"{"alg":"RS256","typ":"JWT"}"#);
+    let claims = URL_SAFE_NO_PAD.encode(
+        serde_json::json!({
+            "iss": key.client_email,
+            "scope": scope,
+            "aud": key.token_uri,
+            "iat": now,
+            "exp": now + 3600,
+        })
+        .to_string(),
+    );
+    let unsigned = format!("{}.{}", header, claims);
+    let pkey = PKey::private_key_from_pem(key.private_key.as_bytes())
+        .map_err(|e| format!("invalid service account private key: {}", e))?;
+    let signature = Signer::new(MessageDigest::sha256(), &pkey)
+        .and_then(|mut signer| signer.sign_oneshot_to_vec(unsigned.as_bytes()))
+        .map_err(|e| format!("failed to sign token request: {}", e))?;
+    let assertion = format!("{}.{}", unsigned, URL_SAFE_NO_PAD.encode(signature));
+
+    let response = client
+        .post(&key.token_uri)
+        .form(&[
+            ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+            ("assertion", assertion.as_str()),
+        ])
+        .send()
+        .map_err(|e| format!("token request failed: {}", e))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = crate::redact::body(&response.text().unwrap_or_default());
+        return Err(format!(
+            "token request failed with status {}: {}",
+            status, body
+        ));
+    }
+    response
+        .json::<TokenResponse>()
+        .map(|token| token.access_token)
+        .map_err(|e| format!("invalid token response: {}", e))
+}
+
+// Sheet names may contain spaces; the A1 range goes in the URL path.
+#[cfg(feature = "sheets")]
+fn percent_encode(text: &str) -> String {
+    text.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
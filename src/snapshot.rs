@@ -0,0 +1,165 @@
+// --- Snapshot Note Window ---
+// "Record Snapshot..." asks for a short note, then stores every current rate
+// with it (see `db::record_snapshot`). Same software-drawn approach as the
+// converter: one line of input, Enter saves, Esc cancels.
+use ab_glyph::{FontVec, PxScale};
+use image::RgbaImage;
+use imageproc::drawing::draw_text_mut;
+use softbuffer::{Context, Surface};
+use std::{rc::Rc, sync::Arc};
+use tao::{
+    dpi::LogicalSize,
+    event::{ElementState, KeyEvent, WindowEvent},
+    event_loop::EventLoopWindowTarget,
+    keyboard::Key,
+    window::{Window, WindowBuilder, WindowId},
+};
+
+use crate::{
+    converter::{self, BACKGROUND, DIMMED, FOREGROUND},
+    i18n::{self, Msg},
+};
+
+const WIDTH: f64 = 380.0;
+const HEIGHT: f64 = 100.0;
+const FONT_SIZE: f32 = 16.0;
+const LINE_HEIGHT: i32 = 22;
+const MARGIN: i32 = 12;
+const MAX_NOTE_CHARS: usize = 120;
+// What fits on the input line at FONT_SIZE.
+const VISIBLE_CHARS: usize = 38;
+
+pub enum NoteAction {
+    Keep,
+    Cancel,
+    Save(String),
+}
+
+pub struct NoteWindow {
+    window: Rc<Window>,
+    surface: Surface<Rc<Window>, Rc<Window>>,
+    font: Arc<FontVec>,
+    note: String,
+}
+
+impl NoteWindow {
+    pub fn open<T>(target: &EventLoopWindowTarget<T>, font: Arc<FontVec>) -> Result<Self, String> {
+        let window = WindowBuilder::new()
+            .with_title(i18n::tr(Msg::RecordSnapshotTitle))
+            .with_inner_size(LogicalSize::new(WIDTH, HEIGHT))
+            .with_resizable(false)
+            .build(target)
+            .map_err(|e| format!("Failed to create snapshot window: {}", e))?;
+        let window = Rc::new(window);
+        let context = Context::new(window.clone())
+            .map_err(|e| format!("Failed to create drawing context: {}", e))?;
+        let surface = Surface::new(&context, window.clone())
+            .map_err(|e| format!("Failed to create drawing surface: {}", e))?;
+        window.set_focus();
+        Ok(Self {
+            window,
+            surface,
+            font,
+            note: String::new(),
+        })
+    }
+
+    pub fn id(&self) -> WindowId {
+        self.window.id()
+    }
+
+    pub fn focus(&self) {
+        self.window.set_focus();
+    }
+
+    pub fn handle_event(&mut self, event: &WindowEvent) -> NoteAction {
+        match event {
+            WindowEvent::CloseRequested => return NoteAction::Cancel,
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        logical_key,
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => {
+                match logical_key {
+                    Key::Escape => return NoteAction::Cancel,
+                    Key::Enter => return NoteAction::Save(self.note.trim().to_string()),
+                    Key::Backspace => {
+                        self.note.pop();
+                    }
+                    Key::Space => self.push_input(" "),
+                    Key::Character(text) => self.push_input(text),
+                    _ => return NoteAction::Keep,
+                }
+                self.window.request_redraw();
+            }
+            WindowEvent::Resized(_) | WindowEvent::ScaleFactorChanged { .. } => {
+                self.window.request_redraw();
+            }
+            _ => {}
+        }
+        NoteAction::Keep
+    }
+
+    fn push_input(&mut self, text: &str) {
+        for c in text.chars().filter(|c| !c.is_control()) {
+            if self.note.chars().count() >= MAX_NOTE_CHARS {
+                break;
+            }
+            self.note.push(c);
+        }
+    }
+
+    pub fn redraw(&mut self) -> Result<(), String> {
+        let size = self.window.inner_size();
+        let scale = self.window.scale_factor() as f32;
+        let canvas = self.render(size.width, size.height, scale);
+        converter::present(&mut self.surface, &canvas)
+    }
+
+    fn render(&self, width: u32, height: u32, scale_factor: f32) -> RgbaImage {
+        let mut canvas = RgbaImage::from_pixel(width, height, BACKGROUND);
+        let scale = PxScale::from(FONT_SIZE * scale_factor);
+        let line_height = (LINE_HEIGHT as f32 * scale_factor) as i32;
+        let margin = (MARGIN as f32 * scale_factor) as i32;
+        let prompt = i18n::tr(Msg::SnapshotPrompt);
+        draw_text_mut(
+            &mut canvas,
+            DIMMED,
+            margin,
+            margin,
+            scale,
+            self.font.as_ref(),
+            prompt,
+        );
+        // Long notes scroll so the end being typed stays visible.
+        let skip = self.note.chars().count().saturating_sub(VISIBLE_CHARS);
+        let visible: String = self.note.chars().skip(skip).collect();
+        let input = format!("{}_", visible);
+        let y = margin + line_height;
+        draw_text_mut(
+            &mut canvas,
+            FOREGROUND,
+            margin,
+            y,
+            scale,
+            self.font.as_ref(),
+            &input,
+        );
+        let help = i18n::tr(Msg::SnapshotHelp);
+        let y = height as i32 - margin - line_height;
+        draw_text_mut(
+            &mut canvas,
+            DIMMED,
+            margin,
+            y,
+            scale,
+            self.font.as_ref(),
+            help,
+        );
+        canvas
+    }
+}
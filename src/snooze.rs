@@ -0,0 +1,85 @@
+// --- Snooze ---
+// "Pause Updates" in the tray menu stops scheduled fetching for an hour, four
+// hours or until tomorrow morning, then resumes on its own with an update.
+// Update Now still fetches while paused. The end time is kept in the app
+// state so a restart doesn't cut the pause short.
+use chrono::{DateTime, Duration, Local, NaiveTime, TimeZone, Utc};
+
+use crate::state;
+
+// Update cycle started when a pause runs out.
+pub const RESUME_TRIGGER: &str = "resume";
+// "Until tomorrow" ends at this local hour the next day.
+const MORNING_HOUR: u32 = 6;
+
+#[derive(Clone, Copy)]
+pub enum Length {
+    Hours(i64),
+    UntilTomorrow,
+}
+
+pub fn start(length: Length) {
+    let until = end(length, Local::now());
+    state::update(|state| state.snoozed_until = Some(until));
+}
+
+pub fn resume() {
+    state::update(|state| state.snoozed_until = None);
+}
+
+// When the current pause ends, or None when not paused.
+pub fn until() -> Option<DateTime<Utc>> {
+    state::get()
+        .snoozed_until
+        .filter(|until| *until > Utc::now())
+}
+
+// Scheduled, live and publication-window updates wait out a pause.
+pub fn blocks(trigger: &str) -> bool {
+    trigger != "manual" && until().is_some()
+}
+
+fn end(length: Length, now: DateTime<Local>) -> DateTime<Utc> {
+    match length {
+        Length::Hours(hours) => (now + Duration::hours(hours)).with_timezone(&Utc),
+        Length::UntilTomorrow => {
+            let morning = (now.date_naive() + Duration::days(1))
+                .and_time(NaiveTime::from_hms_opt(MORNING_HOUR, 0, 0).unwrap_or(NaiveTime::MIN));
+            Local
+                .from_local_datetime(&morning)
+                .earliest()
+                .map(|at| at.with_timezone(&Utc))
+                .unwrap_or_else(|| (now + Duration::days(1)).with_timezone(&Utc))
+        }
+    }
+}
+
+// Time left as "3 h 05 min" or "12 min", rounded up so it never shows 0.
+pub fn remaining(until: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let minutes = ((until - now).num_seconds().max(0) + 59) / 60;
+    if minutes >= 60 {
+        format!("{} h {:02} min", minutes / 60, minutes % 60)
+    } else {
+        format!("{} min", minutes.max(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn until_tomorrow_ends_next_morning() {
+        let now = Local.with_ymd_and_hms(2026, 3, 10, 22, 30, 0).unwrap();
+        let end = end(Length::UntilTomorrow, now).with_timezone(&Local);
+        assert_eq!(end.format("%Y-%m-%d %H:%M").to_string(), "2026-03-11 06:00");
+    }
+
+    #[test]
+    fn remaining_rounds_up() {
+        let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        assert_eq!(remaining(now + Duration::seconds(30), now), "1 min");
+        assert_eq!(remaining(now + Duration::minutes(185), now), "3 h 05 min");
+        assert_eq!(remaining(now + Duration::seconds(3599), now), "1 h 00 min");
+    }
+}
@@ -0,0 +1,97 @@
+// --- Saved App State ---
+// What the user sets from the UI rather than in the config: where the widget
+// and converter were left, whether the widget was open, the active currency,
+// the converter's direction and when a paused schedule resumes. Kept as `state.json` in the data directory
+// and rewritten whenever one of them changes, so a restart comes back as it
+// was left.
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{fs, sync::Mutex};
+use tao::{dpi::PhysicalPosition, event_loop::EventLoopWindowTarget};
+use tracing::warn;
+
+use crate::paths;
+
+const STATE_FILE: &str = "state.json";
+// Where the widget position was kept before there was a state file.
+const LEGACY_WIDGET_POSITION_FILE: &str = "widget_position";
+
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq)]
+#[serde(default)]
+pub struct AppState {
+    pub widget_position: Option<(i32, i32)>,
+    pub widget_open: bool,
+    pub converter_position: Option<(i32, i32)>,
+    // The converter takes amounts in the active fiat instead of USD.
+    pub converter_from_local: bool,
+    // Currency code picked in the Currency menu.
+    pub fiat: Option<String>,
+    // End of a "Pause Updates" snooze.
+    pub snoozed_until: Option<DateTime<Utc>>,
+}
+
+static STATE: Mutex<Option<AppState>> = Mutex::new(None);
+
+pub fn get() -> AppState {
+    let mut state = STATE.lock().unwrap_or_else(|p| p.into_inner());
+    state.get_or_insert_with(load).clone()
+}
+
+// Applies `change` and writes the file if anything changed.
+pub fn update(change: impl FnOnce(&mut AppState)) {
+    let mut guard = STATE.lock().unwrap_or_else(|p| p.into_inner());
+    let state = guard.get_or_insert_with(load);
+    let before = state.clone();
+    change(state);
+    if *state == before {
+        return;
+    }
+    let result = paths::data_dir().and_then(|dir| {
+        let json = serde_json::to_string_pretty(state).map_err(|e| e.to_string())?;
+        fs::write(dir.join(STATE_FILE), json).map_err(|e| e.to_string())
+    });
+    if let Err(e) = result {
+        warn!("Failed to save app state: {}", e);
+    }
+}
+
+fn load() -> AppState {
+    let Ok(dir) = paths::data_dir() else {
+        return AppState::default();
+    };
+    match fs::read_to_string(dir.join(STATE_FILE)) {
+        Ok(text) => serde_json::from_str(&text).unwrap_or_else(|e| {
+            warn!("Ignoring unreadable {}: {}", STATE_FILE, e);
+            AppState::default()
+        }),
+        Err(_) => {
+            let widget_position = fs::read_to_string(dir.join(LEGACY_WIDGET_POSITION_FILE))
+                .ok()
+                .and_then(|text| {
+                    let (x, y) = text.trim().split_once(',')?;
+                    Some((x.parse().ok()?, y.parse().ok()?))
+                });
+            AppState {
+                widget_position,
+                ..AppState::default()
+            }
+        }
+    }
+}
+
+// A saved position, unless the monitor it was on has since been unplugged,
+// which would leave the window off screen.
+pub fn on_screen<T>(
+    target: &EventLoopWindowTarget<T>,
+    position: Option<(i32, i32)>,
+) -> Option<PhysicalPosition<i32>> {
+    let (x, y) = position?;
+    target
+        .available_monitors()
+        .any(|monitor| {
+            let (origin, size) = (monitor.position(), monitor.size());
+            (origin.x..origin.x + size.width as i32).contains(&x)
+                && (origin.y..origin.y + size.height as i32).contains(&y)
+        })
+        .then(|| PhysicalPosition::new(x, y))
+}
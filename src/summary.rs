@@ -0,0 +1,276 @@
+// --- Daily Summary and Weekly Digest ---
+// Opt-in via `[summary] daily = true`: once a day, at the first check after
+// `daily_time`, a notification lists every rate with its change over the
+// last 24 hours (brecha included), for people who don't watch the tray.
+// `weekly = true` adds a digest of the past 7 days of history (average,
+// low, high and change per source) on `weekly_day`, shown as a notification
+// and, with `weekly_file`, written as text or HTML.
+use chrono::{DateTime, Datelike, Duration as Days, Local, NaiveDate, NaiveTime, Utc, Weekday};
+use rusqlite::Connection;
+use std::{
+    fs,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+use tracing::warn;
+
+use crate::{
+    config::Config,
+    db,
+    i18n::{self, Msg},
+    notify, paths, RateInfo,
+};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+// Remember the last day each was sent so restarts don't repeat them.
+const DAILY_STATE_FILE: &str = "summary_last_day";
+const WEEKLY_STATE_FILE: &str = "summary_last_week";
+const DIGEST_DAYS: i64 = 7;
+
+pub fn spawn(db: Arc<Mutex<Connection>>, config: Arc<Config>) {
+    let at = match NaiveTime::parse_from_str(&config.summary.daily_time, "%H:%M") {
+        Ok(at) => at,
+        Err(e) => {
+            warn!(
+                "Summaries disabled, invalid time '{}' (expected HH:MM): {}",
+                config.summary.daily_time, e
+            );
+            return;
+        }
+    };
+    let weekly_day = match config.summary.weekly_day.parse::<Weekday>() {
+        Ok(day) => Some(day),
+        Err(_) if config.summary.weekly => {
+            warn!(
+                "Weekly digest disabled, invalid day '{}'",
+                config.summary.weekly_day
+            );
+            None
+        }
+        Err(_) => None,
+    };
+    thread::spawn(move || {
+        let mut last_daily = read_last_day(DAILY_STATE_FILE);
+        let mut last_weekly = read_last_day(WEEKLY_STATE_FILE);
+        loop {
+            thread::sleep(CHECK_INTERVAL);
+            let now = Local::now();
+            let today = now.date_naive();
+            if now.time() < at {
+                continue;
+            }
+            if config.summary.daily && last_daily != Some(today) {
+                let rates = crate::load_rates(&db, &config);
+                if rates.iter().any(|rate| rate.last_updated.is_some()) {
+                    notify::notify(i18n::tr(Msg::DailySummaryTitle), &body(&rates));
+                    last_daily = Some(today);
+                    save_last_day(DAILY_STATE_FILE, today);
+                }
+            }
+            if config.summary.weekly
+                && weekly_day == Some(today.weekday())
+                && last_weekly != Some(today)
+            {
+                send_digest(&db, &config);
+                last_weekly = Some(today);
+                save_last_day(WEEKLY_STATE_FILE, today);
+            }
+        }
+    });
+}
+
+// "BCV: 178.35 Bs (+0.42%)", one line per rate that has a value.
+fn body(rates: &[RateInfo]) -> String {
+    rates
+        .iter()
+        .filter(|rate| rate.last_updated.is_some())
+        .map(|rate| {
+            let unit = if rate.unit == "%" {
+                "%".to_string()
+            } else {
+                format!(" {}", rate.unit)
+            };
+            let mut line = format!(
+                "{}: {}{}",
+                rate.currency,
+                rate.rounding.format(rate.rate),
+                unit
+            );
+            if let Some(change) = rate.day_change {
+                line.push_str(&format!(" ({:+.2}%)", change));
+            }
+            line
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// --- Weekly Digest ---
+
+struct WeekStats {
+    average: f64,
+    low: f64,
+    high: f64,
+    // Percent from the first to the last value of the week.
+    change: Option<f64>,
+}
+
+// `history` in recording order.
+fn week_stats(history: &[(DateTime<Utc>, f64)]) -> Option<WeekStats> {
+    let (first, last) = (history.first()?.1, history.last()?.1);
+    let rates = history.iter().map(|(_, rate)| *rate);
+    Some(WeekStats {
+        average: rates.clone().sum::<f64>() / history.len() as f64,
+        low: rates.clone().fold(f64::INFINITY, f64::min),
+        high: rates.fold(f64::NEG_INFINITY, f64::max),
+        change: (first != 0.0).then(|| (last - first) / first * 100.0),
+    })
+}
+
+// The digest for the 7 days up to now, as plain text or an HTML page.
+pub fn weekly_digest(conn: &Connection, config: &Config, html: bool) -> Result<String, String> {
+    let now = Utc::now();
+    let since = now - Days::days(DIGEST_DAYS);
+    let rates =
+        crate::fetch_rates(conn, config).map_err(|e| format!("Failed to read rates: {}", e))?;
+    let mut rows = Vec::new();
+    for rate in &rates {
+        let history = db::history_since(conn, &rate.symbol, since)
+            .map_err(|e| format!("Failed to read history: {}", e))?;
+        if let Some(stats) = week_stats(&history) {
+            rows.push((rate, stats));
+        }
+    }
+    let period = format!(
+        "{} – {}",
+        since.with_timezone(&Local).format("%Y-%m-%d"),
+        now.with_timezone(&Local).format("%Y-%m-%d")
+    );
+    let title = i18n::trf(Msg::WeeklyDigestPeriod, &[&period]);
+    let headers = i18n::tr(Msg::WeeklyDigestColumns);
+    let cells = |rate: &RateInfo, stats: &WeekStats| {
+        [
+            rate.currency.clone(),
+            rate.rounding.format(stats.average),
+            rate.rounding.format(stats.low),
+            rate.rounding.format(stats.high),
+            stats
+                .change
+                .map(|change| format!("{:+.2}%", change))
+                .unwrap_or_else(|| "-".to_string()),
+        ]
+    };
+    if rows.is_empty() {
+        return Ok(format!("{}\n{}\n", title, i18n::tr(Msg::WeeklyDigestEmpty)));
+    }
+    if !html {
+        let mut text = format!("{}\n", title);
+        for (rate, stats) in &rows {
+            let [name, average, low, high, change] = cells(rate, stats);
+            text.push_str(&i18n::trf(
+                Msg::WeeklyDigestLine,
+                &[&name, &average, &low, &high, &change],
+            ));
+            text.push('\n');
+        }
+        return Ok(text);
+    }
+    let mut page = format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{0}</title></head>\n\
+         <body><h1>{0}</h1>\n<table border=\"1\" cellpadding=\"4\">\n<tr>",
+        escape(&title)
+    );
+    for header in headers.split(',') {
+        page.push_str(&format!("<th>{}</th>", escape(header)));
+    }
+    page.push_str("</tr>\n");
+    for (rate, stats) in &rows {
+        page.push_str("<tr>");
+        for cell in cells(rate, stats) {
+            page.push_str(&format!("<td>{}</td>", escape(&cell)));
+        }
+        page.push_str("</tr>\n");
+    }
+    page.push_str("</table></body></html>\n");
+    Ok(page)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn send_digest(db: &Mutex<Connection>, config: &Config) {
+    let conn = db.lock().unwrap_or_else(|p| p.into_inner());
+    let text = match weekly_digest(&conn, config, false) {
+        Ok(text) => text,
+        Err(e) => {
+            warn!("Weekly digest failed: {}", e);
+            return;
+        }
+    };
+    let file = &config.summary.weekly_file;
+    if !file.is_empty() {
+        let html = file.ends_with(".html") || file.ends_with(".htm");
+        let result = if html {
+            weekly_digest(&conn, config, true)
+        } else {
+            Ok(text.clone())
+        }
+        .and_then(|content| {
+            fs::write(file, content).map_err(|e| format!("Failed to write {}: {}", file, e))
+        });
+        if let Err(e) = result {
+            warn!("Weekly digest: {}", e);
+        }
+    }
+    drop(conn);
+    // The title line is already the notification title.
+    let body = text.split_once('\n').map_or("", |(_, rest)| rest);
+    notify::notify(i18n::tr(Msg::WeeklyDigestTitle), body.trim_end());
+}
+
+fn read_last_day(state_file: &str) -> Option<NaiveDate> {
+    let text = fs::read_to_string(paths::data_dir().ok()?.join(state_file)).ok()?;
+    NaiveDate::parse_from_str(text.trim(), "%Y-%m-%d").ok()
+}
+
+fn save_last_day(state_file: &str, day: NaiveDate) {
+    let result = paths::data_dir().and_then(|dir| {
+        fs::write(dir.join(state_file), day.format("%Y-%m-%d").to_string())
+            .map_err(|e| e.to_string())
+    });
+    if let Err(e) = result {
+        warn!("Failed to save summary state: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(hour: u32) -> DateTime<Utc> {
+        DateTime::from_timestamp(1_700_000_000 + i64::from(hour) * 3600, 0).unwrap()
+    }
+
+    #[test]
+    fn week_stats_summarizes_history() {
+        let history = [
+            (at(0), 100.0),
+            (at(1), 120.0),
+            (at(2), 90.0),
+            (at(3), 110.0),
+        ];
+        let stats = week_stats(&history).unwrap();
+        assert_eq!(stats.average, 105.0);
+        assert_eq!((stats.low, stats.high), (90.0, 120.0));
+        assert!((stats.change.unwrap() - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn week_stats_of_nothing_is_none() {
+        assert!(week_stats(&[]).is_none());
+    }
+}
@@ -0,0 +1,86 @@
+// --- Taskbar Badge ---
+// Windows hides most tray icons in the overflow flyout. With `[tray]
+// taskbar_badge` the mini widget's taskbar button carries a small overlay
+// arrow with the direction the primary rate moved in the last 24 h, which
+// stays visible on the taskbar itself. Other platforms have no equivalent.
+use tao::window::Window;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Trend {
+    Up,
+    Down,
+    Flat,
+}
+
+impl Trend {
+    pub fn from_change(day_change: Option<f64>) -> Self {
+        match day_change {
+            Some(change) if change > 0.0 => Trend::Up,
+            Some(change) if change < 0.0 => Trend::Down,
+            _ => Trend::Flat,
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+pub fn set_overlay(window: &Window, trend: Trend) -> Result<(), String> {
+    use tao::platform::windows::WindowExtWindows;
+    use windows::{
+        core::HSTRING,
+        Win32::{
+            Foundation::HWND,
+            System::Com::{
+                CoCreateInstance, CoInitializeEx, CLSCTX_INPROC_SERVER, COINIT_APARTMENTTHREADED,
+            },
+            UI::{
+                Shell::{ITaskbarList3, TaskbarList},
+                WindowsAndMessaging::{CreateIcon, DestroyIcon, HICON},
+            },
+        },
+    };
+
+    const SIZE: i32 = 16;
+
+    let hwnd = HWND(window.hwnd() as _);
+    // The event loop thread already has COM; this only bumps its count.
+    let _ = unsafe { CoInitializeEx(None, COINIT_APARTMENTTHREADED) };
+    let taskbar: ITaskbarList3 =
+        unsafe { CoCreateInstance(&TaskbarList, None, CLSCTX_INPROC_SERVER) }
+            .map_err(|e| format!("Taskbar unavailable: {}", e))?;
+    unsafe { taskbar.HrInit() }.map_err(|e| format!("Taskbar unavailable: {}", e))?;
+
+    let (color, description) = match trend {
+        Trend::Up => ([0x4e, 0xb8, 0x2e, 0xff], "Up"),
+        Trend::Down => ([0x3c, 0x3c, 0xe0, 0xff], "Down"),
+        Trend::Flat => {
+            return unsafe {
+                taskbar.SetOverlayIcon(hwnd, HICON(std::ptr::null_mut()), &HSTRING::new())
+            }
+            .map_err(|e| format!("Failed to clear taskbar badge: {}", e));
+        }
+    };
+    // 32-bit BGRA, top row first; the AND mask is unused when there is alpha.
+    let mut pixels = Vec::with_capacity((SIZE * SIZE * 4) as usize);
+    for y in 0..SIZE {
+        // Triangle pointing up; flipped vertically for down.
+        let row = if trend == Trend::Up { y } else { SIZE - 1 - y };
+        let half_width = (row + 1) / 2;
+        for x in 0..SIZE {
+            let inside = row >= 2 && row < SIZE - 1 && (x - SIZE / 2).abs() <= half_width;
+            pixels.extend_from_slice(if inside { &color } else { &[0, 0, 0, 0] });
+        }
+    }
+    let mask = vec![0u8; (SIZE * SIZE / 8) as usize];
+    let icon = unsafe { CreateIcon(None, SIZE, SIZE, 1, 32, mask.as_ptr(), pixels.as_ptr()) }
+        .map_err(|e| format!("Failed to create taskbar badge: {}", e))?;
+    let result = unsafe { taskbar.SetOverlayIcon(hwnd, icon, &HSTRING::from(description)) }
+        .map_err(|e| format!("Failed to set taskbar badge: {}", e));
+    // The taskbar keeps its own copy.
+    let _ = unsafe { DestroyIcon(icon) };
+    result
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn set_overlay(_window: &Window, _trend: Trend) -> Result<(), String> {
+    Ok(())
+}
@@ -0,0 +1,248 @@
+// --- Tray Text Rendering ---
+// imageproc's draw_text_mut mixes every channel, alpha included, with the
+// glyph coverage. On the transparent tray canvas that darkens the color along
+// glyph edges, which is what made 16 px digits look ragged. Here coverage
+// becomes straight alpha, lifted by a gamma curve so thin stems keep their
+// weight, and is composited over the canvas in linear light. With
+// `[tray] supersample` the coverage comes from a pass at twice the size.
+// Characters the embedded font lacks (₿, some currency signs) are taken from
+// the `[fonts] fallback` list instead of drawing as empty boxes.
+use ab_glyph::{point, Font, FontVec, OutlinedGlyph, Point, PxScale, ScaleFont};
+use font_kit::{
+    family_name::FamilyName,
+    handle::Handle,
+    properties::{Properties, Weight},
+    source::SystemSource,
+};
+use image::{Rgba, RgbaImage};
+use std::{fs, sync::OnceLock};
+use tracing::{info, warn};
+
+static FALLBACKS: OnceLock<Vec<FontVec>> = OnceLock::new();
+
+// Coverage is raised to 1/GAMMA; above 1 thickens edges slightly, as most
+// text renderers do for light-on-dark and small sizes.
+const GAMMA: f32 = 1.45;
+
+// Loads the fallback fonts once at startup; unreadable ones are skipped.
+pub fn init_fallbacks(paths: &[String]) {
+    let fonts = paths
+        .iter()
+        .filter_map(|path| match read_font(path) {
+            Ok(font) => {
+                info!("Loaded fallback font {}", path);
+                Some(font)
+            }
+            Err(e) => {
+                warn!("Skipping fallback font {}: {}", path, e);
+                None
+            }
+        })
+        .collect();
+    let _ = FALLBACKS.set(fonts);
+}
+
+pub fn read_font(path: &str) -> Result<FontVec, String> {
+    fs::read(path)
+        .map_err(|e| e.to_string())
+        .and_then(|data| FontVec::try_from_vec(data).map_err(|_| "not a usable font".to_string()))
+}
+
+// The installed monospace the system prefers, bold if there is one, for when
+// the bundled font can't be used. Returns the font with where it came from.
+pub fn system_monospace() -> Result<(FontVec, String), String> {
+    let handle = SystemSource::new()
+        .select_best_match(
+            &[FamilyName::Monospace],
+            Properties::new().weight(Weight::BOLD),
+        )
+        .map_err(|e| format!("No usable font: no system monospace font found ({})", e))?;
+    let (data, index, name) = match handle {
+        Handle::Path { path, font_index } => {
+            let data = fs::read(&path)
+                .map_err(|e| format!("Could not read font {}: {}", path.display(), e))?;
+            (data, font_index, path.display().to_string())
+        }
+        Handle::Memory { bytes, font_index } => {
+            (bytes.to_vec(), font_index, "system monospace".to_string())
+        }
+    };
+    let font = FontVec::try_from_vec_and_index(data, index)
+        .map_err(|_| format!("Failed to parse font {}", name))?;
+    Ok((font, name))
+}
+
+// Outlines of `text` laid out on one line from `start` (on the baseline),
+// each character from the first of `font` and the fallbacks that has it. All
+// share `font`'s baseline. Characters with nothing to draw (spaces) are left out.
+pub fn layout(font: &FontVec, text: &str, scale: PxScale, start: Point) -> Vec<OutlinedGlyph> {
+    let fallbacks = FALLBACKS.get().map(Vec::as_slice).unwrap_or_default();
+    let mut glyphs = Vec::new();
+    let mut caret = start.x;
+    let mut previous = None;
+    for c in text.chars().filter(|c| !c.is_control()) {
+        let (index, face) = std::iter::once(font)
+            .chain(fallbacks)
+            .enumerate()
+            .find(|(_, face)| face.glyph_id(c).0 != 0)
+            .unwrap_or((0, font));
+        let face = face.as_scaled(scale);
+        let id = face.glyph_id(c);
+        // Kerning only makes sense between glyphs of the same face.
+        if let Some((previous_index, previous_id)) = previous {
+            if previous_index == index {
+                caret += face.kern(previous_id, id);
+            }
+        }
+        previous = Some((index, id));
+        let glyph = id.with_scale_and_position(scale, point(caret, start.y));
+        caret += face.h_advance(id);
+        glyphs.extend(face.outline_glyph(glyph));
+    }
+    glyphs
+}
+
+// Right edge of the pixels `text` covers when drawn at x = 0.
+pub fn width(font: &FontVec, text: &str, scale: PxScale) -> i32 {
+    layout(font, text, scale, point(0.0, 0.0))
+        .iter()
+        .map(|glyph| glyph.px_bounds().max.x as i32)
+        .max()
+        .unwrap_or(0)
+}
+
+// Same placement as draw_text_mut: `y` is the top of the line box.
+#[allow(clippy::too_many_arguments)]
+pub fn draw_text(
+    canvas: &mut RgbaImage,
+    color: Rgba<u8>,
+    x: i32,
+    y: i32,
+    scale: PxScale,
+    font: &FontVec,
+    text: &str,
+    supersample: bool,
+) {
+    let factor: u32 = if supersample { 2 } else { 1 };
+    let (width, height) = canvas.dimensions();
+    let (big_w, big_h) = (width * factor, height * factor);
+    let mut coverage = vec![0f32; (big_w * big_h) as usize];
+    let big_scale = PxScale {
+        x: scale.x * factor as f32,
+        y: scale.y * factor as f32,
+    };
+    let ascent = font.as_scaled(big_scale).ascent();
+    let (origin_x, origin_y) = (x * factor as i32, y * factor as i32);
+    for glyph in layout(font, text, big_scale, point(0.0, ascent)) {
+        let bb = glyph.px_bounds();
+        glyph.draw(|gx, gy, v| {
+            let px = origin_x + bb.min.x as i32 + gx as i32;
+            let py = origin_y + bb.min.y as i32 + gy as i32;
+            if px >= 0 && py >= 0 && (px as u32) < big_w && (py as u32) < big_h {
+                let cell = &mut coverage[(py as u32 * big_w + px as u32) as usize];
+                *cell = (*cell + v).min(1.0);
+            }
+        });
+    }
+    let samples = (factor * factor) as f32;
+    for (cx, cy, pixel) in canvas.enumerate_pixels_mut() {
+        let mut sum = 0.0;
+        for sy in 0..factor {
+            for sx in 0..factor {
+                sum += coverage[((cy * factor + sy) * big_w + cx * factor + sx) as usize];
+            }
+        }
+        let cov = sum / samples;
+        if cov > 0.0 {
+            let alpha = cov.powf(1.0 / GAMMA) * color.0[3] as f32 / 255.0;
+            *pixel = over(color, alpha, *pixel);
+        }
+    }
+}
+
+// `color` at `alpha` composited over `dst`, both straight alpha.
+fn over(color: Rgba<u8>, alpha: f32, dst: Rgba<u8>) -> Rgba<u8> {
+    let dst_alpha = dst.0[3] as f32 / 255.0;
+    let out_alpha = alpha + dst_alpha * (1.0 - alpha);
+    if out_alpha <= 0.0 {
+        return Rgba([0, 0, 0, 0]);
+    }
+    let channel = |c: usize| {
+        let src = to_linear(color.0[c]);
+        let below = to_linear(dst.0[c]);
+        to_srgb((src * alpha + below * dst_alpha * (1.0 - alpha)) / out_alpha)
+    };
+    Rgba([
+        channel(0),
+        channel(1),
+        channel(2),
+        (out_alpha * 255.0).round() as u8,
+    ])
+}
+
+fn to_linear(value: u8) -> f32 {
+    let v = value as f32 / 255.0;
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn to_srgb(linear: f32) -> u8 {
+    let v = if linear <= 0.003_130_8 {
+        linear * 12.92
+    } else {
+        1.055 * linear.powf(1.0 / 2.4) - 0.055
+    };
+    (v.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(supersample: bool) -> RgbaImage {
+        let (font, _) = crate::load_font(&Default::default()).unwrap();
+        let mut canvas = RgbaImage::from_pixel(40, 16, Rgba([0, 0, 0, 0]));
+        let white = Rgba([255, 255, 255, 255]);
+        draw_text(
+            &mut canvas,
+            white,
+            0,
+            -2,
+            PxScale::from(19.2),
+            &font,
+            "36.5",
+            supersample,
+        );
+        canvas
+    }
+
+    #[test]
+    fn edges_keep_the_text_color() {
+        for supersample in [false, true] {
+            let canvas = render(supersample);
+            let drawn: Vec<_> = canvas.pixels().filter(|p| p.0[3] > 0).collect();
+            assert!(!drawn.is_empty());
+            // Only alpha fades out; the color itself stays white.
+            assert!(drawn.iter().all(|p| p.0[..3] == [255, 255, 255]));
+            assert!(drawn.iter().any(|p| p.0[3] < 255));
+        }
+    }
+
+    #[test]
+    fn srgb_round_trips() {
+        for value in [0u8, 1, 40, 128, 200, 255] {
+            assert_eq!(to_srgb(to_linear(value)), value);
+        }
+    }
+
+    #[test]
+    fn over_opaque_background_stays_opaque() {
+        let mixed = over(Rgba([255, 255, 255, 255]), 0.5, Rgba([0, 0, 0, 255]));
+        assert_eq!(mixed.0[3], 255);
+        // Halfway in linear light is brighter than halfway in sRGB.
+        assert!(mixed.0[0] > 128);
+    }
+}
@@ -0,0 +1,35 @@
+// --- Time Formatting ---
+// Timestamps are stored in UTC; anything shown to the user goes through here
+// so it is in local time or phrased relative to now ("hace 12 min").
+use chrono::{DateTime, Local, TimeZone, Utc};
+
+use crate::i18n::{self, Msg};
+
+pub fn local(ts: DateTime<Utc>) -> String {
+    ts.with_timezone(&Local)
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string()
+}
+
+// Start of the current local day, for "today" figures.
+pub fn local_midnight() -> DateTime<Utc> {
+    let midnight = Local::now().date_naive().and_time(chrono::NaiveTime::MIN);
+    Local
+        .from_local_datetime(&midnight)
+        .earliest()
+        .map(|ts| ts.with_timezone(&Utc))
+        .unwrap_or_else(|| Utc::now() - chrono::Duration::days(1))
+}
+
+pub fn relative(ts: DateTime<Utc>) -> String {
+    let minutes = (Utc::now() - ts).num_minutes();
+    if minutes < 1 {
+        i18n::tr(Msg::JustNow).to_string()
+    } else if minutes < 60 {
+        i18n::trf(Msg::MinutesAgo, &[&minutes])
+    } else if minutes < 48 * 60 {
+        i18n::trf(Msg::HoursAgo, &[&(minutes / 60)])
+    } else {
+        i18n::trf(Msg::DaysAgo, &[&(minutes / (24 * 60))])
+    }
+}
@@ -0,0 +1,42 @@
+// --- Tray Host Watcher ---
+// Windows re-adds the icon itself on "TaskbarCreated" (handled inside tray-icon).
+// On Linux, a restarted StatusNotifierWatcher (e.g. plasmashell crash) forgets
+// every registered item, so we watch the bus and ask the event loop to rebuild.
+
+#[cfg(target_os = "linux")]
+pub fn spawn_watcher<F>(on_host_restarted: F)
+where
+    F: Fn() + Send + 'static,
+{
+    std::thread::spawn(move || {
+        if let Err(e) = watch_status_notifier_host(on_host_restarted) {
+            tracing::warn!("Tray host watcher stopped: {}", e);
+        }
+    });
+}
+
+#[cfg(target_os = "linux")]
+fn watch_status_notifier_host<F: Fn()>(on_host_restarted: F) -> zbus::Result<()> {
+    const WATCHER_NAME: &str = "org.kde.StatusNotifierWatcher";
+
+    let conn = zbus::blocking::Connection::session()?;
+    let dbus = zbus::blocking::fdo::DBusProxy::new(&conn)?;
+    let changes = dbus.receive_name_owner_changed_with_args(&[(0, WATCHER_NAME)])?;
+    for change in changes {
+        let args = change.args()?;
+        if args.new_owner().is_some() {
+            tracing::info!("StatusNotifierWatcher (re)appeared, re-registering tray icon.");
+            on_host_restarted();
+        } else {
+            tracing::info!("StatusNotifierWatcher went away, waiting for it to return.");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn spawn_watcher<F>(_on_host_restarted: F)
+where
+    F: Fn() + Send + 'static,
+{
+}
@@ -0,0 +1,105 @@
+// --- Trend Indicator ---
+// With `[trend] show = true` each tray value gets a small glyph for where the
+// rate has been heading over the last `hours`. It comes from the slope of a
+// least-squares line through the stored history, so unlike the attention
+// caret a single odd reading doesn't flip it.
+use chrono::{DateTime, Utc};
+
+use crate::taskbar::Trend;
+
+// Material Design "trending" icons from the embedded Nerd Font; it has no
+// plain diagonal arrows.
+const RISING: char = '\u{f0535}';
+const STEADY: char = '\u{f0534}';
+const FALLING: char = '\u{f0533}';
+
+// Percent the fitted line moves from the first reading to the last, or None
+// with fewer than two readings at different times.
+pub fn fitted_change(history: &[(DateTime<Utc>, f64)]) -> Option<f64> {
+    let (start, _) = *history.first()?;
+    let points: Vec<(f64, f64)> = history
+        .iter()
+        .map(|(at, rate)| ((*at - start).num_seconds() as f64 / 3600.0, *rate))
+        .collect();
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let (covariance, variance) = points.iter().fold((0.0, 0.0), |(cov, var), (x, y)| {
+        (
+            cov + (x - mean_x) * (y - mean_y),
+            var + (x - mean_x).powi(2),
+        )
+    });
+    if variance == 0.0 {
+        return None;
+    }
+    let slope = covariance / variance;
+    // Relative to where the fitted line starts.
+    let start_value = mean_y - slope * mean_x;
+    if start_value == 0.0 {
+        return None;
+    }
+    let span = points.last()?.0;
+    Some(slope * span / start_value * 100.0)
+}
+
+// Changes within `flat_pct` either way count as steady.
+pub fn classify(change: f64, flat_pct: f64) -> Trend {
+    if change > flat_pct {
+        Trend::Up
+    } else if change < -flat_pct {
+        Trend::Down
+    } else {
+        Trend::Flat
+    }
+}
+
+pub fn glyph(trend: Trend) -> char {
+    match trend {
+        Trend::Up => RISING,
+        Trend::Down => FALLING,
+        Trend::Flat => STEADY,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(rates: &[f64]) -> Vec<(DateTime<Utc>, f64)> {
+        rates
+            .iter()
+            .enumerate()
+            .map(|(i, rate)| {
+                let at = DateTime::from_timestamp(1_700_000_000 + i as i64 * 3600, 0).unwrap();
+                (at, *rate)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn straight_line_change_is_exact() {
+        let change = fitted_change(&history(&[100.0, 101.0, 102.0])).unwrap();
+        assert!((change - 2.0).abs() < 1e-9, "got {}", change);
+    }
+
+    #[test]
+    fn one_spike_does_not_reverse_the_trend() {
+        // The last reading dips, but the series is still rising overall.
+        let change = fitted_change(&history(&[100.0, 101.0, 102.0, 103.0, 101.5])).unwrap();
+        assert!(change > 0.0, "got {}", change);
+    }
+
+    #[test]
+    fn needs_two_distinct_times() {
+        assert_eq!(fitted_change(&[]), None);
+        assert_eq!(fitted_change(&history(&[100.0])), None);
+    }
+
+    #[test]
+    fn small_moves_are_steady() {
+        assert_eq!(classify(0.05, 0.1), Trend::Flat);
+        assert_eq!(classify(0.5, 0.1), Trend::Up);
+        assert_eq!(classify(-0.5, 0.1), Trend::Down);
+    }
+}
@@ -0,0 +1,258 @@
+// --- Terminal View ---
+// `bcv-tray tui` shows the current rates, a 24 h sparkline, each provider's
+// health and when each source last updated, redrawn every few seconds. It only
+// reads the rate database, where the fetching instance also mirrors its
+// HealthRegistry, so it pairs with a tray or headless instance doing the
+// fetching, e.g. over SSH on a server. Drawn with ratatui on crossterm; q, Esc
+// or Ctrl+C quits and leaves the terminal as it was.
+use chrono::{DateTime, Duration as Span, Local, Utc};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span as TextSpan},
+    widgets::{Cell, Paragraph, Row, Table},
+    DefaultTerminal, Frame,
+};
+use rusqlite::Connection;
+use std::time::{Duration, Instant};
+
+use crate::{
+    config::Config,
+    db,
+    health::{HealthRegistry, ProviderHealth},
+    paths, timefmt,
+};
+
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+const SPARK_HOURS: i64 = 24;
+const SPARK_CHARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+struct RateRow {
+    source: String,
+    value: String,
+    change: Option<f64>,
+    spark: String,
+    health: ProviderHealth,
+    updated: String,
+}
+
+pub fn run(config: &Config) -> Result<(), String> {
+    let conn = db::open_database(&paths::database_path()?)?;
+    // Raw mode and the alternate screen; a panic hook undoes both too.
+    let mut terminal =
+        ratatui::try_init().map_err(|e| format!("Failed to set up the terminal: {}", e))?;
+    let result = run_loop(&mut terminal, &conn, config);
+    ratatui::restore();
+    result
+}
+
+fn run_loop(
+    terminal: &mut DefaultTerminal,
+    conn: &Connection,
+    config: &Config,
+) -> Result<(), String> {
+    let terminal_error = |e: std::io::Error| format!("Terminal error: {}", e);
+    loop {
+        let rows = read_rows(conn, config)?;
+        terminal
+            .draw(|frame| draw(frame, &rows))
+            .map_err(terminal_error)?;
+        // Wait for a key until the next refresh; a resize redraws right away.
+        let deadline = Instant::now() + REFRESH_INTERVAL;
+        loop {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            if timeout.is_zero() || !event::poll(timeout).map_err(terminal_error)? {
+                break;
+            }
+            match event::read().map_err(terminal_error)? {
+                Event::Key(key) if quits(key) => return Ok(()),
+                Event::Resize(..) => break,
+                _ => {}
+            }
+        }
+    }
+}
+
+// Raw mode turns Ctrl+C into a key press instead of a signal.
+fn quits(key: KeyEvent) -> bool {
+    key.kind == KeyEventKind::Press
+        && match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => true,
+            KeyCode::Char('c') => key.modifiers.contains(KeyModifiers::CONTROL),
+            _ => false,
+        }
+}
+
+fn read_rows(conn: &Connection, config: &Config) -> Result<Vec<RateRow>, String> {
+    let rates =
+        crate::fetch_rates(conn, config).map_err(|e| format!("Failed to read rates: {}", e))?;
+    let health = HealthRegistry::from_stored(
+        db::load_health(conn).map_err(|e| format!("Failed to read provider health: {}", e))?,
+    );
+    let now = Utc::now();
+    let mut rows = Vec::new();
+    for rate in &rates {
+        let value = match rate.last_updated {
+            Some(_) if rate.unit == "%" => format!("{}%", rate.rounding.format(rate.rate)),
+            Some(_) => format!("{} {}", rate.rounding.format(rate.rate), rate.unit),
+            None => "--".to_string(),
+        };
+        let history = db::history_since(conn, &rate.symbol, now - Span::hours(SPARK_HOURS))
+            .map_err(|e| format!("Failed to read history: {}", e))?;
+        let updated = rate
+            .last_updated
+            .map(|at| format!("{} · {}", timefmt::relative(at), rate.provider))
+            .unwrap_or_else(|| rate.provider.clone());
+        rows.push(RateRow {
+            source: rate.currency.clone(),
+            value,
+            change: rate.day_change,
+            spark: sparkline(&hourly(&history, now, SPARK_HOURS)),
+            health: health.get(&rate.symbol),
+            updated,
+        });
+    }
+    Ok(rows)
+}
+
+fn draw(frame: &mut Frame, rows: &[RateRow]) {
+    let dim = Style::default().add_modifier(Modifier::DIM);
+    let [title, body] =
+        Layout::vertical([Constraint::Length(2), Constraint::Min(0)]).areas(frame.area());
+    frame.render_widget(
+        Paragraph::new(Line::from(vec![
+            TextSpan::raw(format!("bcv-tray  {}  ", Local::now().format("%H:%M:%S"))),
+            TextSpan::styled("q to quit", dim),
+        ])),
+        title,
+    );
+    let right = |text: &'static str| Cell::from(Line::from(text).right_aligned());
+    let header = Row::new([
+        Cell::from("Source"),
+        right("Rate"),
+        right("24h"),
+        Cell::from("Last 24 h"),
+        Cell::from("Health"),
+        Cell::from("Updated"),
+    ])
+    .style(dim);
+    let lines = rows.iter().map(|row| {
+        let change = match row.change {
+            Some(change) => Cell::from(Line::from(format!("{:+.2}%", change)).right_aligned())
+                .style(Style::default().fg(trend_color(change))),
+            None => Cell::from(Line::from("--").right_aligned()),
+        };
+        let health_style = if row.health.consecutive_failures > 0 {
+            Style::default().fg(Color::Red)
+        } else {
+            Style::default()
+        };
+        Row::new([
+            Cell::from(row.source.as_str()),
+            Cell::from(Line::from(row.value.as_str()).right_aligned()),
+            change,
+            Cell::from(row.spark.as_str()),
+            Cell::from(health_text(&row.health)).style(health_style),
+            Cell::from(row.updated.as_str()).style(dim),
+        ])
+    });
+    let widths = [
+        Constraint::Length(10),
+        Constraint::Length(14),
+        Constraint::Length(8),
+        Constraint::Length(24),
+        Constraint::Length(18),
+        Constraint::Fill(1),
+    ];
+    frame.render_widget(Table::new(lines, widths).header(header), body);
+}
+
+fn trend_color(change: f64) -> Color {
+    match change {
+        c if c > 0.0 => Color::Green,
+        c if c < 0.0 => Color::Red,
+        _ => Color::Reset,
+    }
+}
+
+// Success rate and average latency, plus the failure streak when there is one.
+fn health_text(health: &ProviderHealth) -> String {
+    match (health.success_rate(), health.average_latency()) {
+        (Some(rate), Some(latency)) => {
+            let mut text = format!("{:.0}% {} ms", rate * 100.0, latency.as_millis());
+            if health.consecutive_failures > 0 {
+                text.push_str(&format!(" ✗{}", health.consecutive_failures));
+            }
+            text
+        }
+        _ => "--".to_string(),
+    }
+}
+
+// The last value in each of the `hours` hours up to `now`, carried forward
+// through hours without readings; hours before the first reading are left out.
+fn hourly(history: &[(DateTime<Utc>, f64)], now: DateTime<Utc>, hours: i64) -> Vec<f64> {
+    let mut values = Vec::new();
+    let mut readings = history.iter().peekable();
+    let mut last = None;
+    for hour in (0..hours).rev() {
+        let end = now - Span::hours(hour);
+        while let Some((_, rate)) = readings.next_if(|(at, _)| *at <= end) {
+            last = Some(*rate);
+        }
+        values.extend(last);
+    }
+    values
+}
+
+fn sparkline(values: &[f64]) -> String {
+    let low = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let high = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let top = (SPARK_CHARS.len() - 1) as f64;
+    values
+        .iter()
+        .map(|value| {
+            let level = if high > low {
+                ((value - low) / (high - low) * top).round() as usize
+            } else {
+                SPARK_CHARS.len() / 2
+            };
+            SPARK_CHARS[level.min(SPARK_CHARS.len() - 1)]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sparkline_spans_low_to_high() {
+        assert_eq!(sparkline(&[1.0, 2.0, 3.0]), "▁▅█");
+        assert_eq!(sparkline(&[5.0, 5.0]), "▅▅");
+        assert_eq!(sparkline(&[]), "");
+    }
+
+    #[test]
+    fn hourly_carries_values_forward() {
+        let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let history = [
+            (now - Span::minutes(150), 10.0),
+            (now - Span::minutes(130), 11.0),
+            (now - Span::minutes(30), 12.0),
+        ];
+        // Hours ending 3h, 2h, 1h and 0h ago.
+        assert_eq!(hourly(&history, now, 4), vec![11.0, 11.0, 12.0]);
+    }
+
+    #[test]
+    fn health_text_shows_failure_streaks() {
+        let registry = HealthRegistry::default();
+        assert_eq!(health_text(&registry.get("bcv")), "--");
+        registry.record("bcv", Duration::from_millis(300), Ok(()));
+        assert_eq!(health_text(&registry.get("bcv")), "100% 300 ms");
+        registry.record("bcv", Duration::from_millis(100), Err("timeout"));
+        assert_eq!(health_text(&registry.get("bcv")), "50% 200 ms ✗1");
+    }
+}
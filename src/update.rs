@@ -0,0 +1,207 @@
+// --- Update Cycle ---
+use reqwest::blocking::Client;
+use rusqlite::Connection;
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Instant,
+};
+use tracing::{debug, error, info, info_span, warn};
+
+use crate::{
+    alerts::AlertEngine,
+    api, attention, audit,
+    config::{AttentionConfig, MeteredConfig, OverlayConfig, PowerConfig},
+    db, demo,
+    depeg::{self, DepegMonitor},
+    derived::{self, DerivedSymbol},
+    fiat, grpc,
+    health::HealthRegistry,
+    metered,
+    metrics::Pushgateway,
+    overlay, power, providers,
+    schedule::Schedule,
+    sheets::SheetsExporter,
+    snooze,
+};
+
+// Set once a cycle stores something; until then the tray shows what the
+// previous session left in the database, marked as such.
+static UPDATED: AtomicBool = AtomicBool::new(false);
+
+pub fn updated_this_session() -> bool {
+    UPDATED.load(Ordering::Relaxed)
+}
+
+// Shared handles every fetch thread needs.
+pub struct UpdateContext {
+    pub db: Arc<Mutex<Connection>>,
+    pub http_client: Client,
+    pub cmc_keys: providers::CmcKeys,
+    // Shared with the API, which reports it on `/rates` and `/metrics`.
+    pub health: Arc<HealthRegistry>,
+    // Feed synthetic rates instead of calling the providers.
+    pub demo: bool,
+    pub derived: Vec<DerivedSymbol>,
+    pub alerts: AlertEngine,
+    pub depeg: DepegMonitor,
+    pub attention: AttentionConfig,
+    pub schedule: Schedule,
+    pub power: PowerConfig,
+    pub metered: MeteredConfig,
+    pub sheets: SheetsExporter,
+    pub pushgateway: Pushgateway,
+    pub overlay: OverlayConfig,
+}
+
+pub fn perform_data_update(ctx: &UpdateContext, trigger: &str) -> Result<(), String> {
+    let symbols: Vec<&str> = fiat::tracked()
+        .iter()
+        .flat_map(|fiat| fiat.sources.iter().map(|source| source.symbol))
+        .chain(
+            providers::configured_sources()
+                .iter()
+                .map(|source| source.symbol.as_str()),
+        )
+        .chain(providers::optional_sources().map(|source| source.symbol))
+        .chain(ctx.depeg.enabled().then_some(depeg::SYMBOL))
+        .collect();
+    update_symbols(ctx, trigger, &symbols)
+}
+
+// A single source outside the regular cycle, e.g. BCV during its publication window.
+pub fn perform_symbol_update(
+    ctx: &UpdateContext,
+    symbol: &str,
+    trigger: &str,
+) -> Result<(), String> {
+    update_symbols(ctx, trigger, &[symbol])
+}
+
+fn update_symbols(ctx: &UpdateContext, trigger: &str, symbols: &[&str]) -> Result<(), String> {
+    let _cycle = info_span!("update_cycle", trigger).entered();
+    info!("Performing data update from APIs...");
+    let mut an_update_succeeded = false;
+    // A cycle where every source was waiting for its interval hasn't failed.
+    let (mut attempted, mut not_due) = (false, false);
+    power::refresh(&ctx.power);
+    if !ctx.demo {
+        metered::refresh(&ctx.metered);
+    }
+    if metered::blocks(trigger, &ctx.metered) {
+        return Err("metered connection, waiting for Update Now".to_string());
+    }
+    if snooze::blocks(trigger) {
+        return Err("updates paused from the tray menu".to_string());
+    }
+
+    for &symbol in symbols.iter().filter(|s| providers::enabled(s)) {
+        if ctx.demo {
+            an_update_succeeded |= update_from_provider(ctx, symbol, || {
+                audit::endpoint("demo", "");
+                demo::rate(symbol)
+            });
+            continue;
+        }
+        // CoinMarketCap has no keyless access, so SAT and `[cryptos]` only run with a key.
+        if providers::uses_cmc(symbol) && ctx.cmc_keys.is_empty() {
+            continue;
+        }
+        if power::is_paused(symbol, trigger, &ctx.power) {
+            info!("Skipping {}: paused on battery", symbol);
+            continue;
+        }
+        let have_rate = has_rate(ctx, symbol);
+        if let Some(reason) = ctx.schedule.skip_reason(symbol, trigger, have_rate) {
+            info!("Skipping {}: {}", symbol, reason);
+            continue;
+        }
+        // Every cycle would log this for slow sources, so only at debug.
+        if !ctx.schedule.due(symbol, trigger, have_rate) {
+            debug!("Skipping {}: its interval hasn't passed", symbol);
+            not_due = true;
+            continue;
+        }
+        ctx.schedule.record_attempt(symbol);
+        attempted = true;
+        an_update_succeeded |= update_from_provider(ctx, symbol, || {
+            providers::fetch(symbol, &ctx.http_client, &ctx.cmc_keys)
+        });
+    }
+
+    if attempted {
+        let conn_guard = ctx.db.lock().unwrap_or_else(|p| p.into_inner());
+        if let Err(e) = db::store_health(&conn_guard, &ctx.health.all()) {
+            warn!("Failed to store provider health: {}", e);
+        }
+    }
+    if an_update_succeeded {
+        UPDATED.store(true, Ordering::Relaxed);
+        {
+            let conn_guard = ctx.db.lock().unwrap_or_else(|p| p.into_inner());
+            derived::update_all(&conn_guard, &ctx.derived);
+            ctx.alerts.evaluate(&conn_guard);
+            ctx.depeg.evaluate(&conn_guard);
+            overlay::write_text_file(&conn_guard, &ctx.overlay);
+        }
+        api::publish();
+        grpc::publish();
+        // Demo rates are made up; keep them out of the user's sheet and metrics.
+        if !ctx.demo {
+            ctx.sheets.after_update(&ctx.db);
+            ctx.pushgateway.after_update(&ctx.db, &ctx.health);
+        }
+        Ok(())
+    } else if not_due && !attempted {
+        Ok(())
+    } else {
+        Err("No rates were successfully updated.".to_string())
+    }
+}
+
+fn has_rate(ctx: &UpdateContext, symbol: &str) -> bool {
+    let conn_guard = ctx.db.lock().unwrap_or_else(|p| p.into_inner());
+    matches!(db::latest_rate(&conn_guard, symbol), Ok(Some(_)))
+}
+
+fn update_from_provider(
+    ctx: &UpdateContext,
+    symbol: &str,
+    fetch: impl FnOnce() -> Result<f64, String>,
+) -> bool {
+    let _span = info_span!("provider", symbol).entered();
+    let started = Instant::now();
+    audit::take();
+    let fetched = fetch();
+    let latency = started.elapsed();
+    let source = audit::take();
+    let rate = match fetched {
+        Ok(rate) => rate,
+        Err(e) => {
+            warn!("{}", e);
+            ctx.health.record(symbol, latency, Err(&e));
+            return false;
+        }
+    };
+    let conn_guard = ctx.db.lock().unwrap_or_else(|p| p.into_inner());
+    let previous = db::latest_rate(&conn_guard, symbol).ok().flatten();
+    if let Some(previous) = previous {
+        attention::record(&ctx.attention, symbol, previous, rate);
+    }
+    match db::store_quote(&conn_guard, symbol, rate, &source) {
+        Ok(()) => {
+            ctx.schedule.record_fetch(symbol, previous, rate);
+            info!("Updated {}: {} ({} ms)", symbol, rate, latency.as_millis());
+            ctx.health.record(symbol, latency, Ok(()));
+            true
+        }
+        Err(e) => {
+            error!("Failed to update {} in DB: {}", symbol, e);
+            ctx.health
+                .record(symbol, latency, Err(&format!("database: {}", e)));
+            false
+        }
+    }
+}
@@ -0,0 +1,237 @@
+// --- Mini Widget ---
+// A small frameless window that stays above everything else and shows the
+// rates in larger type, for desktops that hide or shrink tray icons. Drag it
+// anywhere with the mouse; Esc closes it. Its position is saved with the app
+// state and restored next time.
+use ab_glyph::{FontVec, PxScale};
+use image::RgbaImage;
+use imageproc::drawing::draw_text_mut;
+use softbuffer::{Context, Surface};
+use std::{
+    rc::Rc,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tao::{
+    dpi::{LogicalSize, PhysicalPosition},
+    event::{ElementState, KeyEvent, MouseButton, WindowEvent},
+    event_loop::EventLoopWindowTarget,
+    keyboard::Key,
+    window::{Window, WindowBuilder, WindowId},
+};
+use tracing::{debug, warn};
+
+use crate::{
+    converter::{self, BACKGROUND, DIMMED, FOREGROUND},
+    i18n::{self, Msg},
+    state,
+    taskbar::{self, Trend},
+    text, RateInfo,
+};
+
+// Moves arrive continuously while dragging; the final one is saved on close.
+const SAVE_INTERVAL: Duration = Duration::from_secs(2);
+
+const WIDTH: f64 = 190.0;
+const FONT_SIZE: f32 = 24.0;
+const SMALL_FONT_SIZE: f32 = 13.0;
+// A rate and, below it, today's low-high range.
+const LINE_HEIGHT: f64 = 46.0;
+const RANGE_OFFSET: f64 = 27.0;
+const MARGIN: f64 = 10.0;
+
+pub struct WidgetWindow {
+    window: Rc<Window>,
+    surface: Surface<Rc<Window>, Rc<Window>>,
+    font: Arc<FontVec>,
+    rates: Vec<RateInfo>,
+    position: Option<PhysicalPosition<i32>>,
+    last_saved: Instant,
+    // Set with `[tray] taskbar_badge`; remembers what the badge shows.
+    badge: Option<Option<Trend>>,
+}
+
+impl WidgetWindow {
+    pub fn open<T>(
+        target: &EventLoopWindowTarget<T>,
+        font: Arc<FontVec>,
+        rates: Vec<RateInfo>,
+        taskbar_badge: bool,
+    ) -> Result<Self, String> {
+        let mut builder = WindowBuilder::new()
+            .with_title(i18n::tr(Msg::MiniWidget))
+            .with_inner_size(size_for(rates.len()))
+            .with_decorations(false)
+            .with_always_on_top(true)
+            .with_resizable(false);
+        let position = state::on_screen(target, state::get().widget_position);
+        if let Some(position) = position {
+            builder = builder.with_position(position);
+        }
+        let window = builder
+            .build(target)
+            .map_err(|e| format!("Failed to create widget window: {}", e))?;
+        let window = Rc::new(window);
+        let context = Context::new(window.clone())
+            .map_err(|e| format!("Failed to create drawing context: {}", e))?;
+        let surface = Surface::new(&context, window.clone())
+            .map_err(|e| format!("Failed to create drawing surface: {}", e))?;
+        let mut widget = Self {
+            window,
+            surface,
+            font,
+            rates: Vec::new(),
+            position,
+            last_saved: Instant::now(),
+            badge: taskbar_badge.then_some(None),
+        };
+        widget.set_rates(rates);
+        Ok(widget)
+    }
+
+    pub fn id(&self) -> WindowId {
+        self.window.id()
+    }
+
+    pub fn set_rates(&mut self, rates: Vec<RateInfo>) {
+        if rates.len() != self.rates.len() {
+            self.window.set_inner_size(size_for(rates.len()));
+        }
+        self.rates = rates;
+        self.window.request_redraw();
+        self.update_badge();
+    }
+
+    // The taskbar button may not exist yet right after opening, so a failed
+    // update is retried with the next rates.
+    fn update_badge(&mut self) {
+        let Some(shown) = self.badge else {
+            return;
+        };
+        let trend = Trend::from_change(self.rates.first().and_then(|rate| rate.day_change));
+        if shown == Some(trend) {
+            return;
+        }
+        match taskbar::set_overlay(&self.window, trend) {
+            Ok(()) => self.badge = Some(Some(trend)),
+            Err(e) => debug!("{}", e),
+        }
+    }
+
+    // Returns false once the window should be closed.
+    pub fn handle_event(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::CloseRequested
+            | WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        logical_key: Key::Escape,
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => return false,
+            WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                button: MouseButton::Left,
+                ..
+            } => {
+                if let Err(e) = self.window.drag_window() {
+                    warn!("Could not drag the widget: {}", e);
+                }
+            }
+            WindowEvent::Moved(position) => {
+                self.position = Some(*position);
+                if self.last_saved.elapsed() >= SAVE_INTERVAL {
+                    self.save_position();
+                }
+            }
+            WindowEvent::Resized(_) | WindowEvent::ScaleFactorChanged { .. } => {
+                self.window.request_redraw();
+            }
+            _ => {}
+        }
+        true
+    }
+
+    fn save_position(&mut self) {
+        self.last_saved = Instant::now();
+        if let Some(position) = self.position {
+            state::update(|state| state.widget_position = Some((position.x, position.y)));
+        }
+    }
+
+    pub fn redraw(&mut self) -> Result<(), String> {
+        let size = self.window.inner_size();
+        let canvas = self.render(size.width, size.height, self.window.scale_factor() as f32);
+        converter::present(&mut self.surface, &canvas)
+    }
+
+    fn render(&self, width: u32, height: u32, scale_factor: f32) -> RgbaImage {
+        let mut canvas = RgbaImage::from_pixel(width, height, BACKGROUND);
+        let scale = PxScale::from(FONT_SIZE * scale_factor);
+        let line_height = (LINE_HEIGHT * scale_factor as f64) as i32;
+        let margin = (MARGIN * scale_factor as f64) as i32;
+        let small = PxScale::from(SMALL_FONT_SIZE * scale_factor);
+        if self.rates.is_empty() {
+            let text = i18n::tr(Msg::NoRatesYet);
+            draw_text_mut(
+                &mut canvas,
+                DIMMED,
+                margin,
+                margin,
+                small,
+                self.font.as_ref(),
+                text,
+            );
+        }
+        for (i, rate) in self.rates.iter().enumerate() {
+            let y = margin + i as i32 * line_height;
+            let unit = if rate.unit == "%" { "%" } else { "" };
+            let value = format!("{}{}", rate.rounding.format(rate.rate), unit);
+            draw_text_mut(
+                &mut canvas,
+                DIMMED,
+                margin,
+                y,
+                scale,
+                self.font.as_ref(),
+                &rate.currency,
+            );
+            // Values are right-aligned so the decimal points line up.
+            let value_width = text::width(self.font.as_ref(), &value, scale);
+            let x = width as i32 - margin - value_width;
+            draw_text_mut(
+                &mut canvas,
+                FOREGROUND,
+                x,
+                y,
+                scale,
+                self.font.as_ref(),
+                &value,
+            );
+            if let Some((low, high)) = rate.day_range.filter(|(low, high)| low < high) {
+                let range = format!(
+                    "{} - {}",
+                    rate.rounding.format(low),
+                    rate.rounding.format(high)
+                );
+                let range_width = text::width(self.font.as_ref(), &range, small);
+                let x = width as i32 - margin - range_width;
+                let y = y + (RANGE_OFFSET * scale_factor as f64) as i32;
+                draw_text_mut(&mut canvas, DIMMED, x, y, small, self.font.as_ref(), &range);
+            }
+        }
+        canvas
+    }
+}
+
+impl Drop for WidgetWindow {
+    fn drop(&mut self) {
+        self.save_position();
+    }
+}
+
+fn size_for(lines: usize) -> LogicalSize<f64> {
+    LogicalSize::new(WIDTH, MARGIN * 2.0 + lines.max(1) as f64 * LINE_HEIGHT)
+}
@@ -0,0 +1,209 @@
+// --- Excel Export ---
+// `bcv-tray export rates.xlsx` writes a workbook with a summary sheet of the
+// current rates and one sheet of history per symbol, through rust_xlsxwriter.
+use chrono::{Duration, Local, NaiveDateTime, Utc};
+use rusqlite::Connection;
+use rust_xlsxwriter::{Format, Worksheet, XlsxError};
+use std::path::Path;
+
+use crate::{config::Config, db};
+
+// Excel caps sheet names at 31 characters.
+const MAX_SHEET_NAME: usize = 31;
+
+pub enum Cell {
+    Text(String),
+    Number(f64),
+    // Local time, shown with Excel's built-in date and time format.
+    DateTime(NaiveDateTime),
+    Empty,
+}
+
+#[derive(Default)]
+pub struct Workbook {
+    sheets: Vec<(String, Vec<Vec<Cell>>)>,
+}
+
+impl Workbook {
+    pub fn add_sheet(&mut self, name: &str, rows: Vec<Vec<Cell>>) {
+        let taken: Vec<&str> = self.sheets.iter().map(|(name, _)| name.as_str()).collect();
+        let name = sheet_name(name, &taken);
+        self.sheets.push((name, rows));
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        self.build()
+            .and_then(|mut workbook| workbook.save(path))
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    fn build(&self) -> Result<rust_xlsxwriter::Workbook, XlsxError> {
+        let mut workbook = rust_xlsxwriter::Workbook::new();
+        // Built-in format 22, "m/d/yy h:mm" in the user's locale.
+        let date_format = Format::new().set_num_format_index(22);
+        for (name, rows) in &self.sheets {
+            let sheet = workbook.add_worksheet();
+            sheet.set_name(name)?;
+            sheet.set_column_width(0, 20)?;
+            sheet.set_column_range_width(1, 7, 14)?;
+            write_rows(sheet, rows, &date_format)?;
+        }
+        Ok(workbook)
+    }
+}
+
+fn write_rows(
+    sheet: &mut Worksheet,
+    rows: &[Vec<Cell>],
+    date_format: &Format,
+) -> Result<(), XlsxError> {
+    for (r, row) in rows.iter().enumerate() {
+        for (c, cell) in row.iter().enumerate() {
+            let (r, c) = (r as u32, c as u16);
+            match cell {
+                Cell::Text(text) => {
+                    sheet.write_string(r, c, text)?;
+                }
+                Cell::Number(value) if value.is_finite() => {
+                    sheet.write_number(r, c, *value)?;
+                }
+                Cell::DateTime(at) => {
+                    sheet.write_datetime_with_format(r, c, at, date_format)?;
+                }
+                Cell::Number(_) | Cell::Empty => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+// Summary of the current rates plus `days` of history for each of them.
+pub fn export(conn: &Connection, config: &Config, path: &Path, days: u32) -> Result<(), String> {
+    let rates =
+        crate::fetch_rates(conn, config).map_err(|e| format!("Failed to read rates: {}", e))?;
+    let mut workbook = Workbook::default();
+    let header = |names: &[&str]| names.iter().map(|n| Cell::Text(n.to_string())).collect();
+    let mut summary = vec![header(&[
+        "Source", "Symbol", "Rate", "Unit", "Updated", "24h %", "7d ago", "30d ago",
+    ])];
+    for rate in &rates {
+        let number = |value: Option<f64>| value.map_or(Cell::Empty, Cell::Number);
+        summary.push(vec![
+            Cell::Text(rate.currency.clone()),
+            Cell::Text(rate.symbol.clone()),
+            number(rate.last_updated.map(|_| rate.rate)),
+            Cell::Text(rate.unit.clone()),
+            rate.last_updated.map_or(Cell::Empty, |at| {
+                Cell::DateTime(at.with_timezone(&Local).naive_local())
+            }),
+            number(rate.day_change),
+            number(rate.week_ago),
+            number(rate.month_ago),
+        ]);
+    }
+    workbook.add_sheet("Summary", summary);
+    let since = Utc::now() - Duration::days(days.into());
+    for rate in &rates {
+        let history = db::history_since(conn, &rate.symbol, since)
+            .map_err(|e| format!("Failed to read history: {}", e))?;
+        let mut rows = vec![header(&["Time", "Rate"])];
+        rows.extend(history.into_iter().map(|(at, value)| {
+            vec![
+                Cell::DateTime(at.with_timezone(&Local).naive_local()),
+                Cell::Number(value),
+            ]
+        }));
+        workbook.add_sheet(&rate.symbol, rows);
+    }
+    workbook.save(path)
+}
+
+// Excel limits: 31 characters, none of []:*?/\, and unique regardless of
+// case. Symbols that only differ past the limit get a "~2", "~3"... suffix.
+fn sheet_name(name: &str, taken: &[&str]) -> String {
+    let clean: String = name
+        .chars()
+        .map(|c| if "[]:*?/\\".contains(c) { '_' } else { c })
+        .collect();
+    let is_taken = |candidate: &str| {
+        taken
+            .iter()
+            .any(|taken| taken.to_lowercase() == candidate.to_lowercase())
+    };
+    let base: String = clean.chars().take(MAX_SHEET_NAME).collect();
+    if !is_taken(&base) {
+        return base;
+    }
+    (2..)
+        .map(|n| {
+            let suffix = format!("~{}", n);
+            let keep = MAX_SHEET_NAME - suffix.chars().count();
+            format!("{}{}", clean.chars().take(keep).collect::<String>(), suffix)
+        })
+        .find(|candidate| !is_taken(candidate))
+        .unwrap_or(base)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use calamine::{open_workbook, Data, Reader, Xlsx};
+    use chrono::NaiveDate;
+
+    #[test]
+    fn sheet_names_are_cleaned_and_unique() {
+        let long = "scrape_a_very_long_symbol_name_for_rates";
+        assert_eq!(sheet_name("usd/ves", &[]), "usd_ves");
+        let first = sheet_name(long, &[]);
+        assert_eq!(first, &long[..31]);
+        let second = sheet_name(&format!("{}_2", long), &[&first]);
+        assert_eq!(second, format!("{}~2", &long[..29]));
+        assert_eq!(sheet_name("SUMMARY", &["Summary"]), "SUMMARY~2");
+    }
+
+    #[test]
+    fn workbook_reads_back() {
+        let at = NaiveDate::from_ymd_opt(2024, 1, 1)
+            .and_then(|d| d.and_hms_opt(12, 0, 0))
+            .unwrap();
+        let long = "scrape_a_very_long_symbol_name_for_rates";
+        let mut workbook = Workbook::default();
+        workbook.add_sheet(
+            "Summary",
+            vec![
+                vec![
+                    Cell::Text("Source".to_string()),
+                    Cell::Text("Rate".to_string()),
+                ],
+                vec![Cell::Text("BCV".to_string()), Cell::Number(36.5)],
+                vec![Cell::Empty, Cell::Number(f64::NAN)],
+            ],
+        );
+        workbook.add_sheet(long, vec![vec![Cell::DateTime(at), Cell::Number(1.25)]]);
+        workbook.add_sheet(&format!("{}_eur", long), vec![]);
+
+        let path =
+            std::env::temp_dir().join(format!("bcv-tray-xlsx-test-{}.xlsx", std::process::id()));
+        workbook.save(&path).unwrap();
+        let mut read: Xlsx<_> = open_workbook(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(
+            read.sheet_names(),
+            ["Summary", &long[..31], &format!("{}~2", &long[..29])]
+        );
+        let summary = read.worksheet_range("Summary").unwrap();
+        assert_eq!(
+            summary.get((0, 0)),
+            Some(&Data::String("Source".to_string()))
+        );
+        assert_eq!(summary.get((1, 1)), Some(&Data::Float(36.5)));
+        assert_eq!(summary.get_size(), (2, 2));
+        let history = read.worksheet_range(&long[..31]).unwrap();
+        match history.get((0, 0)) {
+            Some(Data::DateTime(date)) => assert_eq!(date.as_f64(), 45292.5),
+            other => panic!("expected a date, got {:?}", other),
+        }
+        assert_eq!(history.get((0, 1)), Some(&Data::Float(1.25)));
+    }
+}